@@ -8,11 +8,12 @@ use std::mem;
 
 use crate::bench_util::Char;
 use bioinformatics::string;
+use bioinformatics::string::matcher::{BoyerMoore, Horspool, Kmp, Matcher, ZMatcher};
 use bioinformatics::string::{
-    border_array, bwt, lcs, suffix_trie_compact, suffix_trie_mcc_arena, suffix_trie_mcc_petgraph,
-    suffix_trie_mcc_rc, suffix_trie_ukn,
+    border_array, bwt, lcs, r_index, suffix_trie_compact, suffix_trie_mcc_arena,
+    suffix_trie_mcc_petgraph, suffix_trie_mcc_rc, suffix_trie_ukn,
 };
-use bioinformatics::string_model::{AStr, arb_astring};
+use bioinformatics::string_model::{AStr, AString, arb_astring};
 use bioinformatics::util::print_histogram;
 use proptest::strategy::{Strategy, ValueTree};
 
@@ -86,6 +87,19 @@ fn bench_substr_simple(bencher: &mut Bencher<'_>, s: &AStr<Char>, t: &AStr<Char>
     bencher.iter(|| string::indexes(s, t))
 }
 
+fn bench_lcs_suffix_automaton(bencher: &mut Bencher<'_>, s: &AStr<Char>, t: &AStr<Char>) {
+    bencher.iter(|| lcs::lcs_suffix_automaton(s, t));
+}
+
+fn bench_substr_matcher<M: Matcher<Char>>(
+    bencher: &mut Bencher<'_>,
+    s: &AStr<Char>,
+    t: &AStr<Char>,
+) {
+    let matcher = M::new(t);
+    bencher.iter(|| matcher.find_iter(s).count());
+}
+
 fn bench_substr_suffix_trie(
     bencher: &mut Bencher<'_>,
     trie: &suffix_trie_mcc_arena::SuffixTrie<Char>,
@@ -98,6 +112,24 @@ fn bench_substr_bwt(bencher: &mut Bencher<'_>, bwt: &bwt::BWT<Char>, t: &AStr<Ch
     bencher.iter(|| bwt.indexes_substr(t));
 }
 
+fn bench_substr_r_index(bencher: &mut Bencher<'_>, index: &r_index::RIndex<Char>, t: &AStr<Char>) {
+    bencher.iter(|| index.indexes_substr(t));
+}
+
+/// `len` chars of copies of `unit`, with one char changed in about every tenth copy
+fn repetitive(unit: &AStr<Char>, len: usize) -> AString<Char> {
+    let mut chars = (0..len)
+        .map(|pos| unit[pos % unit.len()])
+        .collect::<Vec<_>>();
+    for pos in (unit.len() / 2..len).step_by(10 * unit.len() + 7) {
+        chars[pos] = match chars[pos] {
+            Char::A => Char::B,
+            Char::B => Char::A,
+        };
+    }
+    AString::from(chars)
+}
+
 fn bench_border_array_simple(bencher: &mut Bencher<'_>, s: &AStr<Char>) {
     bencher.iter(|| {
         border_array::border_array_simple(s);
@@ -222,6 +254,13 @@ fn lcs_benches(criterion: &mut Criterion) {
                 |bencher, (s, t)| bench_lcs_joined_trie(bencher, s, t),
             )
             .throughput(Throughput::Elements(string_length as u64));
+        lcs_benches
+            .bench_with_input(
+                BenchmarkId::new("lcs_suffix_automaton", string_length),
+                &(s.as_str(), t.as_str()),
+                |bencher, (s, t)| bench_lcs_suffix_automaton(bencher, s, t),
+            )
+            .throughput(Throughput::Elements(string_length as u64));
     }
     lcs_benches.finish();
 }
@@ -248,6 +287,34 @@ fn substr_benches(criterion: &mut Criterion) {
                 )
                 .throughput(Throughput::Elements(string_length as u64));
         }
+        substr_benches
+            .bench_with_input(
+                BenchmarkId::new("substr_kmp", string_length),
+                &(s.as_str(), t.as_str()),
+                |bencher, (s, t)| bench_substr_matcher::<Kmp<_>>(bencher, s, t),
+            )
+            .throughput(Throughput::Elements(string_length as u64));
+        substr_benches
+            .bench_with_input(
+                BenchmarkId::new("substr_z", string_length),
+                &(s.as_str(), t.as_str()),
+                |bencher, (s, t)| bench_substr_matcher::<ZMatcher<_>>(bencher, s, t),
+            )
+            .throughput(Throughput::Elements(string_length as u64));
+        substr_benches
+            .bench_with_input(
+                BenchmarkId::new("substr_horspool", string_length),
+                &(s.as_str(), t.as_str()),
+                |bencher, (s, t)| bench_substr_matcher::<Horspool<_>>(bencher, s, t),
+            )
+            .throughput(Throughput::Elements(string_length as u64));
+        substr_benches
+            .bench_with_input(
+                BenchmarkId::new("substr_boyer_moore", string_length),
+                &(s.as_str(), t.as_str()),
+                |bencher, (s, t)| bench_substr_matcher::<BoyerMoore<_>>(bencher, s, t),
+            )
+            .throughput(Throughput::Elements(string_length as u64));
         let bump = Bump::new();
         let trie = suffix_trie_mcc_arena::build_trie_with_allocator(&s, &bump);
         substr_benches
@@ -266,6 +333,37 @@ fn substr_benches(criterion: &mut Criterion) {
             )
             .throughput(Throughput::Elements(string_length as u64));
         print_histogram("bwt suffix offset", &bwt.suffix_offset_hist.borrow());
+        let index = r_index::build_r_index(&s);
+        substr_benches
+            .bench_with_input(
+                BenchmarkId::new("substr_r_index", string_length),
+                &(s.as_str(), t.as_str()),
+                |bencher, (_, t)| bench_substr_r_index(bencher, &index, t),
+            )
+            .throughput(Throughput::Elements(string_length as u64));
+
+        let unit = arb_astring::<Char>((string_length / 20).clamp(10, 1000))
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        let s_repetitive = repetitive(&unit, string_length);
+        let bwt = bwt::build_bwt(&s_repetitive);
+        substr_benches
+            .bench_with_input(
+                BenchmarkId::new("substr_bwt_repetitive", string_length),
+                &(s_repetitive.as_str(), t.as_str()),
+                |bencher, (_, t)| bench_substr_bwt(bencher, &bwt, t),
+            )
+            .throughput(Throughput::Elements(string_length as u64));
+        let index = r_index::build_r_index(&s_repetitive);
+        println!("r-index repetitive runs: {}", index.run_count());
+        substr_benches
+            .bench_with_input(
+                BenchmarkId::new("substr_r_index_repetitive", string_length),
+                &(s_repetitive.as_str(), t.as_str()),
+                |bencher, (_, t)| bench_substr_r_index(bencher, &index, t),
+            )
+            .throughput(Throughput::Elements(string_length as u64));
     }
     substr_benches.finish();
 }
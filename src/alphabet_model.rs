@@ -10,6 +10,8 @@ pub trait CharT: Display + Copy + Eq + PartialEq + 'static {
 
     fn index(self) -> usize;
 
+    fn from_index(index: usize) -> Self;
+
     fn from_char(ch: char) -> Option<Self>;
 
     fn to_char(self) -> char;
@@ -89,6 +91,19 @@ where
         }
     }
 
+    fn from_index(index: usize) -> Self {
+        if SPECIAL_FIRST {
+            match index {
+                0 => WithSpecial::Special,
+                _ => WithSpecial::Char(C::from_index(index - 1)),
+            }
+        } else if index == C::AlphabetSize::to_usize() {
+            WithSpecial::Special
+        } else {
+            WithSpecial::Char(C::from_index(index))
+        }
+    }
+
     fn from_char(ch: char) -> Option<Self> {
         if ch == SPECIAL_CHAR {
             Some(Self::Special)
@@ -143,6 +158,10 @@ macro_rules! enum_char {
                 self as usize
             }
 
+            fn from_index(index: usize) -> Self {
+                Self::all()[index]
+            }
+
             fn from_char(ch: char) -> Option<Self> {
                 let mut buffer = [0u8; 4];
                 match ch.encode_utf8(&mut buffer) as &str {
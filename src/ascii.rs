@@ -13,6 +13,10 @@ impl CharT for ascii::Char {
         self as usize
     }
 
+    fn from_index(index: usize) -> Self {
+        ascii::Char::from_u8(index as u8).expect("ascii index")
+    }
+
     fn from_char(ch: char) -> Option<Self> {
         ch.as_ascii()
     }
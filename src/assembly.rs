@@ -0,0 +1,17 @@
+use crate::polymers::DnaNt;
+use crate::string_model::AString;
+
+pub mod correction;
+pub mod de_bruijn;
+pub mod evaluation;
+pub mod genome;
+pub mod olc;
+pub mod scaffold;
+pub mod simulation;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contig {
+    pub seq: AString<DnaNt>,
+    /// Average number of reads (or k-mers) covering each position of the contig
+    pub coverage: f64,
+}
@@ -0,0 +1,465 @@
+//! Read error correction from the k-mer spectrum. K-mers occurring at least a threshold number
+//! of times in the reads are solid, others are weak and assumed to contain sequencing errors. A
+//! run of weak k-mers in a read is corrected by the substitution that makes all k-mers covering
+//! the substituted position solid
+
+use crate::alphabet_model::CharT;
+use crate::polymers::DnaNt;
+use crate::string::kmer::{KmerCounterProperties, KmerCounts, count_canonical_kmers, count_kmers};
+use crate::string_model::{AStr, AString};
+use crate::util::SequenceEntry;
+use itertools::Itertools;
+use std::fmt::{Display, Formatter};
+use std::ops::{Range, RangeInclusive};
+
+#[derive(Debug, Copy, Clone)]
+pub struct CorrectionProperties {
+    pub k: usize,
+    /// Count k-mers and their reverse complement together, for reads sampled from both strands
+    pub canonical: bool,
+    /// K-mers seen at least this number of times are solid. If `None`, the valley of the k-mer
+    /// spectrum is used
+    pub solid_threshold: Option<usize>,
+    /// Maximum number of substitutions made in a read
+    pub max_corrections: usize,
+    /// Count k-mers in parallel
+    pub parallel: bool,
+}
+
+impl CorrectionProperties {
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    pub fn solid_threshold(mut self, solid_threshold: Option<usize>) -> Self {
+        self.solid_threshold = solid_threshold;
+        self
+    }
+
+    pub fn max_corrections(mut self, max_corrections: usize) -> Self {
+        self.max_corrections = max_corrections;
+        self
+    }
+
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+}
+
+impl Default for CorrectionProperties {
+    fn default() -> Self {
+        Self {
+            k: 21,
+            canonical: true,
+            solid_threshold: None,
+            max_corrections: 4,
+            parallel: false,
+        }
+    }
+}
+
+/// Substitution of the base at a read position
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    pub pos: usize,
+    pub from: DnaNt,
+    pub to: DnaNt,
+}
+
+impl Display for Substitution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}>{}", self.pos, self.from, self.to)
+    }
+}
+
+/// Changes made to a read
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadCorrection {
+    pub corrections: Vec<Substitution>,
+    /// Number of weak k-mers left in the corrected read
+    pub weak_kmers: usize,
+}
+
+impl Display for ReadCorrection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}",
+            self.corrections.iter().join(","),
+            self.weak_kmers
+        )
+    }
+}
+
+/// Candidate substitution with the smallest count of the k-mers covering it, and the quality of
+/// the substituted base
+struct Candidate {
+    substitution: Substitution,
+    min_count: usize,
+    quality: u8,
+}
+
+pub struct ErrorCorrector {
+    counts: KmerCounts<DnaNt>,
+    solid_threshold: usize,
+    max_corrections: usize,
+}
+
+impl ErrorCorrector {
+    /// Counts the k-mers in the reads. If the threshold is not given and the spectrum has no
+    /// valley, all k-mers are solid
+    pub fn new<E: SequenceEntry<DnaNt>>(reads: &[E], props: CorrectionProperties) -> Self {
+        let polymers = reads.iter().map(|read| read.polymer()).collect_vec();
+        let counter_props = KmerCounterProperties::default()
+            .k(props.k)
+            .parallel(props.parallel);
+        let counts = if props.canonical {
+            count_canonical_kmers(&polymers, counter_props)
+        } else {
+            count_kmers(&polymers, counter_props)
+        };
+        let solid_threshold = props
+            .solid_threshold
+            .or_else(|| counts.spectrum().valley())
+            .unwrap_or(1);
+        Self {
+            counts,
+            solid_threshold,
+            max_corrections: props.max_corrections,
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.counts.k()
+    }
+
+    pub fn solid_threshold(&self) -> usize {
+        self.solid_threshold
+    }
+
+    pub fn is_solid(&self, kmer: &AStr<DnaNt>) -> bool {
+        self.counts.count(kmer) >= self.solid_threshold
+    }
+
+    fn solid_kmers(&self, polymer: &AStr<DnaNt>) -> Vec<bool> {
+        (0..=polymer.len() - self.k())
+            .map(|start| self.is_solid(&polymer[start..start + self.k()]))
+            .collect()
+    }
+
+    /// Corrects substitution errors in the read in place. `quality` is used to choose between
+    /// equally well supported substitutions
+    pub fn correct(&self, polymer: &mut AString<DnaNt>, quality: Option<&[u8]>) -> ReadCorrection {
+        let k = self.k();
+        if polymer.len() < k {
+            return ReadCorrection::default();
+        }
+
+        let mut corrections = Vec::new();
+        let mut start = 0;
+        while corrections.len() < self.max_corrections {
+            let solid = self.solid_kmers(polymer);
+            let Some(run_start) = (start..solid.len()).find(|&idx| !solid[idx]) else {
+                break;
+            };
+            let run_end = (run_start..solid.len())
+                .find(|&idx| solid[idx])
+                .unwrap_or(solid.len());
+
+            // a single error is in all the weak k-mers of the run. Otherwise, try all positions
+            // of the run
+            let candidates = if run_end - 1 < run_start + k {
+                run_end - 1..run_start + k
+            } else {
+                run_start..run_end - 1 + k
+            };
+            let all_covering = |pos: usize| pos.saturating_sub(k - 1)..=pos.min(solid.len() - 1);
+            let mut substitutions = self.substitutions(polymer, candidates, all_covering, quality);
+
+            // with several errors close together, no single substitution makes all covering
+            // k-mers solid. Extend the solid k-mers next to the run by one position instead
+            if substitutions.is_empty() && run_start > 0 {
+                let pos = run_start + k - 1;
+                substitutions =
+                    self.substitutions(polymer, pos..pos + 1, |_| run_start..=run_start, quality);
+            } else if substitutions.is_empty() && run_end < solid.len() {
+                let pos = run_end - 1;
+                substitutions = self.substitutions(polymer, pos..pos + 1, |_| pos..=pos, quality);
+            }
+
+            // the best substitution is used unless ambiguous
+            let best = match substitutions.as_slice() {
+                [best, next, ..]
+                    if (best.min_count, best.quality) == (next.min_count, next.quality) =>
+                {
+                    None
+                }
+                [best, ..] => Some(best.substitution),
+                [] => None,
+            };
+            match best {
+                Some(substitution) => {
+                    let chars: &mut [DnaNt] = polymer;
+                    chars[substitution.pos] = substitution.to;
+                    corrections.push(substitution);
+                    start = run_start;
+                }
+                None => start = run_end,
+            }
+        }
+
+        let weak_kmers = self
+            .solid_kmers(polymer)
+            .into_iter()
+            .filter(|&solid| !solid)
+            .count();
+        ReadCorrection {
+            corrections,
+            weak_kmers,
+        }
+    }
+
+    /// Substitutions at the positions making the k-mers starting at `kmer_starts(pos)` solid,
+    /// best first
+    fn substitutions(
+        &self,
+        polymer: &mut AString<DnaNt>,
+        positions: Range<usize>,
+        kmer_starts: impl Fn(usize) -> RangeInclusive<usize>,
+        quality: Option<&[u8]>,
+    ) -> Vec<Candidate> {
+        let k = self.k();
+        let mut candidates = Vec::new();
+        for pos in positions {
+            let from = polymer[pos];
+            let kmer_starts = kmer_starts(pos);
+            for to in (0..4).map(DnaNt::from_index) {
+                if to == from {
+                    continue;
+                }
+                let chars: &mut [DnaNt] = polymer;
+                chars[pos] = to;
+                let min_count = kmer_starts
+                    .clone()
+                    .map(|start| self.counts.count(&polymer[start..start + k]))
+                    .min()
+                    .unwrap();
+                if min_count >= self.solid_threshold {
+                    candidates.push(Candidate {
+                        substitution: Substitution { pos, from, to },
+                        min_count,
+                        quality: quality.map_or(0, |quality| quality[pos]),
+                    });
+                }
+            }
+            let chars: &mut [DnaNt] = polymer;
+            chars[pos] = from;
+        }
+
+        candidates
+            .sort_by_key(|candidate| (std::cmp::Reverse(candidate.min_count), candidate.quality));
+        candidates
+    }
+
+    /// Corrects the polymer of the entry in place
+    pub fn correct_entry<E: SequenceEntry<DnaNt>>(&self, entry: &mut E) -> ReadCorrection {
+        let quality = entry.quality().map(|quality| quality.to_vec());
+        self.correct(entry.polymer_mut(), quality.as_deref())
+    }
+
+    /// Corrects a stream of entries, yielding each corrected entry with the changes made to it
+    pub fn correct_entries<'a, E: SequenceEntry<DnaNt> + 'a>(
+        &'a self,
+        entries: impl IntoIterator<Item = E> + 'a,
+    ) -> impl Iterator<Item = (E, ReadCorrection)> + 'a {
+        entries.into_iter().map(move |mut entry| {
+            let correction = self.correct_entry(&mut entry);
+            (entry, correction)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembly::simulation::{ErrorProfile, ReadSimulatorProperties, simulate_reads};
+    use crate::polymers::{Strand, reverse_complement};
+    use crate::string_model::test_util::random_dna;
+    use crate::util::FastaEntry;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn dna(s: &str) -> AString<DnaNt> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_correct() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let genome = random_dna(&mut rng, 200);
+        let reads = (0..5)
+            .map(|idx| FastaEntry {
+                description: format!("read{}", idx),
+                polymer: genome[idx * 10..idx * 10 + 100].to_owned(),
+            })
+            .collect_vec();
+        let props = CorrectionProperties::default()
+            .k(11)
+            .solid_threshold(Some(2));
+        let corrector = ErrorCorrector::new(&reads, props);
+        assert_eq!(corrector.solid_threshold(), 2);
+
+        // interior error, and errors at the ends
+        for pos in [50, 0, 99] {
+            let mut read = genome[20..120].to_owned();
+            let from = read[pos];
+            let to = DnaNt::from_index((from.index() + 1) % 4);
+            let chars: &mut [DnaNt] = &mut read;
+            chars[pos] = to;
+            let correction = corrector.correct(&mut read, None);
+            assert_eq!(read, genome[20..120].to_owned(), "{}", pos);
+            assert_eq!(
+                correction,
+                ReadCorrection {
+                    corrections: vec![Substitution {
+                        pos,
+                        from: to,
+                        to: from
+                    }],
+                    weak_kmers: 0
+                }
+            );
+        }
+
+        // solid read is not changed
+        let mut read = genome[30..130].to_owned();
+        assert_eq!(
+            corrector.correct(&mut read, None),
+            ReadCorrection::default()
+        );
+
+        // k-mers not in the reads can't be corrected
+        let mut read = genome[100..200].to_owned();
+        let correction = corrector.correct(&mut read, None);
+        assert!(correction.corrections.is_empty());
+        assert_eq!(correction.weak_kmers, 70);
+        assert_eq!(read, genome[100..200].to_owned());
+
+        // too short for k-mers
+        let mut read = dna("ACGT");
+        assert_eq!(
+            corrector.correct(&mut read, None),
+            ReadCorrection::default()
+        );
+    }
+
+    #[test]
+    fn test_correct_quality() {
+        // substituting either of the last two bases makes the read solid
+        let reads = [
+            FastaEntry {
+                description: "read1".to_string(),
+                polymer: dna("ACGTTGCAAC"),
+            },
+            FastaEntry {
+                description: "read2".to_string(),
+                polymer: dna("ACGTTGCAGA"),
+            },
+        ];
+        let props = CorrectionProperties::default()
+            .k(9)
+            .canonical(false)
+            .solid_threshold(Some(1));
+        let corrector = ErrorCorrector::new(&reads, props);
+
+        let mut read = dna("ACGTTGCAGC");
+        let correction = corrector.correct(&mut read, None);
+        assert!(correction.corrections.is_empty());
+        assert_eq!(correction.weak_kmers, 1);
+
+        let mut read = dna("ACGTTGCAGC");
+        let correction =
+            corrector.correct(&mut read, Some(&[40, 40, 40, 40, 40, 40, 40, 40, 40, 10]));
+        assert_eq!(
+            correction.corrections,
+            vec![Substitution {
+                pos: 9,
+                from: DnaNt::C,
+                to: DnaNt::A
+            }]
+        );
+        assert_eq!(read, dna("ACGTTGCAGA"));
+
+        let mut read = dna("ACGTTGCAGC");
+        let correction =
+            corrector.correct(&mut read, Some(&[40, 40, 40, 40, 40, 40, 40, 40, 10, 40]));
+        assert_eq!(
+            correction.corrections,
+            vec![Substitution {
+                pos: 8,
+                from: DnaNt::G,
+                to: DnaNt::A
+            }]
+        );
+        assert_eq!(read, dna("ACGTTGCAAC"));
+        assert_eq!(correction.to_string(), "8G>A\t0");
+    }
+
+    #[test]
+    fn test_correct_simulated_reads() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let genome = random_dna(&mut rng, 20000);
+        let props = ReadSimulatorProperties::default()
+            .read_len(100)
+            .depth(40.0)
+            .errors(ErrorProfile::default().substitution_rate(0.005))
+            .seed(Some(3));
+        let reads = simulate_reads(&genome, props);
+        let entries = reads.iter().map(|read| read.entry.clone()).collect_vec();
+
+        let corrector = ErrorCorrector::new(&entries, CorrectionProperties::default());
+        assert!((2..10).contains(&corrector.solid_threshold()));
+
+        let expected = |idx: usize| {
+            let origin = &reads[idx].origin;
+            let seq = genome[origin.span.clone()].to_owned();
+            match origin.strand {
+                Strand::Forward => seq,
+                Strand::Reverse => reverse_complement(&seq),
+            }
+        };
+        let errors_before = entries
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| entry.polymer != expected(*idx))
+            .count();
+        let mut errors_after = 0;
+        let mut corrections = 0;
+        for (idx, (entry, correction)) in corrector.correct_entries(entries).enumerate() {
+            corrections += correction.corrections.len();
+            if entry.polymer != expected(idx) {
+                errors_after += 1;
+            }
+        }
+        assert!(errors_before > 500, "{}", errors_before);
+        assert!(
+            errors_after * 20 < errors_before,
+            "{} {}",
+            errors_before,
+            errors_after
+        );
+        assert!(
+            corrections >= errors_before,
+            "{} {}",
+            corrections,
+            errors_before
+        );
+    }
+}
@@ -0,0 +1,486 @@
+//! De Bruijn graph assembly. The graph has a node for each unitig (maximal non-branching path
+//! of k-mers) and an edge between unitigs overlapping in k - 1 chars
+
+use crate::assembly::Contig;
+use crate::polymers::{DnaNt, reverse_complement};
+use crate::string::kmer::{KmerCounterProperties, count_canonical_kmers, count_kmers};
+use crate::string_model::{AStr, AString};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use petgraph::Direction;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+#[derive(Debug, Copy, Clone)]
+pub struct DeBruijnProperties {
+    /// At least 2, and at most 32 for k-mers packed in 64 bits
+    pub k: usize,
+    /// Count k-mers and their reverse complement together, for reads sampled from both strands
+    pub canonical: bool,
+    /// K-mers seen fewer times are discarded as errors
+    pub min_kmer_count: usize,
+    /// Dead end unitigs of at most this number of k-mers are clipped
+    pub max_tip_len: usize,
+    /// Bubble paths of at most this number of k-mers are popped
+    pub max_bubble_len: usize,
+    /// Contigs shorter than this are not emitted
+    pub min_contig_len: usize,
+}
+
+impl DeBruijnProperties {
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    pub fn min_kmer_count(mut self, min_kmer_count: usize) -> Self {
+        self.min_kmer_count = min_kmer_count;
+        self
+    }
+
+    pub fn max_tip_len(mut self, max_tip_len: usize) -> Self {
+        self.max_tip_len = max_tip_len;
+        self
+    }
+
+    pub fn max_bubble_len(mut self, max_bubble_len: usize) -> Self {
+        self.max_bubble_len = max_bubble_len;
+        self
+    }
+
+    pub fn min_contig_len(mut self, min_contig_len: usize) -> Self {
+        self.min_contig_len = min_contig_len;
+        self
+    }
+}
+
+impl Default for DeBruijnProperties {
+    fn default() -> Self {
+        Self {
+            k: 31,
+            canonical: true,
+            min_kmer_count: 2,
+            max_tip_len: 62,
+            max_bubble_len: 62,
+            min_contig_len: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Unitig {
+    seq: AString<DnaNt>,
+    /// Sum of counts of the k-mers in the unitig
+    kmer_count_sum: usize,
+}
+
+impl Unitig {
+    fn kmers(&self, k: usize) -> usize {
+        self.seq.len() + 1 - k
+    }
+
+    fn coverage(&self, k: usize) -> f64 {
+        self.kmer_count_sum as f64 / self.kmers(k) as f64
+    }
+}
+
+pub struct DeBruijnGraph {
+    k: usize,
+    canonical: bool,
+    graph: StableDiGraph<Unitig, ()>,
+}
+
+/// Builds compacted de Bruijn graph of the k-mers in the reads
+pub fn build_graph<'a>(
+    reads: impl IntoIterator<Item = &'a AStr<DnaNt>>,
+    props: DeBruijnProperties,
+) -> DeBruijnGraph {
+    let k = props.k;
+    assert!(k >= 2, "k must be at least 2");
+
+    let reads = reads.into_iter().collect_vec();
+    let counter_props = KmerCounterProperties::default().k(k);
+    let kmer_counts = if props.canonical {
+        count_canonical_kmers(&reads, counter_props)
+    } else {
+        count_kmers(&reads, counter_props)
+    };
+    let mut counts: HashMap<AString<DnaNt>, usize> = kmer_counts
+        .iter()
+        .filter(|(_, count)| *count >= props.min_kmer_count)
+        .collect();
+    if props.canonical {
+        let reverse_complements = counts
+            .iter()
+            .map(|(kmer, count)| (reverse_complement(kmer), *count))
+            .collect_vec();
+        counts.extend(reverse_complements);
+    }
+
+    let mut graph = StableDiGraph::with_capacity(counts.len(), counts.len());
+    let node_idxs: HashMap<AString<DnaNt>, NodeIndex> = counts
+        .iter()
+        .map(|(kmer, &count)| {
+            let node_idx = graph.add_node(Unitig {
+                seq: kmer.clone(),
+                kmer_count_sum: count,
+            });
+            (kmer.clone(), node_idx)
+        })
+        .collect();
+
+    for (kmer, &node_idx) in &node_idxs {
+        for &ch in DnaNt::all() {
+            let mut succ = kmer[1..].to_owned();
+            succ.push(ch);
+            if let Some(&succ_idx) = node_idxs.get(succ.as_str()) {
+                graph.add_edge(node_idx, succ_idx, ());
+            }
+        }
+    }
+
+    let mut de_bruijn = DeBruijnGraph {
+        k,
+        canonical: props.canonical,
+        graph,
+    };
+    de_bruijn.compact();
+    de_bruijn
+}
+
+/// Assembles reads into contigs, sorted by decreasing length
+pub fn assemble<'a>(
+    reads: impl IntoIterator<Item = &'a AStr<DnaNt>>,
+    props: DeBruijnProperties,
+) -> Vec<Contig> {
+    let mut graph = build_graph(reads, props);
+
+    loop {
+        let removed = graph.clip_tips(props.max_tip_len) + graph.pop_bubbles(props.max_bubble_len);
+        if removed == 0 {
+            break;
+        }
+        graph.compact();
+    }
+
+    graph
+        .contigs()
+        .into_iter()
+        .filter(|contig| contig.seq.len() >= props.min_contig_len)
+        .sorted_by(|contig1, contig2| {
+            contig2
+                .seq
+                .len()
+                .cmp(&contig1.seq.len())
+                .then_with(|| contig1.seq.as_str().cmp(contig2.seq.as_str()))
+        })
+        .collect()
+}
+
+impl DeBruijnGraph {
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn degree(&self, node_idx: NodeIndex, dir: Direction) -> usize {
+        self.graph.neighbors_directed(node_idx, dir).count()
+    }
+
+    fn single_neighbor(&self, node_idx: NodeIndex, dir: Direction) -> Option<NodeIndex> {
+        self.graph
+            .neighbors_directed(node_idx, dir)
+            .exactly_one()
+            .ok()
+    }
+
+    /// Whether `node_idx` is the only successor of its single predecessor
+    fn has_mergeable_predecessor(&self, node_idx: NodeIndex) -> bool {
+        self.single_neighbor(node_idx, Direction::Incoming)
+            .is_some_and(|pred| pred != node_idx && self.degree(pred, Direction::Outgoing) == 1)
+    }
+
+    /// Merges all non-branching paths into single unitigs
+    pub fn compact(&mut self) {
+        for node_idx in self.graph.node_indices().collect_vec() {
+            if self.graph.contains_node(node_idx) && !self.has_mergeable_predecessor(node_idx) {
+                let path = self.non_branching_path(node_idx);
+                self.merge_path(&path);
+            }
+        }
+
+        // remaining mergeable nodes are on isolated cycles
+        for node_idx in self.graph.node_indices().collect_vec() {
+            if self.graph.contains_node(node_idx) && self.has_mergeable_predecessor(node_idx) {
+                let path = self.non_branching_path(node_idx);
+                self.merge_path(&path);
+            }
+        }
+    }
+
+    fn non_branching_path(&self, start: NodeIndex) -> Vec<NodeIndex> {
+        let mut path = vec![start];
+        let mut node_idx = start;
+        while let Some(succ) = self.single_neighbor(node_idx, Direction::Outgoing) {
+            if succ == start || self.degree(succ, Direction::Incoming) != 1 {
+                break;
+            }
+            path.push(succ);
+            node_idx = succ;
+        }
+        path
+    }
+
+    fn merge_path(&mut self, path: &[NodeIndex]) {
+        let [first, .., last] = *path else {
+            return;
+        };
+
+        let mut merged = self.graph[first].clone();
+        for &node_idx in &path[1..] {
+            let unitig = &self.graph[node_idx];
+            merged.seq.push_str(&unitig.seq[self.k - 1..]);
+            merged.kmer_count_sum += unitig.kmer_count_sum;
+        }
+
+        let preds = self
+            .graph
+            .neighbors_directed(first, Direction::Incoming)
+            .collect_vec();
+        let succs = self
+            .graph
+            .neighbors_directed(last, Direction::Outgoing)
+            .collect_vec();
+
+        let merged_idx = self.graph.add_node(merged);
+        for pred in preds {
+            // edge from end to start of a cycle becomes a loop
+            let pred = if pred == last { merged_idx } else { pred };
+            self.graph.add_edge(pred, merged_idx, ());
+        }
+        for succ in succs {
+            if succ != first {
+                self.graph.add_edge(merged_idx, succ, ());
+            }
+        }
+
+        for &node_idx in path {
+            self.graph.remove_node(node_idx);
+        }
+    }
+
+    /// Removes short dead end unitigs branching off from other unitigs. Returns the number of
+    /// unitigs removed
+    pub fn clip_tips(&mut self, max_tip_len: usize) -> usize {
+        // tips grouped by the junction they attach to and the direction of the junction
+        let mut tips: HashMap<(NodeIndex, Direction), Vec<NodeIndex>> = HashMap::new();
+        for node_idx in self.graph.node_indices() {
+            if self.graph[node_idx].kmers(self.k) > max_tip_len {
+                continue;
+            }
+            for (dead_end, attached) in [
+                (Direction::Incoming, Direction::Outgoing),
+                (Direction::Outgoing, Direction::Incoming),
+            ] {
+                if self.degree(node_idx, dead_end) == 0
+                    && let Some(junction) = self.single_neighbor(node_idx, attached)
+                    && junction != node_idx
+                    && self.degree(junction, dead_end) >= 2
+                {
+                    tips.entry((junction, dead_end)).or_default().push(node_idx);
+                }
+            }
+        }
+
+        let mut removed = Vec::new();
+        for ((junction, dir), mut group) in tips {
+            // if all branches are tips, the best supported one is kept
+            if group.len() == self.degree(junction, dir) {
+                let best = group
+                    .iter()
+                    .copied()
+                    .max_by(|&tip1, &tip2| {
+                        let unitig1 = &self.graph[tip1];
+                        let unitig2 = &self.graph[tip2];
+                        unitig1
+                            .kmer_count_sum
+                            .cmp(&unitig2.kmer_count_sum)
+                            .then_with(|| unitig2.seq.as_str().cmp(unitig1.seq.as_str()))
+                    })
+                    .unwrap();
+                group.retain(|&node_idx| node_idx != best);
+            }
+            removed.extend(group);
+        }
+
+        self.remove_nodes(removed)
+    }
+
+    /// Removes the lower coverage paths of simple bubbles: unitigs with the same single
+    /// predecessor and single successor. Returns the number of unitigs removed
+    pub fn pop_bubbles(&mut self, max_bubble_len: usize) -> usize {
+        let mut removed = Vec::new();
+        for node_idx in self.graph.node_indices() {
+            if self.degree(node_idx, Direction::Outgoing) < 2 {
+                continue;
+            }
+
+            let bubble_paths = self
+                .graph
+                .neighbors_directed(node_idx, Direction::Outgoing)
+                .filter(|&path_idx| {
+                    path_idx != node_idx
+                        && self.degree(path_idx, Direction::Incoming) == 1
+                        && self.graph[path_idx].kmers(self.k) <= max_bubble_len
+                })
+                .filter_map(|path_idx| {
+                    self.single_neighbor(path_idx, Direction::Outgoing)
+                        .filter(|&end| end != path_idx)
+                        .map(|end| (end, path_idx))
+                })
+                .into_group_map();
+
+            for (_, paths) in bubble_paths {
+                let best = paths
+                    .iter()
+                    .copied()
+                    .max_by(|&path1, &path2| {
+                        let unitig1 = &self.graph[path1];
+                        let unitig2 = &self.graph[path2];
+                        unitig1
+                            .coverage(self.k)
+                            .total_cmp(&unitig2.coverage(self.k))
+                            .then_with(|| unitig2.seq.as_str().cmp(unitig1.seq.as_str()))
+                    })
+                    .unwrap();
+                removed.extend(paths.into_iter().filter(|&path_idx| path_idx != best));
+            }
+        }
+
+        self.remove_nodes(removed)
+    }
+
+    fn remove_nodes(&mut self, node_idxs: Vec<NodeIndex>) -> usize {
+        let mut count = 0;
+        for node_idx in node_idxs {
+            if self.graph.remove_node(node_idx).is_some() {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Contigs for all unitigs. With canonical k-mers, only one of a contig and its reverse
+    /// complement is returned
+    pub fn contigs(&self) -> Vec<Contig> {
+        let mut contigs: HashMap<AString<DnaNt>, f64> = HashMap::new();
+        for unitig in self.graph.node_weights() {
+            let seq = if self.canonical {
+                let seq_rc = reverse_complement(&unitig.seq);
+                seq_rc.as_str().min(unitig.seq.as_str()).to_owned()
+            } else {
+                unitig.seq.clone()
+            };
+            contigs.insert(seq, unitig.coverage(self.k));
+        }
+
+        contigs
+            .into_iter()
+            .map(|(seq, coverage)| Contig { seq, coverage })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::alphabet_model::CharT;
+    use crate::string_model::test_util::{random_dna, tiled_reads};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn props() -> DeBruijnProperties {
+        DeBruijnProperties::default().k(15).min_kmer_count(1)
+    }
+
+    #[test]
+    fn test_assemble_forward_reads() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let genome = random_dna(&mut rng, 1000);
+        let reads = tiled_reads(&genome, 50, 20);
+
+        let contigs = assemble(
+            reads.iter().map(|read| read.as_str()),
+            props().canonical(false),
+        );
+        assert_eq!(contigs.len(), 1);
+        assert_eq!(contigs[0].seq, genome);
+        assert!(contigs[0].coverage > 1.0);
+    }
+
+    #[test]
+    fn test_assemble_reads_both_strands() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let genome = random_dna(&mut rng, 1000);
+        let reads = tiled_reads(&genome, 50, 20)
+            .into_iter()
+            .enumerate()
+            .map(|(i, read)| {
+                if i % 2 == 0 {
+                    read
+                } else {
+                    reverse_complement(&read)
+                }
+            })
+            .collect_vec();
+
+        let contigs = assemble(reads.iter().map(|read| read.as_str()), props());
+        assert_eq!(contigs.len(), 1);
+        assert!(contigs[0].seq == genome || contigs[0].seq == reverse_complement(&genome));
+    }
+
+    #[test]
+    fn test_assemble_clip_tips_and_pop_bubbles() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let genome = random_dna(&mut rng, 1000);
+        let mut reads = tiled_reads(&genome, 50, 5);
+
+        // error in the middle of a read gives a bubble, near the end a tip
+        for (read_idx, pos) in [(20, 25), (60, 48), (100, 2)] {
+            let mut read = reads[read_idx].to_vec();
+            read[pos] = DnaNt::from_index((read[pos].index() + 1) % 4);
+            reads[read_idx] = AString::from(read);
+        }
+
+        let graph = build_graph(
+            reads.iter().map(|read| read.as_str()),
+            props().canonical(false),
+        );
+        assert!(graph.node_count() > 1);
+
+        let contigs = assemble(
+            reads.iter().map(|read| read.as_str()),
+            props().canonical(false),
+        );
+        assert_eq!(contigs.len(), 1);
+        assert_eq!(contigs[0].seq, genome);
+    }
+
+    #[test]
+    fn test_assemble_circular_genome() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let genome = random_dna(&mut rng, 500);
+        let circular = genome.clone() + &genome[..50];
+        let reads = tiled_reads(&circular, 50, 20);
+
+        let contigs = assemble(
+            reads.iter().map(|read| read.as_str()),
+            props().canonical(false),
+        );
+        assert_eq!(contigs.len(), 1);
+        assert_eq!(contigs[0].seq.len(), genome.len() + props().k - 1);
+    }
+}
@@ -0,0 +1,825 @@
+//! Assembly quality evaluation in the style of QUAST. Contiguity statistics are computed from
+//! the contig lengths. When a reference is given, contigs are aligned to it by chaining maximal
+//! exact matches found with a BWT of the reference into colinear blocks, and adjacent blocks of
+//! a contig that are inconsistent on the reference are reported as misassemblies
+
+use crate::polymers::{DnaNt, Strand, reverse_complement};
+use crate::string::alignment::global_alignment_wagner_fischer::global_alignment_with_buffer;
+use crate::string::alignment::{AlignmentBuffer, AlignmentProperties, Edit};
+use crate::string::bwt::{BWT, build_bwt};
+use crate::string_model::{AStr, AString};
+use itertools::Itertools;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::ops::Range;
+
+#[derive(Debug, Copy, Clone)]
+pub struct EvaluationProperties {
+    /// Contigs shorter than this are not evaluated
+    pub min_contig_len: usize,
+    /// Length of the k-mers looked up in the reference to find exact matches
+    pub k: usize,
+    /// K-mers occurring more often in the reference are ignored
+    pub max_seed_occurrences: usize,
+    /// Exact matches on diagonals at most this far apart are chained into one block
+    pub max_indel_len: usize,
+    /// Aligned blocks shorter than this are discarded
+    pub min_block_len: usize,
+    /// Adjacent blocks of a contig whose distance on the reference differs from their distance
+    /// on the contig by more than this are a relocation. Exact matches further apart are not
+    /// chained
+    pub relocation_threshold: usize,
+    pub alignment: AlignmentProperties,
+}
+
+impl EvaluationProperties {
+    pub fn min_contig_len(mut self, min_contig_len: usize) -> Self {
+        self.min_contig_len = min_contig_len;
+        self
+    }
+
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn max_seed_occurrences(mut self, max_seed_occurrences: usize) -> Self {
+        self.max_seed_occurrences = max_seed_occurrences;
+        self
+    }
+
+    pub fn max_indel_len(mut self, max_indel_len: usize) -> Self {
+        self.max_indel_len = max_indel_len;
+        self
+    }
+
+    pub fn min_block_len(mut self, min_block_len: usize) -> Self {
+        self.min_block_len = min_block_len;
+        self
+    }
+
+    pub fn relocation_threshold(mut self, relocation_threshold: usize) -> Self {
+        self.relocation_threshold = relocation_threshold;
+        self
+    }
+
+    pub fn alignment(mut self, alignment: AlignmentProperties) -> Self {
+        self.alignment = alignment;
+        self
+    }
+}
+
+impl Default for EvaluationProperties {
+    fn default() -> Self {
+        Self {
+            min_contig_len: 0,
+            k: 21,
+            max_seed_occurrences: 20,
+            max_indel_len: 50,
+            min_block_len: 65,
+            relocation_threshold: 1000,
+            alignment: AlignmentProperties::default(),
+        }
+    }
+}
+
+/// Colinear part of a contig aligned to the reference
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AlignedBlock {
+    pub strand: Strand,
+    /// Aligned range of the contig, on the forward strand of the contig
+    pub contig_range: Range<usize>,
+    pub ref_range: Range<usize>,
+    pub mismatches: usize,
+    /// Number of inserted and deleted chars
+    pub indels: usize,
+}
+
+/// Inconsistency between adjacent aligned blocks of a contig
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Breakpoint {
+    /// Blocks are more than the relocation threshold out of place on the reference
+    Relocation,
+    /// Blocks are on different strands of the reference
+    Inversion,
+    /// Blocks are out of place by more than an indel but less than a relocation
+    Local,
+}
+
+/// Maximal exact match between contig and reference
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+struct Match {
+    contig_pos: usize,
+    ref_pos: usize,
+    len: usize,
+}
+
+impl Match {
+    fn diagonal(&self) -> isize {
+        self.ref_pos as isize - self.contig_pos as isize
+    }
+
+    fn contig_end(&self) -> usize {
+        self.contig_pos + self.len
+    }
+
+    fn ref_end(&self) -> usize {
+        self.ref_pos + self.len
+    }
+}
+
+/// Aligns contigs to a reference indexed by a BWT
+pub struct ContigAligner {
+    reference: AString<DnaNt>,
+    bwt: BWT<DnaNt>,
+    props: EvaluationProperties,
+}
+
+impl ContigAligner {
+    pub fn new(reference: AString<DnaNt>, props: EvaluationProperties) -> Self {
+        let bwt = build_bwt(&reference);
+        Self {
+            reference,
+            bwt,
+            props,
+        }
+    }
+
+    pub fn reference(&self) -> &AStr<DnaNt> {
+        &self.reference
+    }
+
+    /// Non-overlapping aligned blocks of the contig on either strand, ordered by position in
+    /// the contig. Blocks are chosen greedily by number of bases in exact matches
+    pub fn align(&self, contig: &AStr<DnaNt>) -> Vec<AlignedBlock> {
+        let contig_rc = reverse_complement(contig);
+        let strands = [(Strand::Forward, contig), (Strand::Reverse, &*contig_rc)];
+
+        let mut chain_ends = Vec::new();
+        let mut strand_chains = Vec::new();
+        for (strand_idx, &(_, seq)) in strands.iter().enumerate() {
+            let matches = self.matches(seq);
+            let chains = self.chains(&matches);
+            chain_ends.extend(
+                chains
+                    .iter()
+                    .enumerate()
+                    .map(|(end, &(score, _))| (score, strand_idx, end)),
+            );
+            strand_chains.push((matches, chains));
+        }
+        chain_ends.sort_by(|end1, end2| end2.cmp(end1));
+
+        let mut used: Vec<Vec<bool>> = strand_chains
+            .iter()
+            .map(|(matches, _)| vec![false; matches.len()])
+            .collect();
+        let mut buffer = AlignmentBuffer::default();
+        let mut blocks: Vec<AlignedBlock> = Vec::new();
+        for (_, strand_idx, end) in chain_ends {
+            let (matches, chains) = &strand_chains[strand_idx];
+            let mut chain = vec![end];
+            while let Some(prev) = chains[*chain.last().unwrap()].1 {
+                chain.push(prev);
+            }
+            if chain.iter().any(|&idx| used[strand_idx][idx]) {
+                continue;
+            }
+            for &idx in &chain {
+                used[strand_idx][idx] = true;
+            }
+            let chain = chain.iter().rev().map(|&idx| matches[idx]).collect_vec();
+
+            let (strand, seq) = strands[strand_idx];
+            let start = chain[0].contig_pos;
+            let end = chain.last().unwrap().contig_end();
+            let contig_range = match strand {
+                Strand::Forward => start..end,
+                Strand::Reverse => contig.len() - end..contig.len() - start,
+            };
+            let overlap: usize = blocks
+                .iter()
+                .map(|block| {
+                    block
+                        .contig_range
+                        .end
+                        .min(contig_range.end)
+                        .saturating_sub(block.contig_range.start.max(contig_range.start))
+                })
+                .sum();
+            if contig_range.len() < self.props.min_block_len || 2 * overlap > contig_range.len() {
+                continue;
+            }
+
+            blocks.push(self.block(seq, &chain, strand, contig_range, &mut buffer));
+        }
+
+        blocks.sort_by_key(|block| block.contig_range.start);
+        blocks
+    }
+
+    /// Maximal exact matches of the contig containing a k-mer occurring in the reference at
+    /// most `max_seed_occurrences` times. K-mers are looked up every `k / 2` positions, and
+    /// after the end of the matches found
+    fn matches(&self, contig: &AStr<DnaNt>) -> Vec<Match> {
+        let k = self.props.k;
+        let step = (k / 2).max(1);
+        let mut matches = Vec::new();
+        let mut pos = 0;
+        while pos + k <= contig.len() {
+            let range = self.bwt.suffix_range(&contig[pos..pos + k]);
+            let mut next_pos = pos + step;
+            if !range.is_empty() && range.len() <= self.props.max_seed_occurrences {
+                let found = range
+                    .map(|idx| self.extend_match(contig, pos, self.bwt.locate(idx), k))
+                    .collect_vec();
+                let min_end = found.iter().map(Match::contig_end).min().unwrap();
+                next_pos = next_pos.max(min_end + 1);
+                matches.extend(found);
+            }
+            pos = next_pos;
+        }
+
+        matches.sort();
+        matches.dedup();
+        matches
+    }
+
+    fn extend_match(
+        &self,
+        contig: &AStr<DnaNt>,
+        contig_pos: usize,
+        ref_pos: usize,
+        len: usize,
+    ) -> Match {
+        let before = contig[..contig_pos]
+            .iter()
+            .rev()
+            .zip(self.reference[..ref_pos].iter().rev())
+            .take_while(|(contig_nt, ref_nt)| contig_nt == ref_nt)
+            .count();
+        let after = contig[contig_pos + len..]
+            .iter()
+            .zip(self.reference[ref_pos + len..].iter())
+            .take_while(|(contig_nt, ref_nt)| contig_nt == ref_nt)
+            .count();
+        Match {
+            contig_pos: contig_pos - before,
+            ref_pos: ref_pos - before,
+            len: before + len + after,
+        }
+    }
+
+    /// Score (bases in exact matches) and predecessor of best chain ending in each match.
+    /// Matches must be sorted by position in the contig
+    fn chains(&self, matches: &[Match]) -> Vec<(usize, Option<usize>)> {
+        let max_indel_len = self.props.max_indel_len as isize;
+        let mut best: Vec<(usize, Option<usize>)> = Vec::with_capacity(matches.len());
+        for (i, m) in matches.iter().enumerate() {
+            let mut entry = (m.len, None);
+            for (j, prev) in matches[..i].iter().enumerate() {
+                if prev.contig_pos < m.contig_pos
+                    && prev.ref_pos < m.ref_pos
+                    && prev.contig_end() < m.contig_end()
+                    && prev.ref_end() < m.ref_end()
+                    && m.contig_pos <= prev.contig_end() + self.props.relocation_threshold
+                    && (m.diagonal() - prev.diagonal()).abs() <= max_indel_len
+                {
+                    let added = (m.contig_end() - prev.contig_end()).min(m.len);
+                    if best[j].0 + added > entry.0 {
+                        entry = (best[j].0 + added, Some(j));
+                    }
+                }
+            }
+            best.push(entry);
+        }
+        best
+    }
+
+    /// Block spanned by a chain of matches, with the gaps between matches aligned
+    fn block(
+        &self,
+        contig: &AStr<DnaNt>,
+        chain: &[Match],
+        strand: Strand,
+        contig_range: Range<usize>,
+        buffer: &mut AlignmentBuffer,
+    ) -> AlignedBlock {
+        let mut mismatches = 0;
+        let mut indels = 0;
+        for (prev, m) in chain.iter().tuple_windows() {
+            // matches on different diagonals may overlap, and then the overlap is aligned
+            let overlap = (prev.contig_end().saturating_sub(m.contig_pos))
+                .max(prev.ref_end().saturating_sub(m.ref_pos));
+            let align = global_alignment_with_buffer(
+                &self.reference[prev.ref_end()..m.ref_pos + overlap],
+                &contig[prev.contig_end()..m.contig_pos + overlap],
+                self.props.alignment,
+                buffer,
+            );
+            for edit in align.edits.iter() {
+                match edit {
+                    Edit::Match => {}
+                    Edit::Mismatch => mismatches += 1,
+                    Edit::Insert | Edit::Delete => indels += 1,
+                }
+            }
+        }
+
+        AlignedBlock {
+            strand,
+            contig_range,
+            ref_range: chain[0].ref_pos..chain.last().unwrap().ref_end(),
+            mismatches,
+            indels,
+        }
+    }
+
+    /// Inconsistency between blocks adjacent in a contig, if any
+    pub fn breakpoint(&self, prev: &AlignedBlock, next: &AlignedBlock) -> Option<Breakpoint> {
+        if prev.strand != next.strand {
+            return Some(Breakpoint::Inversion);
+        }
+        let contig_gap = next.contig_range.start as isize - prev.contig_range.end as isize;
+        let ref_gap = match prev.strand {
+            Strand::Forward => next.ref_range.start as isize - prev.ref_range.end as isize,
+            Strand::Reverse => prev.ref_range.start as isize - next.ref_range.end as isize,
+        };
+        let distance = ref_gap.abs_diff(contig_gap);
+        if distance > self.props.relocation_threshold {
+            Some(Breakpoint::Relocation)
+        } else if distance > self.props.max_indel_len {
+            Some(Breakpoint::Local)
+        } else {
+            None
+        }
+    }
+}
+
+/// Nx and Lx: the length of the shortest contig, and the number of contigs, such that contigs
+/// at least this long add up to `fraction` of `total_len`. `None` if all contigs add up to less
+pub fn nx(lengths: &[usize], total_len: usize, fraction: f64) -> Option<(usize, usize)> {
+    let mut sum = 0;
+    for (idx, len) in lengths
+        .iter()
+        .sorted_by(|len1, len2| len2.cmp(len1))
+        .enumerate()
+    {
+        sum += len;
+        if sum as f64 >= fraction * total_len as f64 {
+            return Some((*len, idx + 1));
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContiguityStats {
+    pub num_contigs: usize,
+    pub total_len: usize,
+    pub largest_contig: usize,
+    pub n50: usize,
+    pub l50: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceStats {
+    pub reference_len: usize,
+    pub ng50: Option<usize>,
+    pub lg50: Option<usize>,
+    /// Percentage of reference bases covered by aligned blocks
+    pub genome_fraction: f64,
+    /// Aligned contig bases per covered reference base
+    pub duplication_ratio: f64,
+    pub relocations: usize,
+    pub inversions: usize,
+    pub local_misassemblies: usize,
+    pub misassembled_contigs: usize,
+    pub mismatches_per_100kb: f64,
+    pub indels_per_100kb: f64,
+    pub unaligned_contigs: usize,
+    pub unaligned_len: usize,
+}
+
+impl ReferenceStats {
+    /// Relocations and inversions
+    pub fn misassemblies(&self) -> usize {
+        self.relocations + self.inversions
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssemblyReport {
+    pub contiguity: ContiguityStats,
+    /// Statistics relative to the reference, if one was given
+    pub reference: Option<ReferenceStats>,
+}
+
+/// Evaluates contigs, and their alignment to the reference if given
+pub fn evaluate<S: AsRef<AStr<DnaNt>>>(
+    contigs: &[S],
+    reference: Option<&AStr<DnaNt>>,
+    props: EvaluationProperties,
+) -> AssemblyReport {
+    let contigs = contigs
+        .iter()
+        .map(|contig| contig.as_ref())
+        .filter(|contig| contig.len() >= props.min_contig_len)
+        .collect_vec();
+    let lengths = contigs.iter().map(|contig| contig.len()).collect_vec();
+    let total_len = lengths.iter().sum();
+    let (n50, l50) = nx(&lengths, total_len, 0.5).unwrap_or_default();
+
+    let contiguity = ContiguityStats {
+        num_contigs: contigs.len(),
+        total_len,
+        largest_contig: lengths.iter().copied().max().unwrap_or_default(),
+        n50,
+        l50,
+    };
+    let reference =
+        reference.map(|reference| reference_stats(&contigs, &lengths, reference, props));
+
+    AssemblyReport {
+        contiguity,
+        reference,
+    }
+}
+
+fn reference_stats(
+    contigs: &[&AStr<DnaNt>],
+    lengths: &[usize],
+    reference: &AStr<DnaNt>,
+    props: EvaluationProperties,
+) -> ReferenceStats {
+    let aligner = ContigAligner::new(reference.to_owned(), props);
+    let ng = nx(lengths, reference.len(), 0.5);
+
+    let mut stats = ReferenceStats {
+        reference_len: reference.len(),
+        ng50: ng.map(|(ng50, _)| ng50),
+        lg50: ng.map(|(_, lg50)| lg50),
+        genome_fraction: 0.0,
+        duplication_ratio: 0.0,
+        relocations: 0,
+        inversions: 0,
+        local_misassemblies: 0,
+        misassembled_contigs: 0,
+        mismatches_per_100kb: 0.0,
+        indels_per_100kb: 0.0,
+        unaligned_contigs: 0,
+        unaligned_len: 0,
+    };
+
+    let mut covered = vec![false; reference.len()];
+    let mut aligned_len = 0;
+    let mut mismatches = 0;
+    let mut indels = 0;
+    for contig in contigs {
+        let blocks = aligner.align(contig);
+        if blocks.is_empty() {
+            stats.unaligned_contigs += 1;
+            stats.unaligned_len += contig.len();
+            continue;
+        }
+
+        let mut misassembled = false;
+        for (prev, next) in blocks.iter().tuple_windows() {
+            match aligner.breakpoint(prev, next) {
+                Some(Breakpoint::Relocation) => {
+                    stats.relocations += 1;
+                    misassembled = true;
+                }
+                Some(Breakpoint::Inversion) => {
+                    stats.inversions += 1;
+                    misassembled = true;
+                }
+                Some(Breakpoint::Local) => stats.local_misassemblies += 1,
+                None => {}
+            }
+        }
+        if misassembled {
+            stats.misassembled_contigs += 1;
+        }
+
+        for block in &blocks {
+            covered[block.ref_range.clone()].fill(true);
+            aligned_len += block.contig_range.len();
+            mismatches += block.mismatches;
+            indels += block.indels;
+        }
+    }
+
+    let covered_len = covered.iter().filter(|&&covered| covered).count();
+    if !reference.is_empty() {
+        stats.genome_fraction = 100.0 * covered_len as f64 / reference.len() as f64;
+    }
+    if covered_len != 0 {
+        stats.duplication_ratio = aligned_len as f64 / covered_len as f64;
+    }
+    if aligned_len != 0 {
+        stats.mismatches_per_100kb = 100_000.0 * mismatches as f64 / aligned_len as f64;
+        stats.indels_per_100kb = 100_000.0 * indels as f64 / aligned_len as f64;
+    }
+
+    stats
+}
+
+#[derive(Debug, Copy, Clone)]
+enum ReportValue {
+    Int(usize),
+    Float(f64),
+    Missing,
+}
+
+impl From<Option<usize>> for ReportValue {
+    fn from(value: Option<usize>) -> Self {
+        value.map_or(ReportValue::Missing, ReportValue::Int)
+    }
+}
+
+impl AssemblyReport {
+    /// Label in text report, key in JSON report and value of each reported statistic
+    fn fields(&self) -> Vec<(&'static str, &'static str, ReportValue)> {
+        use ReportValue::{Float, Int};
+
+        let contiguity = &self.contiguity;
+        let mut fields = vec![
+            ("# contigs", "num_contigs", Int(contiguity.num_contigs)),
+            ("Total length", "total_len", Int(contiguity.total_len)),
+            (
+                "Largest contig",
+                "largest_contig",
+                Int(contiguity.largest_contig),
+            ),
+            ("N50", "n50", Int(contiguity.n50)),
+            ("L50", "l50", Int(contiguity.l50)),
+        ];
+        if let Some(reference) = &self.reference {
+            fields.extend([
+                (
+                    "Reference length",
+                    "reference_len",
+                    Int(reference.reference_len),
+                ),
+                ("NG50", "ng50", reference.ng50.into()),
+                ("LG50", "lg50", reference.lg50.into()),
+                (
+                    "Genome fraction (%)",
+                    "genome_fraction",
+                    Float(reference.genome_fraction),
+                ),
+                (
+                    "Duplication ratio",
+                    "duplication_ratio",
+                    Float(reference.duplication_ratio),
+                ),
+                (
+                    "# misassemblies",
+                    "misassemblies",
+                    Int(reference.misassemblies()),
+                ),
+                (
+                    "    # relocations",
+                    "relocations",
+                    Int(reference.relocations),
+                ),
+                ("    # inversions", "inversions", Int(reference.inversions)),
+                (
+                    "# misassembled contigs",
+                    "misassembled_contigs",
+                    Int(reference.misassembled_contigs),
+                ),
+                (
+                    "# local misassemblies",
+                    "local_misassemblies",
+                    Int(reference.local_misassemblies),
+                ),
+                (
+                    "# mismatches per 100 kbp",
+                    "mismatches_per_100kb",
+                    Float(reference.mismatches_per_100kb),
+                ),
+                (
+                    "# indels per 100 kbp",
+                    "indels_per_100kb",
+                    Float(reference.indels_per_100kb),
+                ),
+                (
+                    "# unaligned contigs",
+                    "unaligned_contigs",
+                    Int(reference.unaligned_contigs),
+                ),
+                (
+                    "Unaligned length",
+                    "unaligned_len",
+                    Int(reference.unaligned_len),
+                ),
+            ]);
+        }
+        fields
+    }
+
+    /// Writes the report as a JSON object
+    pub fn write_json(&self, write: &mut impl Write) {
+        let fields = self
+            .fields()
+            .into_iter()
+            .map(|(_, key, value)| match value {
+                ReportValue::Int(value) => format!("  \"{}\": {}", key, value),
+                ReportValue::Float(value) => format!("  \"{}\": {:.3}", key, value),
+                ReportValue::Missing => format!("  \"{}\": null", key),
+            })
+            .join(",\n");
+        writeln!(write, "{{\n{}\n}}", fields).unwrap();
+    }
+}
+
+impl Display for AssemblyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (label, _, value) in self.fields() {
+            match value {
+                ReportValue::Int(value) => writeln!(f, "{:<28}{}", label, value)?,
+                ReportValue::Float(value) => writeln!(f, "{:<28}{:.3}", label, value)?,
+                ReportValue::Missing => writeln!(f, "{:<28}-", label)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::alphabet_model::CharT;
+    use crate::assembly::simulation::{ErrorProfile, add_errors};
+    use crate::string_model::test_util::random_dna;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn concat(parts: &[&AStr<DnaNt>]) -> AString<DnaNt> {
+        parts.iter().flat_map(|part| part.iter().copied()).collect()
+    }
+
+    #[test]
+    fn test_nx() {
+        assert_eq!(nx(&[2, 5, 3, 10], 20, 0.5), Some((10, 1)));
+        assert_eq!(nx(&[2, 5, 3, 10], 20, 0.75), Some((5, 2)));
+        assert_eq!(nx(&[2, 5, 3, 10], 40, 0.5), Some((2, 4)));
+        assert_eq!(nx(&[2, 5, 3, 10], 41, 0.5), None);
+        assert_eq!(nx(&[], 0, 0.5), None);
+    }
+
+    #[test]
+    fn test_evaluate_contiguity() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let contigs = [100, 400, 300, 50]
+            .map(|len| random_dna(&mut rng, len))
+            .to_vec();
+        let report = evaluate(
+            &contigs,
+            None,
+            EvaluationProperties::default().min_contig_len(60),
+        );
+        assert_eq!(
+            report.contiguity,
+            ContiguityStats {
+                num_contigs: 3,
+                total_len: 800,
+                largest_contig: 400,
+                n50: 400,
+                l50: 1,
+            }
+        );
+        assert_eq!(report.reference, None);
+    }
+
+    #[test]
+    fn test_align_contigs() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let reference = random_dna(&mut rng, 5000);
+        let aligner = ContigAligner::new(reference.clone(), EvaluationProperties::default());
+
+        let blocks = aligner.align(&reference[1000..2000]);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].strand, Strand::Forward);
+        assert_eq!(blocks[0].contig_range, 0..1000);
+        assert_eq!(blocks[0].ref_range, 1000..2000);
+
+        let blocks = aligner.align(&reverse_complement(&reference[1000..2000]));
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].strand, Strand::Reverse);
+        assert_eq!(blocks[0].contig_range, 0..1000);
+        assert_eq!(blocks[0].ref_range, 1000..2000);
+
+        // substitution and insertion
+        let mut contig = reference[1000..2000].to_vec();
+        contig[300] = DnaNt::from_index((contig[300].index() + 1) % 4);
+        contig.insert(600, DnaNt::A);
+        let blocks = aligner.align(&AString::from(contig));
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].ref_range, 1000..2000);
+        assert_eq!(blocks[0].mismatches, 1);
+        assert_eq!(blocks[0].indels, 1);
+
+        // relocation and inversion
+        let rc = reverse_complement(&reference[4000..4500]);
+        let contig = concat(&[&reference[0..500], &reference[2500..3000], &rc]);
+        let blocks = aligner.align(&contig);
+        // bases at the junctions may match by chance and end up in either block
+        let expected = [(0, 0), (500, 2500), (1000, 4000)];
+        assert_eq!(blocks.len(), expected.len());
+        for (block, (contig_start, ref_start)) in blocks.iter().zip(expected) {
+            assert!(block.contig_range.start.abs_diff(contig_start) <= 5);
+            assert!(block.ref_range.start.abs_diff(ref_start) <= 5);
+            assert!(block.contig_range.len().abs_diff(500) <= 5);
+        }
+        assert_eq!(
+            blocks.iter().map(|block| block.strand).collect_vec(),
+            vec![Strand::Forward, Strand::Forward, Strand::Reverse]
+        );
+        assert_eq!(
+            aligner.breakpoint(&blocks[0], &blocks[1]),
+            Some(Breakpoint::Relocation)
+        );
+        assert_eq!(
+            aligner.breakpoint(&blocks[1], &blocks[2]),
+            Some(Breakpoint::Inversion)
+        );
+
+        // local misassembly
+        let contig = concat(&[&reference[0..500], &reference[700..1200]]);
+        let blocks = aligner.align(&contig);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            aligner.breakpoint(&blocks[0], &blocks[1]),
+            Some(Breakpoint::Local)
+        );
+
+        assert_eq!(aligner.align(&random_dna(&mut rng, 1000)), vec![]);
+    }
+
+    #[test]
+    fn test_evaluate_reference() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let reference = random_dna(&mut rng, 10000);
+        let profile = ErrorProfile::default()
+            .substitution_rate(0.001)
+            .insertion_rate(0.0005)
+            .deletion_rate(0.0005);
+        let contigs = vec![
+            add_errors(&reference[0..4000], profile, &mut rng),
+            reverse_complement(&reference[3900..7000]),
+            concat(&[&reference[7000..8000], &reference[1000..2000]]),
+            random_dna(&mut rng, 500),
+        ];
+
+        let report = evaluate(&contigs, Some(&reference), EvaluationProperties::default());
+        assert_eq!(report.contiguity.num_contigs, 4);
+        assert_eq!(report.contiguity.n50, 3100);
+        let stats = report.reference.clone().unwrap();
+        assert_eq!(stats.reference_len, 10000);
+        assert_eq!(stats.ng50, Some(3100));
+        assert_eq!(stats.lg50, Some(2));
+        assert!(
+            (stats.genome_fraction - 80.0).abs() < 0.5,
+            "{}",
+            stats.genome_fraction
+        );
+        assert!(stats.duplication_ratio > 1.1 && stats.duplication_ratio < 1.2);
+        assert_eq!(stats.relocations, 1);
+        assert_eq!(stats.inversions, 0);
+        assert_eq!(stats.local_misassemblies, 0);
+        assert_eq!(stats.misassembled_contigs, 1);
+        assert!(stats.mismatches_per_100kb > 0.0 && stats.mismatches_per_100kb < 200.0);
+        assert_eq!(stats.unaligned_contigs, 1);
+        assert_eq!(stats.unaligned_len, 500);
+
+        let text = report.to_string();
+        assert!(
+            text.contains(&format!("{:<28}1\n", "# misassemblies")),
+            "{}",
+            text
+        );
+        let mut json = Vec::new();
+        report.write_json(&mut json);
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with("{\n  \"num_contigs\": 4,\n"), "{}", json);
+        assert!(json.contains("\"ng50\": 3100,"), "{}", json);
+        assert!(json.ends_with("\"unaligned_len\": 500\n}\n"), "{}", json);
+    }
+
+    #[test]
+    fn test_report_missing_values() {
+        let report = evaluate(
+            &["ACGT".parse::<AString<DnaNt>>().unwrap()],
+            Some(&"ACGTACGTAC".parse::<AString<DnaNt>>().unwrap()),
+            EvaluationProperties::default(),
+        );
+        let text = report.to_string();
+        assert!(text.contains(&format!("{:<28}-\n", "NG50")), "{}", text);
+        let mut json = Vec::new();
+        report.write_json(&mut json);
+        assert!(String::from_utf8(json).unwrap().contains("\"ng50\": null,"));
+    }
+}
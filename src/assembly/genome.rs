@@ -0,0 +1,531 @@
+//! Generation of genomes with local composition from a Markov model and planted repeats.
+//! Repeats are planted by overwriting the background sequence with mutated copies of a repeat
+//! unit, and the positions of all copies are returned as ground truth
+
+use crate::alphabet_model::CharT;
+use crate::assembly::simulation::{ErrorCounts, ErrorProfile, add_errors_counted};
+use crate::polymers::{DnaNt, Strand, reverse_complement};
+use crate::string_model::{AStr, AString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::io::Write;
+use std::ops::Range;
+
+const NUM_NTS: usize = 4;
+
+/// Attempts at placing a repeat copy at a random free position
+const MAX_PLACEMENT_ATTEMPTS: usize = 10000;
+
+/// Markov chain of order k over nucleotides, giving the probability of each nucleotide given the
+/// k preceding nucleotides
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkovModel {
+    order: usize,
+    /// Probabilities of the next nucleotide for each context, with the context of `order`
+    /// nucleotides encoded in base 4
+    transitions: Vec<[f64; NUM_NTS]>,
+    /// Probabilities of each nucleotide, used for the first `order` nucleotides
+    composition: [f64; NUM_NTS],
+}
+
+fn normalize(counts: [f64; NUM_NTS]) -> [f64; NUM_NTS] {
+    let sum: f64 = counts.iter().sum();
+    if sum > 0.0 {
+        counts.map(|count| count / sum)
+    } else {
+        [1.0 / NUM_NTS as f64; NUM_NTS]
+    }
+}
+
+fn sample(probabilities: &[f64; NUM_NTS], rng: &mut impl Rng) -> DnaNt {
+    let mut r: f64 = rng.random();
+    for (idx, &probability) in probabilities.iter().enumerate() {
+        if r < probability {
+            return DnaNt::from_index(idx);
+        }
+        r -= probability;
+    }
+    DnaNt::from_index(NUM_NTS - 1)
+}
+
+impl MarkovModel {
+    /// Model of independent nucleotides with GC content `gc`
+    pub fn iid(gc: f64) -> Self {
+        let mut composition = [(1.0 - gc) / 2.0; NUM_NTS];
+        composition[DnaNt::C.index()] = gc / 2.0;
+        composition[DnaNt::G.index()] = gc / 2.0;
+        Self {
+            order: 0,
+            transitions: vec![composition],
+            composition,
+        }
+    }
+
+    /// Model of order `order` with probabilities estimated from the nucleotide counts in `seqs`.
+    /// `pseudocount` is added to each count, so that contexts not seen get a positive
+    /// probability
+    pub fn train<S: AsRef<AStr<DnaNt>>>(seqs: &[S], order: usize, pseudocount: f64) -> Self {
+        let num_contexts = NUM_NTS.pow(order as u32);
+        let mut transitions = vec![[pseudocount; NUM_NTS]; num_contexts];
+        let mut composition = [pseudocount; NUM_NTS];
+        for seq in seqs {
+            let seq = seq.as_ref();
+            let mut context = 0;
+            for (pos, nt) in seq.iter().enumerate() {
+                composition[nt.index()] += 1.0;
+                if pos >= order {
+                    transitions[context][nt.index()] += 1.0;
+                }
+                context = (context * NUM_NTS + nt.index()) % num_contexts;
+            }
+        }
+
+        Self {
+            order,
+            transitions: transitions.into_iter().map(normalize).collect(),
+            composition: normalize(composition),
+        }
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Probability of `nt` following `context`. Only the last `order` nucleotides of the context
+    /// are used, and the nucleotide composition is used if the context is shorter
+    pub fn probability(&self, context: &AStr<DnaNt>, nt: DnaNt) -> f64 {
+        if context.len() < self.order {
+            return self.composition[nt.index()];
+        }
+        let context = context[context.len() - self.order..]
+            .iter()
+            .fold(0, |context, nt| context * NUM_NTS + nt.index());
+        self.transitions[context][nt.index()]
+    }
+
+    pub fn generate(&self, len: usize, rng: &mut impl Rng) -> AString<DnaNt> {
+        let num_contexts = self.transitions.len();
+        let mut seq = AString::with_capacity(len);
+        let mut context = 0;
+        for pos in 0..len {
+            let probabilities = if pos < self.order {
+                &self.composition
+            } else {
+                &self.transitions[context]
+            };
+            let nt = sample(probabilities, rng);
+            seq.push(nt);
+            context = (context * NUM_NTS + nt.index()) % num_contexts;
+        }
+        seq
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RepeatKind {
+    /// Copies are adjacent
+    Tandem,
+    /// Copies are at independent random positions
+    Interspersed,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RepeatProperties {
+    pub kind: RepeatKind,
+    pub unit_len: usize,
+    pub copies: usize,
+    /// Mutations of each copy relative to the repeat unit
+    pub divergence: ErrorProfile,
+    /// Interspersed copies are planted in random orientation, otherwise all copies are on the
+    /// forward strand
+    pub both_strands: bool,
+}
+
+impl RepeatProperties {
+    pub fn tandem(unit_len: usize, copies: usize) -> Self {
+        Self {
+            kind: RepeatKind::Tandem,
+            unit_len,
+            copies,
+            divergence: ErrorProfile::default(),
+            both_strands: false,
+        }
+    }
+
+    pub fn interspersed(unit_len: usize, copies: usize) -> Self {
+        Self {
+            kind: RepeatKind::Interspersed,
+            ..Self::tandem(unit_len, copies)
+        }
+    }
+
+    pub fn divergence(mut self, divergence: ErrorProfile) -> Self {
+        self.divergence = divergence;
+        self
+    }
+
+    pub fn both_strands(mut self, both_strands: bool) -> Self {
+        self.both_strands = both_strands;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenomeProperties {
+    pub len: usize,
+    /// Model of the background sequence and of the repeat units
+    pub model: MarkovModel,
+    pub repeats: Vec<RepeatProperties>,
+    /// Seed for the random number generator, or seeded from the OS if `None`
+    pub seed: Option<u64>,
+}
+
+impl GenomeProperties {
+    pub fn len(mut self, len: usize) -> Self {
+        self.len = len;
+        self
+    }
+
+    pub fn model(mut self, model: MarkovModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Adds a repeat to plant
+    pub fn repeat(mut self, repeat: RepeatProperties) -> Self {
+        self.repeats.push(repeat);
+        self
+    }
+
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Default for GenomeProperties {
+    fn default() -> Self {
+        Self {
+            len: 10000,
+            model: MarkovModel::iid(0.5),
+            repeats: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
+/// Copy of a repeat planted in a genome
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlantedRepeat {
+    /// Index of the repeat in [`GenomeProperties::repeats`]
+    pub repeat: usize,
+    pub kind: RepeatKind,
+    /// Index of the copy among the copies of the repeat
+    pub copy: usize,
+    pub range: Range<usize>,
+    /// Strand of the genome the repeat unit is on
+    pub strand: Strand,
+    /// Mutations of the copy relative to the repeat unit
+    pub mutations: ErrorCounts,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedGenome {
+    pub seq: AString<DnaNt>,
+    /// Unit of each repeat
+    pub units: Vec<AString<DnaNt>>,
+    /// Planted repeat copies ordered by position
+    pub repeats: Vec<PlantedRepeat>,
+}
+
+impl GeneratedGenome {
+    /// Writes the planted repeats as tab separated lines with repeat index, kind, copy index,
+    /// 0-based start and end, strand and mutation counts
+    pub fn write_annotation(&self, write: &mut impl Write) {
+        writeln!(
+            write,
+            "#repeat\tkind\tcopy\tstart\tend\tstrand\tsubstitutions\tinsertions\tdeletions"
+        )
+        .unwrap();
+        for repeat in &self.repeats {
+            let kind = match repeat.kind {
+                RepeatKind::Tandem => "tandem",
+                RepeatKind::Interspersed => "interspersed",
+            };
+            let strand = match repeat.strand {
+                Strand::Forward => '+',
+                Strand::Reverse => '-',
+            };
+            writeln!(
+                write,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                repeat.repeat,
+                kind,
+                repeat.copy,
+                repeat.range.start,
+                repeat.range.end,
+                strand,
+                repeat.mutations.substitutions,
+                repeat.mutations.insertions,
+                repeat.mutations.deletions
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Random start of a free window of length `len`, where windows in `occupied` are not free
+fn free_window(
+    genome_len: usize,
+    len: usize,
+    occupied: &[Range<usize>],
+    rng: &mut impl Rng,
+) -> Option<usize> {
+    if len > genome_len {
+        return None;
+    }
+    (0..MAX_PLACEMENT_ATTEMPTS)
+        .map(|_| rng.random_range(0..=genome_len - len))
+        .find(|&start| {
+            occupied
+                .iter()
+                .all(|range| start + len <= range.start || range.end <= start)
+        })
+}
+
+/// Generates a genome from the model and plants the repeats in it. Copies of different repeats
+/// do not overlap, and repeats are planted in order.
+///
+/// Panics if no free position is found for a copy
+pub fn generate_genome(props: &GenomeProperties) -> GeneratedGenome {
+    let mut rng = match props.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    let mut seq = props.model.generate(props.len, &mut rng).to_vec();
+    let mut units = Vec::with_capacity(props.repeats.len());
+    let mut planted: Vec<PlantedRepeat> = Vec::new();
+    let mut occupied: Vec<Range<usize>> = Vec::new();
+
+    for (repeat_idx, repeat) in props.repeats.iter().enumerate() {
+        let unit = props.model.generate(repeat.unit_len, &mut rng);
+        let mut copies = (0..repeat.copies)
+            .map(|_| {
+                let (copy, mutations) = add_errors_counted(&unit, repeat.divergence, &mut rng);
+                if repeat.kind == RepeatKind::Interspersed
+                    && repeat.both_strands
+                    && rng.random_bool(0.5)
+                {
+                    (reverse_complement(&copy), mutations, Strand::Reverse)
+                } else {
+                    (copy, mutations, Strand::Forward)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // windows of the copies, all adjacent for a tandem repeat
+        let windows: Vec<Range<usize>> = match repeat.kind {
+            RepeatKind::Tandem => {
+                let array_len = copies.iter().map(|(copy, _, _)| copy.len()).sum();
+                let start = free_window(props.len, array_len, &occupied, &mut rng)
+                    .expect("no room for tandem repeat");
+                occupied.push(start..start + array_len);
+                copies
+                    .iter()
+                    .scan(start, |start, (copy, _, _)| {
+                        *start += copy.len();
+                        Some(*start - copy.len()..*start)
+                    })
+                    .collect()
+            }
+            RepeatKind::Interspersed => copies
+                .iter()
+                .map(|(copy, _, _)| {
+                    let start = free_window(props.len, copy.len(), &occupied, &mut rng)
+                        .expect("no room for interspersed repeat");
+                    occupied.push(start..start + copy.len());
+                    start..start + copy.len()
+                })
+                .collect(),
+        };
+
+        for (copy_idx, ((copy, mutations, strand), window)) in
+            copies.drain(..).zip(windows).enumerate()
+        {
+            seq[window.clone()].copy_from_slice(&copy);
+            planted.push(PlantedRepeat {
+                repeat: repeat_idx,
+                kind: repeat.kind,
+                copy: copy_idx,
+                range: window,
+                strand,
+                mutations,
+            });
+        }
+        units.push(unit);
+    }
+
+    planted.sort_by_key(|repeat| repeat.range.start);
+    GeneratedGenome {
+        seq: AString::from(seq),
+        units,
+        repeats: planted,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use itertools::Itertools;
+
+    fn gc_content(seq: &AStr<DnaNt>) -> f64 {
+        seq.iter()
+            .filter(|&&nt| nt == DnaNt::C || nt == DnaNt::G)
+            .count() as f64
+            / seq.len() as f64
+    }
+
+    #[test]
+    fn test_iid_model() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let model = MarkovModel::iid(0.3);
+        assert_eq!(model.order(), 0);
+        assert_eq!(model.probability(AStr::from_slice(&[]), DnaNt::G), 0.15);
+        assert_eq!(
+            model.probability(AStr::from_slice(&[DnaNt::G]), DnaNt::A),
+            0.35
+        );
+
+        let seq = model.generate(100000, &mut rng);
+        assert_eq!(seq.len(), 100000);
+        assert!((gc_content(&seq) - 0.3).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_train_markov_model() {
+        let seq: AString<DnaNt> = "ACGTACGTACGTAAGT".parse().unwrap();
+        let model = MarkovModel::train(&[&seq], 1, 0.0);
+        let ctx = |s: &str| s.parse::<AString<DnaNt>>().unwrap();
+        // A is followed by C 3 times, by A once and by G once
+        assert_eq!(model.probability(&ctx("A"), DnaNt::C), 0.6);
+        assert_eq!(model.probability(&ctx("A"), DnaNt::A), 0.2);
+        assert_eq!(model.probability(&ctx("GTA"), DnaNt::G), 0.2);
+        assert_eq!(model.probability(&ctx("C"), DnaNt::G), 1.0);
+        assert_eq!(model.probability(&ctx(""), DnaNt::T), 0.25);
+
+        // unseen contexts get the pseudocount
+        let model = MarkovModel::train(&[&seq], 2, 1.0);
+        assert_eq!(model.probability(&ctx("TT"), DnaNt::A), 0.25);
+
+        // a trained model reproduces the dinucleotide frequencies of the training sequence
+        let mut rng = StdRng::seed_from_u64(2);
+        let training: AString<DnaNt> = (0..20000)
+            .map(|i| {
+                if i % 3 == 0 {
+                    DnaNt::A
+                } else {
+                    DnaNt::from_index(rng.random_range(0..4))
+                }
+            })
+            .collect();
+        let model = MarkovModel::train(&[&training], 2, 1.0);
+        let generated = model.generate(100000, &mut rng);
+        let retrained = MarkovModel::train(&[&generated], 2, 1.0);
+        for (p1, p2) in model
+            .transitions
+            .iter()
+            .flatten()
+            .zip(retrained.transitions.iter().flatten())
+        {
+            assert!((p1 - p2).abs() < 0.05, "{} {}", p1, p2);
+        }
+    }
+
+    #[test]
+    fn test_generate_genome_with_repeats() {
+        let props = GenomeProperties::default()
+            .len(20000)
+            .model(MarkovModel::iid(0.4))
+            .repeat(RepeatProperties::tandem(50, 8))
+            .repeat(RepeatProperties::interspersed(500, 4).both_strands(true))
+            .repeat(
+                RepeatProperties::interspersed(300, 5).divergence(
+                    ErrorProfile::default()
+                        .substitution_rate(0.02)
+                        .insertion_rate(0.01)
+                        .deletion_rate(0.01),
+                ),
+            )
+            .seed(Some(3));
+        let genome = generate_genome(&props);
+        assert_eq!(genome.seq.len(), 20000);
+        assert_eq!(genome.units.len(), 3);
+        assert_eq!(genome.repeats.len(), 8 + 4 + 5);
+
+        // copies do not overlap
+        for (repeat1, repeat2) in genome.repeats.iter().tuple_windows() {
+            assert!(repeat1.range.end <= repeat2.range.start);
+        }
+
+        for repeat in &genome.repeats {
+            let copy = &genome.seq[repeat.range.clone()];
+            let copy = match repeat.strand {
+                Strand::Forward => copy.to_owned(),
+                Strand::Reverse => reverse_complement(copy),
+            };
+            let unit = &genome.units[repeat.repeat];
+            match repeat.repeat {
+                0 | 1 => {
+                    assert_eq!(&copy, unit);
+                    assert_eq!(repeat.mutations, ErrorCounts::default());
+                }
+                _ => {
+                    let mutations = repeat.mutations;
+                    assert_eq!(
+                        copy.len() + mutations.deletions,
+                        unit.len() + mutations.insertions
+                    );
+                }
+            }
+        }
+
+        // tandem copies are adjacent
+        let tandem = genome
+            .repeats
+            .iter()
+            .filter(|repeat| repeat.kind == RepeatKind::Tandem)
+            .collect_vec();
+        assert_eq!(tandem.len(), 8);
+        for (copy1, copy2) in tandem.iter().tuple_windows() {
+            assert_eq!(copy1.range.end, copy2.range.start);
+            assert_eq!(copy1.copy + 1, copy2.copy);
+        }
+
+        // seeded generation is reproducible
+        assert_eq!(generate_genome(&props).seq, genome.seq);
+
+        let mut annotation = Vec::new();
+        genome.write_annotation(&mut annotation);
+        let annotation = String::from_utf8(annotation).unwrap();
+        assert_eq!(annotation.lines().count(), 1 + genome.repeats.len());
+        let first = &genome.repeats[0];
+        assert!(
+            annotation
+                .lines()
+                .nth(1)
+                .unwrap()
+                .starts_with(&format!("{}\t", first.repeat))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no room")]
+    fn test_generate_genome_without_room() {
+        generate_genome(
+            &GenomeProperties::default()
+                .len(1000)
+                .repeat(RepeatProperties::interspersed(400, 3))
+                .seed(Some(4)),
+        );
+    }
+}
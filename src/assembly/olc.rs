@@ -0,0 +1,495 @@
+//! Overlap-layout-consensus assembly of reads with sequencing errors. Approximate overlaps are
+//! found by overlap alignment of read pairs sharing a k-mer, the string graph of overlaps is
+//! transitively reduced, contigs are laid out along non-branching paths and the contig
+//! sequence is the per column consensus of the reads on the path. Reads are assumed to be
+//! from the forward strand
+
+use crate::assembly::Contig;
+use crate::polymers::DnaNt;
+use crate::string::alignment::local_alignment_banded::local_alignment_banded;
+use crate::string::alignment::msa::Freq;
+use crate::string::alignment::overlap_alignment::overlap_alignment;
+use crate::string::alignment::{AlignmentProperties, Edit, edit_distance};
+use crate::string_model::{AStr, AString};
+use hashbrown::HashMap;
+use itertools::Itertools;
+
+#[derive(Debug, Copy, Clone)]
+pub struct OlcProperties {
+    pub min_overlap: usize,
+    /// Maximum fraction of edits in an overlap
+    pub max_error_rate: f64,
+    /// Length of k-mers shared by candidate overlapping reads, at least 1
+    pub k: usize,
+    /// K-mers occurring in more reads are not used to find candidates
+    pub max_kmer_occurrences: usize,
+    /// Slack in read offsets allowed for indels
+    pub band: usize,
+    pub alignment: AlignmentProperties,
+}
+
+impl OlcProperties {
+    pub fn min_overlap(mut self, min_overlap: usize) -> Self {
+        self.min_overlap = min_overlap;
+        self
+    }
+
+    pub fn max_error_rate(mut self, max_error_rate: f64) -> Self {
+        self.max_error_rate = max_error_rate;
+        self
+    }
+
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn max_kmer_occurrences(mut self, max_kmer_occurrences: usize) -> Self {
+        self.max_kmer_occurrences = max_kmer_occurrences;
+        self
+    }
+
+    pub fn band(mut self, band: usize) -> Self {
+        self.band = band;
+        self
+    }
+
+    pub fn alignment(mut self, alignment: AlignmentProperties) -> Self {
+        self.alignment = alignment;
+        self
+    }
+}
+
+impl Default for OlcProperties {
+    fn default() -> Self {
+        Self {
+            min_overlap: 40,
+            max_error_rate: 0.1,
+            k: 15,
+            max_kmer_occurrences: 50,
+            band: 10,
+            alignment: AlignmentProperties::default(),
+        }
+    }
+}
+
+/// Approximate overlap of a suffix of read `source` with a prefix of read `target`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReadOverlap {
+    pub source: usize,
+    pub target: usize,
+    /// Position in source where target starts
+    pub offset: usize,
+    /// Length of overlapping part of source
+    pub len: usize,
+    pub edit_distance: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Overlaps {
+    pub overlaps: Vec<ReadOverlap>,
+    /// Reads approximately contained in other reads
+    pub contained: Vec<bool>,
+}
+
+fn within_error_rate(edit_distance: usize, len: usize, props: OlcProperties) -> bool {
+    edit_distance as f64 <= props.max_error_rate * len as f64
+}
+
+/// Finds approximate overlaps and contained reads
+pub fn find_overlaps<S: AsRef<AStr<DnaNt>>>(reads: &[S], props: OlcProperties) -> Overlaps {
+    assert!(props.k >= 1, "k must be at least 1");
+
+    let reads = reads.iter().map(|read| read.as_ref()).collect_vec();
+
+    let mut kmer_index: HashMap<&AStr<DnaNt>, Vec<(usize, usize)>> = HashMap::new();
+    for (read_idx, read) in reads.iter().enumerate() {
+        for (pos, kmer) in read.windows(props.k).enumerate() {
+            kmer_index
+                .entry(AStr::from_slice(kmer))
+                .or_default()
+                .push((read_idx, pos));
+        }
+    }
+
+    // diagonals (offset of second read in first read) of shared k-mers for each read pair
+    let mut pair_diagonals: HashMap<(usize, usize), Vec<isize>> = HashMap::new();
+    for occurrences in kmer_index.values() {
+        if occurrences.len() > props.max_kmer_occurrences {
+            continue;
+        }
+        for &(read1, pos1) in occurrences {
+            for &(read2, pos2) in occurrences {
+                if read1 != read2 {
+                    pair_diagonals
+                        .entry((read1, read2))
+                        .or_default()
+                        .push(pos1 as isize - pos2 as isize);
+                }
+            }
+        }
+    }
+
+    let mut res = Overlaps {
+        overlaps: Vec::new(),
+        contained: vec![false; reads.len()],
+    };
+    let band = props.band as isize;
+    for ((source, target), mut diagonals) in
+        pair_diagonals.into_iter().sorted_by_key(|(pair, _)| *pair)
+    {
+        diagonals.sort();
+        let diagonal = diagonals[diagonals.len() / 2];
+        let source_read = reads[source];
+        let target_read = reads[target];
+
+        // reads starting at about the same position with about the same length contain each
+        // other, and the one with the highest index is considered contained
+        let mutual = diagonal.unsigned_abs() <= props.band
+            && source_read.len().abs_diff(target_read.len()) <= props.band;
+        let contained = if mutual {
+            target > source
+        } else {
+            diagonal >= 0 && diagonal as usize + target_read.len() <= source_read.len()
+        };
+
+        if contained {
+            if let Some(align) = local_alignment_banded(
+                source_read,
+                target_read,
+                props.alignment,
+                diagonal - band..=diagonal + band,
+            ) && within_error_rate(edit_distance(&align.edits), target_read.len(), props)
+            {
+                res.contained[target] = true;
+            }
+        } else if !mutual && diagonal > 0 {
+            let start = (diagonal - band).max(0) as usize;
+            let align = overlap_alignment(&source_read[start..], target_read, props.alignment);
+            let offset = start + align.x_start;
+            let len = source_read.len() - offset;
+            let edit_distance = edit_distance(&align.edits);
+            if len >= props.min_overlap && within_error_rate(edit_distance, len, props) {
+                res.overlaps.push(ReadOverlap {
+                    source,
+                    target,
+                    offset,
+                    len,
+                    edit_distance,
+                });
+            }
+        }
+    }
+
+    res
+}
+
+/// Removes overlaps `a -> c` implied by overlaps `a -> b` and `b -> c` with consistent offsets
+pub fn transitive_reduction(overlaps: &[ReadOverlap], fuzz: usize) -> Vec<ReadOverlap> {
+    let offsets: HashMap<(usize, usize), usize> = overlaps
+        .iter()
+        .map(|overlap| ((overlap.source, overlap.target), overlap.offset))
+        .collect();
+    let outgoing = overlaps.iter().into_group_map_by(|overlap| overlap.source);
+
+    overlaps
+        .iter()
+        .filter(|overlap_ac| {
+            !outgoing[&overlap_ac.source].iter().any(|overlap_ab| {
+                overlap_ab.offset < overlap_ac.offset
+                    && offsets
+                        .get(&(overlap_ab.target, overlap_ac.target))
+                        .is_some_and(|&offset_bc| {
+                            (overlap_ab.offset + offset_bc).abs_diff(overlap_ac.offset) <= fuzz
+                        })
+            })
+        })
+        .copied()
+        .collect()
+}
+
+/// Read in a contig layout
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LayoutRead {
+    pub read: usize,
+    /// Offset of read start from start of previous read in layout
+    pub offset: usize,
+}
+
+/// Lays out contigs along non-branching paths of the string graph of the overlaps between
+/// the given reads
+pub fn layout(reads: &[usize], overlaps: &[ReadOverlap]) -> Vec<Vec<LayoutRead>> {
+    let outgoing = overlaps.iter().into_group_map_by(|overlap| overlap.source);
+    let incoming = overlaps.iter().into_group_map_by(|overlap| overlap.target);
+    let out_degree = |read: usize| outgoing.get(&read).map_or(0, Vec::len);
+    let in_degree = |read: usize| incoming.get(&read).map_or(0, Vec::len);
+    let single_successor = |read: usize| {
+        outgoing
+            .get(&read)
+            .and_then(|overlaps| overlaps.iter().exactly_one().ok())
+            .filter(|overlap| in_degree(overlap.target) == 1)
+    };
+    let has_path_predecessor = |read: usize| {
+        incoming
+            .get(&read)
+            .and_then(|overlaps| overlaps.iter().exactly_one().ok())
+            .is_some_and(|overlap| out_degree(overlap.source) == 1)
+    };
+
+    let mut visited = vec![false; reads.iter().max().map_or(0, |max| max + 1)];
+    let mut paths = Vec::new();
+    let mut walk = |start: usize, visited: &mut Vec<bool>| {
+        let mut path = vec![LayoutRead {
+            read: start,
+            offset: 0,
+        }];
+        visited[start] = true;
+        let mut read = start;
+        while let Some(overlap) = single_successor(read) {
+            if visited[overlap.target] {
+                break;
+            }
+            visited[overlap.target] = true;
+            path.push(LayoutRead {
+                read: overlap.target,
+                offset: overlap.offset,
+            });
+            read = overlap.target;
+        }
+        paths.push(path);
+    };
+
+    for &read in reads {
+        if !visited[read] && !has_path_predecessor(read) {
+            walk(read, &mut visited);
+        }
+    }
+    // remaining reads are on cycles
+    for &read in reads {
+        if !visited[read] {
+            walk(read, &mut visited);
+        }
+    }
+
+    paths
+}
+
+/// Consensus of the reads in a layout. Each read is aligned to the consensus so far, and the
+/// contig is the most frequent character in each column. A character inserted before a column
+/// by the majority of the reads covering it is included as well
+pub fn consensus<S: AsRef<AStr<DnaNt>>>(
+    reads: &[S],
+    layout: &[LayoutRead],
+    props: OlcProperties,
+) -> Contig {
+    let mut columns: Vec<Freq<DnaNt>> = Vec::new();
+    // first character inserted by reads before each column
+    let mut insertions: Vec<Freq<DnaNt>> = Vec::new();
+    let mut draft: AString<DnaNt> = AString::default();
+    let mut read_start = 0;
+
+    for (layout_idx, layout_read) in layout.iter().enumerate() {
+        let read = reads[layout_read.read].as_ref();
+        let extension = if layout_idx == 0 {
+            read
+        } else {
+            let start = (read_start + layout_read.offset)
+                .saturating_sub(props.band)
+                .min(draft.len());
+            let align = overlap_alignment(&draft[start..], read, props.alignment);
+            read_start = start + align.x_start;
+
+            let mut col = read_start;
+            let mut read_pos = 0;
+            let mut prev_edit = None;
+            for edit in align.edits.iter() {
+                match edit {
+                    Edit::Match | Edit::Mismatch => {
+                        columns[col].observe(read[read_pos]);
+                        col += 1;
+                        read_pos += 1;
+                    }
+                    Edit::Delete => {
+                        columns[col].observe_gap();
+                        col += 1;
+                    }
+                    Edit::Insert => {
+                        if prev_edit != Some(Edit::Insert) && col < insertions.len() {
+                            insertions[col].observe(read[read_pos]);
+                        }
+                        read_pos += 1;
+                    }
+                }
+                prev_edit = Some(*edit);
+            }
+
+            &read[align.y_end..]
+        };
+
+        // part of read not overlapping the draft extends it
+        for &ch in extension.iter() {
+            let mut freq = Freq::default();
+            freq.observe(ch);
+            columns.push(freq);
+            insertions.push(Freq::default());
+            draft.push(ch);
+        }
+    }
+
+    let seq: AString<DnaNt> = columns
+        .iter()
+        .zip(&insertions)
+        .flat_map(|(freq, inserted)| {
+            let depth = freq.chars() + freq.gaps();
+            let inserted = inserted
+                .most_frequent()
+                .filter(|_| 2 * inserted.chars() > depth);
+            let column = freq
+                .most_frequent()
+                .filter(|&ch| freq.count(ch) >= freq.gaps());
+            inserted.into_iter().chain(column)
+        })
+        .collect();
+    let coverage =
+        columns.iter().map(|freq| freq.chars()).sum::<usize>() as f64 / seq.len().max(1) as f64;
+
+    Contig { seq, coverage }
+}
+
+/// Assembles reads into contigs, sorted by decreasing length
+pub fn assemble<S: AsRef<AStr<DnaNt>>>(reads: &[S], props: OlcProperties) -> Vec<Contig> {
+    let overlaps = find_overlaps(reads, props);
+    let contained = &overlaps.contained;
+    let overlaps = overlaps
+        .overlaps
+        .iter()
+        .filter(|overlap| !contained[overlap.source] && !contained[overlap.target])
+        .copied()
+        .collect_vec();
+    let overlaps = transitive_reduction(&overlaps, props.band);
+
+    let reads_uncontained = (0..reads.len())
+        .filter(|&read| !contained[read])
+        .collect_vec();
+
+    layout(&reads_uncontained, &overlaps)
+        .iter()
+        .map(|path| consensus(reads, path, props))
+        .sorted_by(|contig1, contig2| contig2.seq.len().cmp(&contig1.seq.len()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembly::simulation::{ErrorProfile, add_errors};
+    use crate::string::alignment::global_alignment_wagner_fischer::global_alignment;
+    use crate::string_model::test_util::{random_dna, tiled_reads};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_find_overlaps() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let genome = random_dna(&mut rng, 300);
+        let reads = [
+            genome[0..100].to_owned(),
+            genome[50..150].to_owned(),
+            genome[60..90].to_owned(),
+            genome[0..100].to_owned(),
+        ];
+
+        let overlaps = find_overlaps(&reads, OlcProperties::default());
+        assert_eq!(overlaps.contained, vec![false, false, true, true]);
+        let overlap = |source, target| {
+            overlaps
+                .overlaps
+                .iter()
+                .find(|overlap| overlap.source == source && overlap.target == target)
+                .copied()
+        };
+        assert_eq!(
+            overlap(0, 1),
+            Some(ReadOverlap {
+                source: 0,
+                target: 1,
+                offset: 50,
+                len: 50,
+                edit_distance: 0
+            })
+        );
+        assert_eq!(overlap(1, 0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "k must be at least 1")]
+    fn test_find_overlaps_k_zero() {
+        let reads = ["ACGT".parse::<AString<DnaNt>>().unwrap()];
+        find_overlaps(&reads, OlcProperties::default().k(0));
+    }
+
+    #[test]
+    fn test_transitive_reduction() {
+        let overlap = |source, target, offset| ReadOverlap {
+            source,
+            target,
+            offset,
+            len: 100 - offset,
+            edit_distance: 0,
+        };
+        let overlaps = [overlap(0, 1, 20), overlap(1, 2, 30), overlap(0, 2, 51)];
+        assert_eq!(
+            transitive_reduction(&overlaps, 2),
+            vec![overlap(0, 1, 20), overlap(1, 2, 30)]
+        );
+        assert_eq!(transitive_reduction(&overlaps, 0), overlaps.to_vec());
+    }
+
+    #[test]
+    fn test_layout() {
+        let overlap = |source, target, offset| ReadOverlap {
+            source,
+            target,
+            offset,
+            len: 100 - offset,
+            edit_distance: 0,
+        };
+        // 0 -> 1 -> 2 branches to 3 and 4
+        let overlaps = [
+            overlap(0, 1, 10),
+            overlap(1, 2, 20),
+            overlap(2, 3, 30),
+            overlap(2, 4, 40),
+        ];
+        let layout_read = |read, offset| LayoutRead { read, offset };
+        assert_eq!(
+            layout(&[0, 1, 2, 3, 4, 5], &overlaps),
+            vec![
+                vec![layout_read(0, 0), layout_read(1, 10), layout_read(2, 20)],
+                vec![layout_read(3, 0)],
+                vec![layout_read(4, 0)],
+                vec![layout_read(5, 0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_reads_with_errors() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let genome = random_dna(&mut rng, 2000);
+        let profile = ErrorProfile::default()
+            .substitution_rate(0.01)
+            .insertion_rate(0.01)
+            .deletion_rate(0.01);
+        let reads = tiled_reads(&genome, 200, 40)
+            .iter()
+            .map(|read| add_errors(read, profile, &mut rng))
+            .collect_vec();
+
+        let contigs = assemble(&reads, OlcProperties::default().max_error_rate(0.15));
+        assert_eq!(contigs.len(), 1);
+        let align = global_alignment(&genome, &contigs[0].seq, AlignmentProperties::default());
+        assert!(align.penalty <= 10, "{}", align.penalty);
+        assert!(contigs[0].coverage > 4.0);
+    }
+}
@@ -0,0 +1,658 @@
+//! Scaffolding of contigs with paired-end reads. Both ends of each pair are mapped to the
+//! contigs, and pairs with ends on different contigs link the contig ends the fragment
+//! continues past. Links between the same contig ends are bundled into edges of a graph of
+//! contigs, and scaffolds are read off the paths of the graph after removing weak and
+//! ambiguous edges. Gaps are estimated from the insert size, and pairs implying a fragment
+//! length far from the insert size are discarded as mis-mapped or chimeric
+
+use crate::assembly::simulation::InsertSize;
+use crate::polymers::{DnaNt, Strand, reverse_complement};
+use crate::string::alignment::Edit;
+use crate::string::read_mapper::{Mapper, MapperProperties};
+use crate::string_model::{AStr, AString};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableUnGraph};
+use petgraph::visit::EdgeRef;
+
+/// Pairs with a gap estimate more than this many insert size standard deviations from the
+/// possible gaps are discordant
+const MAX_INSERT_SDS: f64 = 3.0;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ScaffoldProperties {
+    /// Fragment length distribution of the read pairs. Mates face each other, with the first
+    /// on the forward strand of the fragment
+    pub insert_size: InsertSize,
+    /// Contig ends are joined if linked by at least this number of pairs
+    pub min_links: usize,
+    /// Read mappings with lower mapping quality are ignored
+    pub min_mapq: u8,
+    /// Number of Ns between contigs with an estimated gap less than this
+    pub min_gap: usize,
+    pub mapper: MapperProperties,
+}
+
+impl ScaffoldProperties {
+    pub fn insert_size(mut self, insert_size: InsertSize) -> Self {
+        self.insert_size = insert_size;
+        self
+    }
+
+    pub fn min_links(mut self, min_links: usize) -> Self {
+        self.min_links = min_links;
+        self
+    }
+
+    pub fn min_mapq(mut self, min_mapq: u8) -> Self {
+        self.min_mapq = min_mapq;
+        self
+    }
+
+    pub fn min_gap(mut self, min_gap: usize) -> Self {
+        self.min_gap = min_gap;
+        self
+    }
+
+    pub fn mapper(mut self, mapper: MapperProperties) -> Self {
+        self.mapper = mapper;
+        self
+    }
+}
+
+impl Default for ScaffoldProperties {
+    fn default() -> Self {
+        Self {
+            insert_size: InsertSize {
+                mean: 500.0,
+                sd: 50.0,
+            },
+            min_links: 3,
+            min_mapq: 20,
+            min_gap: 10,
+            mapper: MapperProperties::default(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Side {
+    Start,
+    End,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct ContigEnd {
+    contig: usize,
+    side: Side,
+}
+
+/// Pairs linking two contig ends
+#[derive(Debug, Clone)]
+struct Link {
+    ends: [ContigEnd; 2],
+    pairs: usize,
+    /// Average gap estimate of the pairs
+    gap: f64,
+    /// Standard error of the gap estimate
+    gap_sd: f64,
+}
+
+impl Link {
+    fn other(&self, end: ContigEnd) -> ContigEnd {
+        if self.ends[0] == end {
+            self.ends[1]
+        } else {
+            self.ends[0]
+        }
+    }
+}
+
+/// Read mapped to a contig
+#[derive(Debug, Copy, Clone)]
+struct ContigHit {
+    contig: usize,
+    strand: Strand,
+    /// Distance from the end of the read on the fragment to the contig end the fragment
+    /// continues past
+    distance: usize,
+}
+
+impl ContigHit {
+    /// Contig end the fragment continues past
+    fn end(&self) -> ContigEnd {
+        let side = match self.strand {
+            Strand::Forward => Side::End,
+            Strand::Reverse => Side::Start,
+        };
+        ContigEnd {
+            contig: self.contig,
+            side,
+        }
+    }
+}
+
+/// Contig in a scaffold
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScaffoldPart {
+    /// Index of the contig in the input
+    pub contig: usize,
+    /// The contig is reverse complemented in the scaffold for [`Strand::Reverse`]
+    pub strand: Strand,
+    /// Estimated gap to the previous contig, negative if they overlap. 0 for the first contig
+    pub gap: isize,
+    /// Standard error of the gap estimate, from the insert size standard deviation and the
+    /// number of links
+    pub gap_sd: usize,
+    /// Number of pairs linking the contig to the previous contig
+    pub links: usize,
+}
+
+/// Ordered and oriented contigs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scaffold {
+    pub parts: Vec<ScaffoldPart>,
+}
+
+impl Scaffold {
+    /// Sequence of the scaffold, with gaps of at least `min_gap` Ns between contigs
+    pub fn sequence<S: AsRef<AStr<DnaNt>>>(&self, contigs: &[S], min_gap: usize) -> String {
+        let mut seq = String::new();
+        for (idx, part) in self.parts.iter().enumerate() {
+            if idx > 0 {
+                let gap = part.gap.max(min_gap as isize) as usize;
+                seq.extend(std::iter::repeat_n('N', gap));
+            }
+            let contig = contigs[part.contig].as_ref();
+            match part.strand {
+                Strand::Forward => seq.push_str(&contig.to_string()),
+                Strand::Reverse => seq.push_str(&reverse_complement(contig).to_string()),
+            }
+        }
+        seq
+    }
+
+    /// Length of the scaffold sequence
+    pub fn len<S: AsRef<AStr<DnaNt>>>(&self, contigs: &[S], min_gap: usize) -> usize {
+        self.parts
+            .iter()
+            .enumerate()
+            .map(|(idx, part)| {
+                let gap = if idx > 0 {
+                    part.gap.max(min_gap as isize) as usize
+                } else {
+                    0
+                };
+                gap + contigs[part.contig].as_ref().len()
+            })
+            .sum()
+    }
+}
+
+/// Number of reference positions covered by the alignment
+fn reference_len(edits: &AStr<Edit>) -> usize {
+    edits.iter().filter(|edit| **edit != Edit::Insert).count()
+}
+
+struct ContigIndex {
+    mapper: Mapper,
+    /// Start of each contig in the concatenated contigs, and the total length
+    offsets: Vec<usize>,
+    min_mapq: u8,
+}
+
+impl ContigIndex {
+    fn new<S: AsRef<AStr<DnaNt>>>(contigs: &[S], props: &ScaffoldProperties) -> Self {
+        let mut offsets = vec![0];
+        let mut concatenated = AString::<DnaNt>::default();
+        for contig in contigs {
+            concatenated.push_str(contig.as_ref());
+            offsets.push(concatenated.len());
+        }
+        Self {
+            mapper: Mapper::new(concatenated, props.mapper),
+            offsets,
+            min_mapq: props.min_mapq,
+        }
+    }
+
+    /// Mapping of the read within a single contig
+    fn map(&self, read: &AStr<DnaNt>) -> Option<ContigHit> {
+        let mapping = self.mapper.map(read)?;
+        if mapping.mapq < self.min_mapq {
+            return None;
+        }
+        let contig = self
+            .offsets
+            .partition_point(|&offset| offset <= mapping.position)
+            - 1;
+        let start = mapping.position - self.offsets[contig];
+        let end = start + reference_len(&mapping.edits);
+        let contig_len = self.offsets[contig + 1] - self.offsets[contig];
+        if end > contig_len {
+            return None;
+        }
+        let distance = match mapping.strand {
+            Strand::Forward => contig_len - start,
+            Strand::Reverse => end,
+        };
+        Some(ContigHit {
+            contig,
+            strand: mapping.strand,
+            distance,
+        })
+    }
+}
+
+struct LinkGraph {
+    graph: StableUnGraph<usize, Link>,
+}
+
+impl LinkGraph {
+    fn new(contigs: usize, links: impl IntoIterator<Item = Link>) -> Self {
+        let mut graph = StableUnGraph::default();
+        for contig in 0..contigs {
+            graph.add_node(contig);
+        }
+        for link in links {
+            graph.add_edge(
+                NodeIndex::new(link.ends[0].contig),
+                NodeIndex::new(link.ends[1].contig),
+                link,
+            );
+        }
+        Self { graph }
+    }
+
+    fn edges_at(&self, end: ContigEnd) -> Vec<EdgeIndex> {
+        self.graph
+            .edges(NodeIndex::new(end.contig))
+            .filter(|edge| edge.weight().ends.contains(&end))
+            .map(|edge| edge.id())
+            .dedup()
+            .collect()
+    }
+
+    /// Removes all edges at contig ends with more than one edge, as the contig end is next to
+    /// a repeat or the links are inconsistent
+    fn remove_ambiguous(&mut self) {
+        let ambiguous = self
+            .graph
+            .node_indices()
+            .flat_map(|node| {
+                [Side::Start, Side::End].map(|side| ContigEnd {
+                    contig: self.graph[node],
+                    side,
+                })
+            })
+            .map(|end| self.edges_at(end))
+            .filter(|edges| edges.len() > 1)
+            .flatten()
+            .unique()
+            .collect_vec();
+        for edge in ambiguous {
+            self.graph.remove_edge(edge);
+        }
+    }
+
+    /// Link at the contig end, if any
+    fn link_at(&self, end: ContigEnd) -> Option<&Link> {
+        self.edges_at(end)
+            .first()
+            .map(|&edge| self.graph.edge_weight(edge).unwrap())
+    }
+
+    /// Scaffold following the links from the contig, entering it at `side`
+    fn walk(&self, contig: usize, side: Side, visited: &mut [bool]) -> Scaffold {
+        let strand_from = |side| match side {
+            Side::Start => Strand::Forward,
+            Side::End => Strand::Reverse,
+        };
+        let mut parts = vec![ScaffoldPart {
+            contig,
+            strand: strand_from(side),
+            gap: 0,
+            gap_sd: 0,
+            links: 0,
+        }];
+        visited[contig] = true;
+        let mut exit = ContigEnd {
+            contig,
+            side: match side {
+                Side::Start => Side::End,
+                Side::End => Side::Start,
+            },
+        };
+        while let Some(link) = self.link_at(exit) {
+            let entry = link.other(exit);
+            if visited[entry.contig] {
+                break;
+            }
+            visited[entry.contig] = true;
+            parts.push(ScaffoldPart {
+                contig: entry.contig,
+                strand: strand_from(entry.side),
+                gap: link.gap.round() as isize,
+                gap_sd: link.gap_sd.round() as usize,
+                links: link.pairs,
+            });
+            exit = ContigEnd {
+                contig: entry.contig,
+                side: match entry.side {
+                    Side::Start => Side::End,
+                    Side::End => Side::Start,
+                },
+            };
+        }
+        Scaffold { parts }
+    }
+}
+
+/// Orders and orients the contigs using the read pairs. Every contig is in exactly one of the
+/// returned scaffolds, which are sorted by decreasing length
+pub fn scaffold<S: AsRef<AStr<DnaNt>>, R: AsRef<AStr<DnaNt>>>(
+    contigs: &[S],
+    pairs: &[(R, R)],
+    props: ScaffoldProperties,
+) -> Vec<Scaffold> {
+    let index = ContigIndex::new(contigs, &props);
+
+    let insert_size = props.insert_size;
+    let max_deviation = MAX_INSERT_SDS * insert_size.sd;
+
+    let mut bundles: HashMap<(ContigEnd, ContigEnd), Vec<f64>> = HashMap::new();
+    for (read1, read2) in pairs {
+        let (Some(hit1), Some(hit2)) = (index.map(read1.as_ref()), index.map(read2.as_ref()))
+        else {
+            continue;
+        };
+        if hit1.contig == hit2.contig {
+            continue;
+        }
+        let gap = insert_size.mean - hit1.distance as f64 - hit2.distance as f64;
+        // the contigs do not overlap by more than a few chars, so the fragment would be too
+        // long
+        if gap < -max_deviation {
+            continue;
+        }
+        let ends = if hit1.end() < hit2.end() {
+            (hit1.end(), hit2.end())
+        } else {
+            (hit2.end(), hit1.end())
+        };
+        bundles.entry(ends).or_default().push(gap);
+    }
+
+    // the gap estimates of the pairs of a bundle are normal around the gap, so pairs far from
+    // the median are discordant
+    let links = bundles.into_iter().filter_map(|((end1, end2), mut gaps)| {
+        gaps.sort_by(f64::total_cmp);
+        let median = gaps[gaps.len() / 2];
+        gaps.retain(|gap| (gap - median).abs() <= max_deviation);
+        let pairs = gaps.len();
+        (pairs >= props.min_links).then(|| Link {
+            ends: [end1, end2],
+            pairs,
+            gap: gaps.iter().sum::<f64>() / pairs as f64,
+            gap_sd: insert_size.sd / (pairs as f64).sqrt(),
+        })
+    });
+    let mut graph = LinkGraph::new(contigs.len(), links);
+    graph.remove_ambiguous();
+
+    // paths are walked from a contig end without links, cycles from any contig
+    let mut visited = vec![false; contigs.len()];
+    let mut scaffolds = Vec::new();
+    for contig in 0..contigs.len() {
+        if visited[contig] {
+            continue;
+        }
+        for side in [Side::Start, Side::End] {
+            if graph.link_at(ContigEnd { contig, side }).is_none() {
+                scaffolds.push(graph.walk(contig, side, &mut visited));
+                break;
+            }
+        }
+    }
+    for contig in 0..contigs.len() {
+        if !visited[contig] {
+            scaffolds.push(graph.walk(contig, Side::Start, &mut visited));
+        }
+    }
+
+    scaffolds.sort_by_key(|scaffold| std::cmp::Reverse(scaffold.len(contigs, props.min_gap)));
+    scaffolds
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembly::simulation::{ReadSimulatorProperties, simulate_reads};
+    use crate::string_model::test_util::random_dna;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// Read pairs from the genome, with mates adjacent in the simulated reads
+    fn read_pairs(
+        genome: &AStr<DnaNt>,
+        insert_size: InsertSize,
+        seed: u64,
+    ) -> Vec<(AString<DnaNt>, AString<DnaNt>)> {
+        let props = ReadSimulatorProperties::default()
+            .read_len(100)
+            .depth(5.0)
+            .paired(Some(insert_size))
+            .seed(Some(seed));
+        simulate_reads(genome, props)
+            .into_iter()
+            .map(|read| read.entry.polymer)
+            .tuples()
+            .collect()
+    }
+
+    #[test]
+    fn test_scaffold() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let genome = random_dna(&mut rng, 20000);
+
+        // contigs with gaps between them, in shuffled order and orientation
+        let spans = [0..4000, 4100..9000, 9250..9800, 9800..15000, 15300..20000];
+        let contigs = [
+            genome[spans[3].clone()].to_owned(),
+            reverse_complement(&genome[spans[1].clone()]),
+            genome[spans[4].clone()].to_owned(),
+            reverse_complement(&genome[spans[0].clone()]),
+            genome[spans[2].clone()].to_owned(),
+        ];
+        let insert_size = InsertSize {
+            mean: 1000.0,
+            sd: 50.0,
+        };
+        let pairs = read_pairs(&genome, insert_size, 2);
+        let props = ScaffoldProperties::default().insert_size(insert_size);
+        let scaffolds = scaffold(&contigs, &pairs, props);
+        assert_eq!(scaffolds.len(), 1);
+
+        // the scaffold may be on either strand
+        let mut parts = scaffolds[0].parts.clone();
+        if parts[0].contig != 3 {
+            parts.reverse();
+            let (mut gap, mut gap_sd, mut links) = (0, 0, 0);
+            for part in &mut parts {
+                part.strand = match part.strand {
+                    Strand::Forward => Strand::Reverse,
+                    Strand::Reverse => Strand::Forward,
+                };
+                (part.gap, gap) = (gap, part.gap);
+                (part.gap_sd, gap_sd) = (gap_sd, part.gap_sd);
+                (part.links, links) = (links, part.links);
+            }
+        }
+        assert_eq!(
+            parts
+                .iter()
+                .map(|part| (part.contig, part.strand))
+                .collect_vec(),
+            vec![
+                (3, Strand::Reverse),
+                (1, Strand::Reverse),
+                (4, Strand::Forward),
+                (0, Strand::Forward),
+                (2, Strand::Forward)
+            ]
+        );
+        for (part, expected) in parts.iter().skip(1).zip([100, 250, 0, 300]) {
+            assert!((part.gap - expected).abs() < 40, "{:?} {}", part, expected);
+            assert!(part.links >= props.min_links);
+            assert!(part.gap_sd > 0 && part.gap_sd < 50);
+        }
+
+        let seq = scaffolds[0].sequence(&contigs, 10);
+        assert_eq!(seq.len(), scaffolds[0].len(&contigs, 10));
+        let genome_str = genome.to_string();
+        let rc_str = reverse_complement(&genome).to_string();
+        let contig_seqs = seq.split('N').filter(|s| !s.is_empty()).collect_vec();
+        assert_eq!(contig_seqs.len(), 5);
+        assert!(
+            contig_seqs
+                .iter()
+                .tuple_windows()
+                .all(|(seq1, seq2)| genome_str.find(seq1) < genome_str.find(seq2))
+                || contig_seqs
+                    .iter()
+                    .tuple_windows()
+                    .all(|(seq1, seq2)| rc_str.find(seq1) < rc_str.find(seq2))
+        );
+    }
+
+    #[test]
+    fn test_scaffold_unlinked() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let genome = random_dna(&mut rng, 10000);
+
+        // the gap between the contigs is longer than the fragments
+        let contigs = [genome[..4000].to_owned(), genome[6000..].to_owned()];
+        let insert_size = InsertSize {
+            mean: 500.0,
+            sd: 50.0,
+        };
+        let pairs = read_pairs(&genome, insert_size, 4);
+        let scaffolds = scaffold(
+            &contigs,
+            &pairs,
+            ScaffoldProperties::default().insert_size(insert_size),
+        );
+        assert_eq!(
+            scaffolds,
+            vec![
+                Scaffold {
+                    parts: vec![ScaffoldPart {
+                        contig: 0,
+                        strand: Strand::Forward,
+                        gap: 0,
+                        gap_sd: 0,
+                        links: 0
+                    }]
+                },
+                Scaffold {
+                    parts: vec![ScaffoldPart {
+                        contig: 1,
+                        strand: Strand::Forward,
+                        gap: 0,
+                        gap_sd: 0,
+                        links: 0
+                    }]
+                }
+            ]
+        );
+        assert_eq!(
+            scaffolds[0].sequence(&contigs, 10),
+            genome[..4000].to_string()
+        );
+    }
+
+    #[test]
+    fn test_scaffold_discordant_pairs() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let genome = random_dna(&mut rng, 10000);
+
+        let contigs = [
+            genome[..4000].to_owned(),
+            genome[4100..7000].to_owned(),
+            genome[9000..].to_owned(),
+        ];
+        let insert_size = InsertSize {
+            mean: 500.0,
+            sd: 50.0,
+        };
+        let mut pairs = read_pairs(&genome, insert_size, 6);
+        // chimeric pairs linking the end of contig 0 to the start of contig 2, implying
+        // fragments of thousands of chars
+        for idx in 0..5 {
+            pairs.push((
+                genome[1000 + 200 * idx..1100 + 200 * idx].to_owned(),
+                reverse_complement(&genome[9300 + 100 * idx..9400 + 100 * idx]),
+            ));
+        }
+        let scaffolds = scaffold(
+            &contigs,
+            &pairs,
+            ScaffoldProperties::default().insert_size(insert_size),
+        );
+        assert_eq!(scaffolds.len(), 2);
+        assert_eq!(
+            scaffolds[0]
+                .parts
+                .iter()
+                .map(|part| part.contig)
+                .sorted()
+                .collect_vec(),
+            vec![0, 1]
+        );
+        assert!((scaffolds[0].parts[1].gap - 100).abs() < 40);
+    }
+
+    #[test]
+    fn test_remove_ambiguous() {
+        // the end of contig 0 is linked to two contigs
+        let end = |contig, side| ContigEnd { contig, side };
+        let link = |end1, end2| Link {
+            ends: [end1, end2],
+            pairs: 5,
+            gap: 10.0,
+            gap_sd: 20.0,
+        };
+        let mut graph = LinkGraph::new(
+            4,
+            [
+                link(end(0, Side::End), end(1, Side::Start)),
+                link(end(0, Side::End), end(2, Side::End)),
+                link(end(2, Side::Start), end(3, Side::Start)),
+            ],
+        );
+        graph.remove_ambiguous();
+        assert!(graph.link_at(end(0, Side::End)).is_none());
+        assert!(graph.link_at(end(1, Side::Start)).is_none());
+
+        let mut visited = vec![false; 4];
+        assert_eq!(
+            graph.walk(2, Side::End, &mut visited).parts,
+            vec![
+                ScaffoldPart {
+                    contig: 2,
+                    strand: Strand::Reverse,
+                    gap: 0,
+                    gap_sd: 0,
+                    links: 0
+                },
+                ScaffoldPart {
+                    contig: 3,
+                    strand: Strand::Forward,
+                    gap: 10,
+                    gap_sd: 20,
+                    links: 5
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,776 @@
+//! Simulation of sequencing reads. Fragments are sampled uniformly from a genome, and reads are
+//! sequenced from one or both ends of the fragment with substitution and indel errors. Error
+//! rates increase along the read, and the reported base qualities match the error rates
+
+use crate::alphabet_model::CharT;
+use crate::polymers::{DnaNt, Strand, reverse_complement};
+use crate::string_model::{AStr, AString};
+use crate::util::FastqEntry;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+use std::io::Write;
+use std::ops::Range;
+
+const MIN_QUALITY: u8 = 2;
+const MAX_QUALITY: u8 = 41;
+
+/// Per base probabilities of sequencing errors
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ErrorProfile {
+    pub substitution_rate: f64,
+    pub insertion_rate: f64,
+    pub deletion_rate: f64,
+}
+
+impl ErrorProfile {
+    pub fn substitution_rate(mut self, substitution_rate: f64) -> Self {
+        self.substitution_rate = substitution_rate;
+        self
+    }
+
+    pub fn insertion_rate(mut self, insertion_rate: f64) -> Self {
+        self.insertion_rate = insertion_rate;
+        self
+    }
+
+    pub fn deletion_rate(mut self, deletion_rate: f64) -> Self {
+        self.deletion_rate = deletion_rate;
+        self
+    }
+
+    fn total_rate(&self) -> f64 {
+        self.substitution_rate + self.insertion_rate + self.deletion_rate
+    }
+}
+
+/// Number of errors of each kind in a read
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct ErrorCounts {
+    pub substitutions: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+fn random_nt(rng: &mut impl Rng) -> DnaNt {
+    DnaNt::from_index(rng.random_range(0..DnaNt::all().len()))
+}
+
+/// Read sequenced from the start of a template
+struct Sequenced {
+    read: AString<DnaNt>,
+    /// Number of template bases the read spans
+    consumed: usize,
+    errors: ErrorCounts,
+}
+
+/// Sequences at most `len` bases from the start of `template`. The error rates at each read
+/// position are scaled by `scale`
+fn sequence(
+    template: &AStr<DnaNt>,
+    len: usize,
+    profile: ErrorProfile,
+    scale: impl Fn(usize) -> f64,
+    rng: &mut impl Rng,
+) -> Sequenced {
+    let mut res = Sequenced {
+        read: AString::with_capacity(len.min(template.len() + 1)),
+        consumed: 0,
+        errors: ErrorCounts::default(),
+    };
+    for &nt in template.iter() {
+        if res.read.len() >= len {
+            break;
+        }
+        res.consumed += 1;
+        let scale = scale(res.read.len());
+        let r: f64 = rng.random();
+        if r < profile.deletion_rate * scale {
+            res.errors.deletions += 1;
+            continue;
+        }
+        let r = r - profile.deletion_rate * scale;
+        if r < profile.insertion_rate * scale {
+            res.errors.insertions += 1;
+            res.read.push(random_nt(rng));
+            if res.read.len() == len {
+                // template base is not sequenced
+                res.consumed -= 1;
+                break;
+            }
+            res.read.push(nt);
+            continue;
+        }
+        let r = r - profile.insertion_rate * scale;
+        if r < profile.substitution_rate * scale {
+            res.errors.substitutions += 1;
+            let shift = rng.random_range(1..DnaNt::all().len());
+            res.read
+                .push(DnaNt::from_index((nt.index() + shift) % DnaNt::all().len()));
+        } else {
+            res.read.push(nt);
+        }
+    }
+    res
+}
+
+/// Copy of `seq` with substitution, insertion and deletion errors. Each base is independently
+/// deleted, preceded by a random inserted base or substituted by another base
+pub fn add_errors(seq: &AStr<DnaNt>, profile: ErrorProfile, rng: &mut impl Rng) -> AString<DnaNt> {
+    add_errors_counted(seq, profile, rng).0
+}
+
+/// Like [`add_errors`], also returning the number of errors of each kind
+pub fn add_errors_counted(
+    seq: &AStr<DnaNt>,
+    profile: ErrorProfile,
+    rng: &mut impl Rng,
+) -> (AString<DnaNt>, ErrorCounts) {
+    let sequenced = sequence(seq, usize::MAX, profile, |_| 1.0, rng);
+    (sequenced.read, sequenced.errors)
+}
+
+/// Normally distributed sample by the Box-Muller transform
+fn normal(rng: &mut impl Rng, mean: f64, sd: f64) -> f64 {
+    let u1: f64 = 1.0 - rng.random::<f64>();
+    let u2: f64 = rng.random();
+    mean + sd * (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+/// Normal distribution of fragment lengths for paired-end reads
+#[derive(Debug, Copy, Clone)]
+pub struct InsertSize {
+    pub mean: f64,
+    pub sd: f64,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ReadSimulatorProperties {
+    pub read_len: usize,
+    /// Average number of reads covering each genome position
+    pub depth: f64,
+    /// Reads are paired-end with this insert size if given, single-end otherwise
+    pub paired: Option<InsertSize>,
+    /// Fragments are sampled from both strands, otherwise only from the forward strand
+    pub both_strands: bool,
+    /// Error rates averaged over the read
+    pub errors: ErrorProfile,
+    /// Error rate at the last base of a read relative to the first base
+    pub error_rate_increase: f64,
+    /// Seed for the random number generator, or seeded from the OS if `None`
+    pub seed: Option<u64>,
+}
+
+impl ReadSimulatorProperties {
+    pub fn read_len(mut self, read_len: usize) -> Self {
+        self.read_len = read_len;
+        self
+    }
+
+    pub fn depth(mut self, depth: f64) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn paired(mut self, paired: Option<InsertSize>) -> Self {
+        self.paired = paired;
+        self
+    }
+
+    pub fn both_strands(mut self, both_strands: bool) -> Self {
+        self.both_strands = both_strands;
+        self
+    }
+
+    pub fn errors(mut self, errors: ErrorProfile) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn error_rate_increase(mut self, error_rate_increase: f64) -> Self {
+        self.error_rate_increase = error_rate_increase;
+        self
+    }
+
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Default for ReadSimulatorProperties {
+    fn default() -> Self {
+        Self {
+            read_len: 100,
+            depth: 10.0,
+            paired: None,
+            both_strands: true,
+            errors: ErrorProfile::default()
+                .substitution_rate(0.002)
+                .insertion_rate(0.0002)
+                .deletion_rate(0.0002),
+            error_rate_increase: 10.0,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Mate {
+    First,
+    Second,
+}
+
+/// Where a simulated read was sequenced from
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReadOrigin {
+    /// Genome positions spanned by the read
+    pub span: Range<usize>,
+    /// Strand the read was sequenced from. Reads from the reverse strand are the reverse
+    /// complement of the genome
+    pub strand: Strand,
+    /// Genome positions of the fragment the read was sequenced from
+    pub fragment: Range<usize>,
+    /// Mate of a paired-end read
+    pub mate: Option<Mate>,
+    pub errors: ErrorCounts,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedRead {
+    pub entry: FastqEntry<DnaNt>,
+    pub origin: ReadOrigin,
+}
+
+/// Simulates reads from `genome`. Reads are named `index:position:length` with the 1-based
+/// first genome position spanned by the read, and paired-end reads have the suffix `/1` or
+/// `/2`. Mates are adjacent in the returned reads
+pub fn simulate_reads(genome: &AStr<DnaNt>, props: ReadSimulatorProperties) -> Vec<SimulatedRead> {
+    let mut rng = match props.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    let read_len = props.read_len.min(genome.len());
+    if read_len == 0 {
+        return Vec::new();
+    }
+    let num_reads = (props.depth * genome.len() as f64 / read_len as f64).ceil() as usize;
+    let reads_per_fragment = if props.paired.is_some() { 2 } else { 1 };
+    let num_fragments = num_reads.div_ceil(reads_per_fragment);
+
+    // error rates increase exponentially along the read and average to the profile rates
+    let weights: Vec<f64> = (0..read_len)
+        .map(|pos| {
+            props
+                .error_rate_increase
+                .powf(pos as f64 / (read_len - 1).max(1) as f64)
+        })
+        .collect();
+    let mean_weight = weights.iter().sum::<f64>() / read_len as f64;
+    let scales: Vec<f64> = weights.iter().map(|weight| weight / mean_weight).collect();
+    let qualities: Vec<u8> = scales
+        .iter()
+        .map(|scale| quality(props.errors.total_rate() * scale))
+        .collect();
+
+    let mut reads = Vec::with_capacity(num_fragments * reads_per_fragment);
+    for fragment_idx in 0..num_fragments {
+        let fragment_len = match props.paired {
+            Some(insert_size) => (normal(&mut rng, insert_size.mean, insert_size.sd).round()
+                as usize)
+                .clamp(read_len, genome.len()),
+            None => read_len,
+        };
+        let start = rng.random_range(0..=genome.len() - fragment_len);
+        let fragment = start..start + fragment_len;
+        let strand = if props.both_strands && rng.random_bool(0.5) {
+            Strand::Reverse
+        } else {
+            Strand::Forward
+        };
+
+        let mates = match props.paired {
+            Some(_) => {
+                let other = match strand {
+                    Strand::Forward => Strand::Reverse,
+                    Strand::Reverse => Strand::Forward,
+                };
+                vec![(strand, Some(Mate::First)), (other, Some(Mate::Second))]
+            }
+            None => vec![(strand, None)],
+        };
+        for (strand, mate) in mates {
+            // reads are sequenced from the 5' end of the fragment on the strand, and may extend
+            // beyond the fragment when bases are deleted
+            let (template, template_start) = match strand {
+                Strand::Forward => {
+                    let end = (fragment.start + 2 * read_len).min(genome.len());
+                    (genome[fragment.start..end].to_owned(), fragment.start)
+                }
+                Strand::Reverse => {
+                    let start = fragment.end.saturating_sub(2 * read_len);
+                    (
+                        reverse_complement(&genome[start..fragment.end]),
+                        fragment.end,
+                    )
+                }
+            };
+            let sequenced = sequence(
+                &template,
+                read_len,
+                props.errors,
+                |pos| scales[pos],
+                &mut rng,
+            );
+            let span = match strand {
+                Strand::Forward => template_start..template_start + sequenced.consumed,
+                Strand::Reverse => template_start - sequenced.consumed..template_start,
+            };
+
+            let suffix = match mate {
+                None => "",
+                Some(Mate::First) => "/1",
+                Some(Mate::Second) => "/2",
+            };
+            let entry = FastqEntry {
+                description: format!(
+                    "{}:{}:{}{}",
+                    fragment_idx,
+                    span.start + 1,
+                    span.len(),
+                    suffix
+                ),
+                quality: qualities[..sequenced.read.len()].to_vec(),
+                polymer: sequenced.read,
+            };
+            reads.push(SimulatedRead {
+                entry,
+                origin: ReadOrigin {
+                    span,
+                    strand,
+                    fragment: fragment.clone(),
+                    mate,
+                    errors: sequenced.errors,
+                },
+            });
+        }
+    }
+
+    reads
+}
+
+/// Phred quality of a base with the given error probability
+fn quality(error_probability: f64) -> u8 {
+    if error_probability <= 0.0 {
+        return MAX_QUALITY;
+    }
+    (-10.0 * error_probability.log10())
+        .round()
+        .clamp(MIN_QUALITY as f64, MAX_QUALITY as f64) as u8
+}
+
+/// Writes the reads as FASTQ
+pub fn write_fastq<'a>(reads: impl IntoIterator<Item = &'a SimulatedRead>, write: &mut impl Write) {
+    for read in reads {
+        read.entry.write(write);
+    }
+}
+
+/// Writes the origin of each read as a tab separated line with name, 0-based start and end of
+/// the span, strand, fragment start and end and error counts
+pub fn write_truth<'a>(reads: impl IntoIterator<Item = &'a SimulatedRead>, write: &mut impl Write) {
+    writeln!(
+        write,
+        "#name\tstart\tend\tstrand\tfragment_start\tfragment_end\tsubstitutions\tinsertions\tdeletions"
+    )
+    .unwrap();
+    for read in reads {
+        let origin = &read.origin;
+        let strand = match origin.strand {
+            Strand::Forward => '+',
+            Strand::Reverse => '-',
+        };
+        writeln!(
+            write,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            read.entry.description,
+            origin.span.start,
+            origin.span.end,
+            strand,
+            origin.fragment.start,
+            origin.fragment.end,
+            origin.errors.substitutions,
+            origin.errors.insertions,
+            origin.errors.deletions
+        )
+        .unwrap();
+    }
+}
+
+/// Empirical coverage of a genome by reads
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageStats {
+    pub genome_len: usize,
+    pub num_reads: usize,
+    /// Number of genome positions covered by at least one read
+    pub bases_covered: usize,
+    pub avg_depth: f64,
+    pub var_depth: f64,
+    /// Number of maximal groups of reads chained by overlaps of at least the minimum overlap
+    pub num_islands: usize,
+}
+
+/// Coverage of a genome by reads spanning `spans`. Reads belong to the same island when they
+/// overlap by at least `min_overlap` positions
+pub fn coverage_stats(
+    genome_len: usize,
+    spans: &[Range<usize>],
+    min_overlap: usize,
+) -> CoverageStats {
+    // number of reads starting and ending at each position, where the ends of reads are
+    // moved `min_overlap` positions back when counting islands
+    let mut starts_ends = vec![(0isize, 0isize, 0isize); genome_len + 1];
+    for span in spans.iter().filter(|span| !span.is_empty()) {
+        starts_ends[span.start].0 += 1;
+        starts_ends[span.end].1 += 1;
+        let island_end = span.end.saturating_sub(min_overlap).max(span.start + 1);
+        starts_ends[island_end].2 += 1;
+    }
+
+    let mut depth = 0;
+    let mut island_depth = 0;
+    let mut on_island = false;
+    let mut depths = Vec::with_capacity(genome_len);
+    let mut num_islands = 0;
+    for &(starts, ends, island_ends) in &starts_ends[..genome_len] {
+        depth += starts - ends;
+        island_depth += starts - island_ends;
+        depths.push(depth as usize);
+        if island_depth > 0 && !on_island {
+            num_islands += 1;
+        }
+        on_island = island_depth > 0;
+    }
+
+    let avg_depth = spans.iter().map(|span| span.len()).sum::<usize>() as f64 / genome_len as f64;
+    let var_depth = depths
+        .iter()
+        .map(|&depth| (depth as f64 - avg_depth).powi(2))
+        .sum::<f64>()
+        / (genome_len.max(2) - 1) as f64;
+
+    CoverageStats {
+        genome_len,
+        num_reads: spans.len(),
+        bases_covered: depths.iter().filter(|&&depth| depth != 0).count(),
+        avg_depth,
+        var_depth,
+        num_islands,
+    }
+}
+
+/// Expected coverage by the Lander-Waterman model
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanderWaterman {
+    /// Average number of reads covering each genome position
+    pub coverage: f64,
+    pub expected_bases_covered: f64,
+    pub expected_islands: f64,
+    pub expected_reads_per_island: f64,
+    pub expected_island_len: f64,
+}
+
+/// Lander-Waterman expectations for `num_reads` reads of length `read_len` placed uniformly in
+/// a genome, where reads overlapping by at least a fraction `theta` of the read length are
+/// detected
+pub fn lander_waterman(
+    genome_len: usize,
+    num_reads: usize,
+    read_len: usize,
+    theta: f64,
+) -> LanderWaterman {
+    let coverage = (num_reads * read_len) as f64 / genome_len as f64;
+    let sigma = 1.0 - theta;
+    LanderWaterman {
+        coverage,
+        expected_bases_covered: genome_len as f64 * (1.0 - (-coverage).exp()),
+        expected_islands: num_reads as f64 * (-coverage * sigma).exp(),
+        expected_reads_per_island: (coverage * sigma).exp(),
+        expected_island_len: read_len as f64
+            * (((coverage * sigma).exp() - 1.0) / coverage + 1.0 - sigma),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::string::alignment::global_alignment_wagner_fischer::global_alignment;
+    use crate::string::alignment::{AlignmentProperties, Edit};
+    use crate::string_model::test_util::random_dna;
+    use crate::util::fastq_entries;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_add_errors() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let seq: AString<DnaNt> = (0..2000).map(|_| random_nt(&mut rng)).collect();
+
+        assert_eq!(add_errors(&seq, ErrorProfile::default(), &mut rng), seq);
+
+        let substituted = add_errors(
+            &seq,
+            ErrorProfile::default().substitution_rate(0.05),
+            &mut rng,
+        );
+        assert_eq!(substituted.len(), seq.len());
+        let mismatches = seq
+            .iter()
+            .zip(substituted.iter())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!((50..150).contains(&mismatches), "{}", mismatches);
+
+        let profile = ErrorProfile::default()
+            .insertion_rate(0.05)
+            .deletion_rate(0.05);
+        let with_indels = add_errors(&seq, profile, &mut rng);
+        let align = global_alignment(&seq, &with_indels, AlignmentProperties::default());
+        let count = |edit: Edit| align.edits.iter().filter(|&&e| e == edit).count();
+        assert!((50..250).contains(&(count(Edit::Insert) + count(Edit::Delete))));
+    }
+
+    #[test]
+    fn test_simulate_single_end_reads() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let genome = random_dna(&mut rng, 300);
+        let props = ReadSimulatorProperties::default()
+            .read_len(50)
+            .depth(100.0)
+            .errors(ErrorProfile::default())
+            .seed(Some(3));
+        let reads = simulate_reads(&genome, props);
+
+        assert_eq!(reads.len(), 600);
+        for read in &reads {
+            let origin = &read.origin;
+            assert_eq!(origin.span, origin.fragment);
+            assert_eq!(origin.mate, None);
+            assert_eq!(origin.errors, ErrorCounts::default());
+            let expected = match origin.strand {
+                Strand::Forward => genome[origin.span.clone()].to_owned(),
+                Strand::Reverse => reverse_complement(&genome[origin.span.clone()]),
+            };
+            assert_eq!(read.entry.polymer, expected);
+            assert_eq!(read.entry.quality, vec![MAX_QUALITY; 50]);
+            assert!(
+                read.entry
+                    .description
+                    .ends_with(&format!(":{}:50", origin.span.start + 1))
+            );
+        }
+        // the first and last windows are sampled
+        assert!(reads.iter().any(|read| read.origin.span.start == 0));
+        assert!(
+            reads
+                .iter()
+                .any(|read| read.origin.span.end == genome.len())
+        );
+        let forward = reads
+            .iter()
+            .filter(|read| read.origin.strand == Strand::Forward)
+            .count();
+        assert!((200..400).contains(&forward), "{}", forward);
+
+        // seeded simulations are reproducible
+        let polymers = |reads: Vec<SimulatedRead>| {
+            reads
+                .into_iter()
+                .map(|read| read.entry.polymer)
+                .collect_vec()
+        };
+        assert_eq!(
+            polymers(simulate_reads(&genome, props)),
+            polymers(reads.clone())
+        );
+        assert_ne!(
+            polymers(simulate_reads(&genome, props.seed(Some(4)))),
+            polymers(reads)
+        );
+    }
+
+    #[test]
+    fn test_simulate_paired_end_reads() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let genome = random_dna(&mut rng, 5000);
+        let props = ReadSimulatorProperties::default()
+            .read_len(100)
+            .depth(20.0)
+            .paired(Some(InsertSize {
+                mean: 400.0,
+                sd: 30.0,
+            }))
+            .errors(ErrorProfile::default())
+            .seed(Some(6));
+        let reads = simulate_reads(&genome, props);
+
+        assert_eq!(reads.len(), 1000);
+        for (first, second) in reads.iter().tuples() {
+            assert_eq!(first.origin.mate, Some(Mate::First));
+            assert_eq!(second.origin.mate, Some(Mate::Second));
+            assert_eq!(first.origin.fragment, second.origin.fragment);
+            assert_ne!(first.origin.strand, second.origin.strand);
+            assert!(first.entry.description.ends_with("/1"));
+            assert!(second.entry.description.ends_with("/2"));
+
+            // mates are sequenced from opposite ends of the fragment
+            let fragment = &first.origin.fragment;
+            for read in [first, second] {
+                match read.origin.strand {
+                    Strand::Forward => assert_eq!(read.origin.span.start, fragment.start),
+                    Strand::Reverse => assert_eq!(read.origin.span.end, fragment.end),
+                }
+            }
+        }
+        let fragment_lens = reads
+            .iter()
+            .step_by(2)
+            .map(|read| read.origin.fragment.len() as f64)
+            .collect_vec();
+        let mean = fragment_lens.iter().sum::<f64>() / fragment_lens.len() as f64;
+        let sd = (fragment_lens
+            .iter()
+            .map(|len| (len - mean).powi(2))
+            .sum::<f64>()
+            / fragment_lens.len() as f64)
+            .sqrt();
+        assert!((mean - 400.0).abs() < 5.0, "{}", mean);
+        assert!((sd - 30.0).abs() < 5.0, "{}", sd);
+    }
+
+    #[test]
+    fn test_simulate_reads_with_errors() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let genome = random_dna(&mut rng, 10000);
+        let errors = ErrorProfile::default()
+            .substitution_rate(0.01)
+            .insertion_rate(0.002)
+            .deletion_rate(0.002);
+        let props = ReadSimulatorProperties::default()
+            .read_len(100)
+            .depth(20.0)
+            .errors(errors)
+            .error_rate_increase(10.0)
+            .seed(Some(8));
+        let reads = simulate_reads(&genome, props);
+
+        // reads have full length unless they reach the end of the genome
+        for read in &reads {
+            let origin = &read.origin;
+            if origin.span.start != 0 && origin.span.end != genome.len() {
+                assert_eq!(read.entry.polymer.len(), 100);
+            }
+            let template = match origin.strand {
+                Strand::Forward => genome[origin.span.clone()].to_owned(),
+                Strand::Reverse => reverse_complement(&genome[origin.span.clone()]),
+            };
+            assert_eq!(
+                template.len() + origin.errors.insertions,
+                read.entry.polymer.len() + origin.errors.deletions
+            );
+        }
+
+        let num_bases = (reads.len() * 100) as f64;
+        let substitutions: usize = reads
+            .iter()
+            .map(|read| read.origin.errors.substitutions)
+            .sum();
+        let substitution_rate = substitutions as f64 / num_bases;
+        assert!(
+            (substitution_rate - 0.01).abs() < 0.002,
+            "{}",
+            substitution_rate
+        );
+
+        // qualities decrease along the read, from 24 to 14 for a total error rate of 0.014
+        // increasing by a factor of 10
+        let quality = &reads[0].entry.quality;
+        assert!(quality.iter().tuple_windows().all(|(q1, q2)| q1 >= q2));
+        assert_eq!((quality[0], quality[99]), (24, 14));
+    }
+
+    #[test]
+    fn test_write_fastq_and_truth() {
+        let genome: AString<DnaNt> = "ACGTACGTTT".parse().unwrap();
+        let props = ReadSimulatorProperties::default()
+            .read_len(4)
+            .depth(0.4)
+            .both_strands(false)
+            .errors(ErrorProfile::default())
+            .seed(Some(1));
+        let reads = simulate_reads(&genome, props);
+        assert_eq!(reads.len(), 1);
+        let start = reads[0].origin.span.start;
+
+        let mut fastq = Vec::new();
+        write_fastq(&reads, &mut fastq);
+        let entries = fastq_entries::<DnaNt>(&String::from_utf8(fastq).unwrap()).collect_vec();
+        assert_eq!(entries[0].description, format!("0:{}:4", start + 1));
+        assert_eq!(entries[0].polymer, genome[start..start + 4].to_owned());
+        assert_eq!(entries[0].quality, vec![MAX_QUALITY; 4]);
+
+        let mut truth = Vec::new();
+        write_truth(&reads, &mut truth);
+        let truth = String::from_utf8(truth).unwrap();
+        assert_eq!(
+            truth.lines().nth(1).unwrap(),
+            format!(
+                "0:{}:4\t{}\t{}\t+\t{}\t{}\t0\t0\t0",
+                start + 1,
+                start,
+                start + 4,
+                start,
+                start + 4
+            )
+        );
+    }
+
+    #[test]
+    fn test_coverage_stats() {
+        let spans = [0..4, 2..6, 5..9, 12..14];
+        let stats = coverage_stats(15, &spans, 2);
+        assert_eq!(stats.num_reads, 4);
+        assert_eq!(stats.bases_covered, 11);
+        assert_eq!(stats.avg_depth, 14.0 / 15.0);
+        // 0..4 and 2..6 overlap by 2, 2..6 and 5..9 by only 1
+        assert_eq!(stats.num_islands, 3);
+        assert_eq!(coverage_stats(15, &spans, 1).num_islands, 2);
+        assert_eq!(coverage_stats(15, &[], 1).num_islands, 0);
+    }
+
+    #[test]
+    fn test_lander_waterman() {
+        let expected = lander_waterman(10000, 125, 200, 0.1);
+        assert_eq!(expected.coverage, 2.5);
+        assert!((expected.expected_bases_covered - 9179.15).abs() < 0.01);
+        assert!((expected.expected_islands - 13.17).abs() < 0.01);
+        assert!((expected.expected_reads_per_island - 9.49).abs() < 0.01);
+        assert!((expected.expected_island_len - 699.02).abs() < 0.01);
+
+        // simulated coverage is close to the expectation
+        let mut rng = StdRng::seed_from_u64(9);
+        let genome = random_dna(&mut rng, 100000);
+        let props = ReadSimulatorProperties::default()
+            .read_len(100)
+            .depth(3.0)
+            .seed(Some(10));
+        let spans = simulate_reads(&genome, props)
+            .into_iter()
+            .map(|read| read.origin.span)
+            .collect_vec();
+        let stats = coverage_stats(genome.len(), &spans, 10);
+        let expected = lander_waterman(genome.len(), spans.len(), 100, 0.1);
+        let relative_diff = |x: f64, y: f64| (x - y).abs() / y;
+        assert!(relative_diff(stats.bases_covered as f64, expected.expected_bases_covered) < 0.01);
+        assert!(relative_diff(stats.num_islands as f64, expected.expected_islands) < 0.1);
+    }
+}
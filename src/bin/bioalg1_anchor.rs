@@ -0,0 +1,51 @@
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::string::maximal_match::mums_in_trie;
+use bioinformatics::string::suffix_trie_mcc_arena::build_trie_with_allocator;
+use bioinformatics::util::fasta_polymers_file;
+use bumpalo::Bump;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+
+fn main() {
+    // anchors the contigs from bioalg1_assemble on the genome from bioalg1_gen, as the first
+    // step of a whole genome alignment
+    let reference_path = "src/bin/bioalg1_seq_sim_data.txt";
+    let contigs_path = "src/bin/bioalg1_assemble_out.txt";
+    let output_path = "src/bin/bioalg1_anchor_out.txt";
+    let min_len = 20;
+
+    let reference = fasta_polymers_file::<DnaNt>(reference_path)
+        .next()
+        .unwrap()
+        .polymer;
+
+    let start = Instant::now();
+    let bump = Bump::new();
+    let trie = build_trie_with_allocator(&reference, &bump);
+    println!("build trie elapsed: {:?}", start.elapsed());
+
+    let mut out_file = BufWriter::new(File::create(output_path).unwrap());
+    writeln!(out_file, "#contig\tcontig_start\treference_start\tlen").unwrap();
+    let start = Instant::now();
+    for contig in fasta_polymers_file::<DnaNt>(contigs_path) {
+        let anchors = mums_in_trie(&trie, &contig.polymer, min_len);
+        let anchored_len: usize = anchors.iter().map(|anchor| anchor.len).sum();
+        println!(
+            "{}: {} anchors covering {} of {}",
+            contig.description,
+            anchors.len(),
+            anchored_len,
+            contig.polymer.len()
+        );
+        for anchor in anchors {
+            writeln!(
+                out_file,
+                "{}\t{}\t{}\t{}",
+                contig.description, anchor.t_start, anchor.s_start, anchor.len
+            )
+            .unwrap();
+        }
+    }
+    println!("anchor elapsed: {:?}", start.elapsed());
+}
@@ -0,0 +1,25 @@
+use bioinformatics::assembly::de_bruijn::{DeBruijnProperties, assemble};
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::util::fasta_polymers_file;
+use itertools::Itertools;
+use std::fs::File;
+use std::io::Write;
+use std::time::Instant;
+
+fn main() {
+    let input_path = "src/bin/bioalg1_assemble_data.txt";
+    let output_path = "src/bin/bioalg1_assemble_dbg_out.txt";
+
+    let polymers = fasta_polymers_file::<DnaNt>(input_path).collect_vec();
+
+    let start = Instant::now();
+    let props = DeBruijnProperties::default().k(21).min_kmer_count(1);
+    let contigs = assemble(polymers.iter().map(|pol| pol.polymer.as_str()), props);
+    println!("assemble elapsed: {:?}", start.elapsed());
+
+    let mut file = File::create(output_path).unwrap();
+    for (idx, contig) in contigs.iter().enumerate() {
+        writeln!(file, ">{}:{}:{:.1}", idx, contig.seq.len(), contig.coverage).unwrap();
+        writeln!(file, "{}", contig.seq).unwrap();
+    }
+}
@@ -0,0 +1,28 @@
+use bioinformatics::assembly::olc::{OlcProperties, assemble};
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::util::fasta_polymers_file;
+use itertools::Itertools;
+use std::fs::File;
+use std::io::Write;
+use std::time::Instant;
+
+fn main() {
+    let input_path = "src/bin/bioalg1_assemble_olc_data.txt";
+    let output_path = "src/bin/bioalg1_assemble_olc_out.txt";
+
+    let polymers = fasta_polymers_file::<DnaNt>(input_path).collect_vec();
+
+    let start = Instant::now();
+    let reads = polymers
+        .iter()
+        .map(|pol| pol.polymer.as_str())
+        .collect_vec();
+    let contigs = assemble(&reads, OlcProperties::default());
+    println!("assemble elapsed: {:?}", start.elapsed());
+
+    let mut file = File::create(output_path).unwrap();
+    for (idx, contig) in contigs.iter().enumerate() {
+        writeln!(file, ">{}:{}:{:.1}", idx, contig.seq.len(), contig.coverage).unwrap();
+        writeln!(file, "{}", contig.seq).unwrap();
+    }
+}
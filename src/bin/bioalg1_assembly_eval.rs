@@ -0,0 +1,53 @@
+use bioinformatics::assembly::evaluation::{EvaluationProperties, evaluate};
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::util::fasta_polymers_file;
+use itertools::Itertools;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+fn main() {
+    let reference_path = "src/bin/bioalg1_seq_sim_data.txt";
+    let assembly_paths = [
+        ("scs", "src/bin/bioalg1_assemble_out.txt"),
+        ("dbg", "src/bin/bioalg1_assemble_dbg_out.txt"),
+        ("olc", "src/bin/bioalg1_assemble_olc_out.txt"),
+    ];
+    let output_path = "src/bin/bioalg1_assembly_eval_out.txt";
+    let json_output_path = "src/bin/bioalg1_assembly_eval_out.json";
+
+    let reference = fasta_polymers_file::<DnaNt>(reference_path)
+        .next()
+        .unwrap()
+        .polymer;
+
+    let mut out_file = File::create(output_path).unwrap();
+    let mut json_out_file = File::create(json_output_path).unwrap();
+    let mut json_reports = Vec::new();
+    for (name, path) in assembly_paths {
+        if !Path::new(path).exists() {
+            println!("{} not found, skipping {}", path, name);
+            continue;
+        }
+        let contigs = fasta_polymers_file::<DnaNt>(path)
+            .map(|entry| entry.polymer)
+            .collect_vec();
+
+        let start = Instant::now();
+        let report = evaluate(&contigs, Some(&reference), EvaluationProperties::default());
+        println!("evaluate {} elapsed: {:?}", name, start.elapsed());
+
+        writeln!(out_file, "{:<28}{}", "Assembly", name).unwrap();
+        writeln!(out_file, "{}", report).unwrap();
+
+        let mut json = Vec::new();
+        report.write_json(&mut json);
+        json_reports.push(format!(
+            "\"{}\": {}",
+            name,
+            String::from_utf8(json).unwrap().trim_end()
+        ));
+    }
+    writeln!(json_out_file, "{{{}}}", json_reports.join(",\n")).unwrap();
+}
@@ -0,0 +1,42 @@
+use bioinformatics::assembly::correction::{CorrectionProperties, ErrorCorrector};
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::util::fastq_entries_file;
+use itertools::Itertools;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+fn main() {
+    let input_path = "src/bin/bioalg1_seq_sim_out.fastq";
+    let output_path = "src/bin/bioalg1_correct_out.fastq";
+    let report_output_path = "src/bin/bioalg1_correct_report.txt";
+
+    let reads = fastq_entries_file::<DnaNt>(input_path).collect_vec();
+    // the simulated reads are from the forward strand. The solid threshold is taken from the
+    // spectrum, which needs a depth of 10x or more in bioalg1_seq_sim to separate errors
+    let props = CorrectionProperties::default()
+        .k(21)
+        .canonical(false)
+        .parallel(true);
+    let corrector = ErrorCorrector::new(&reads, props);
+    println!("solid k-mer threshold: {}", corrector.solid_threshold());
+
+    let mut out_file = BufWriter::new(File::create(output_path).unwrap());
+    let mut report_file = BufWriter::new(File::create(report_output_path).unwrap());
+    let mut corrected_reads = 0;
+    let mut corrections = 0;
+    let mut weak_reads = 0;
+    for (read, correction) in corrector.correct_entries(reads) {
+        read.write(&mut out_file);
+        writeln!(report_file, "{}\t{}", read.description, correction).unwrap();
+        if !correction.corrections.is_empty() {
+            corrected_reads += 1;
+            corrections += correction.corrections.len();
+        }
+        if correction.weak_kmers > 0 {
+            weak_reads += 1;
+        }
+    }
+    println!("corrected reads: {}", corrected_reads);
+    println!("corrections: {}", corrections);
+    println!("reads with weak k-mers: {}", weak_reads);
+}
@@ -0,0 +1,40 @@
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::string::complexity::{
+    LowComplexityProperties, Masking, low_complexity_regions, mask,
+};
+use bioinformatics::string::lz77::lz77_factorization;
+use bioinformatics::util::fasta_polymers_file;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+
+fn main() {
+    // genome from bioalg1_gen, soft masked for seeding alignments
+    let input_path = "src/bin/bioalg1_seq_sim_data.txt";
+    let output_path = "src/bin/bioalg1_dust_out.txt";
+
+    let mut out_file = BufWriter::new(File::create(output_path).unwrap());
+    for entry in fasta_polymers_file::<DnaNt>(input_path) {
+        let genome = entry.polymer;
+
+        let start = Instant::now();
+        let regions = low_complexity_regions(&genome, LowComplexityProperties::default());
+        println!("mask elapsed: {:?}", start.elapsed());
+        let masked_len: usize = regions.iter().map(|region| region.len()).sum();
+        println!(
+            "{}: {} low complexity regions, {} of {} masked",
+            entry.description,
+            regions.len(),
+            masked_len,
+            genome.len()
+        );
+
+        let start = Instant::now();
+        let factors = lz77_factorization(&genome);
+        println!("lz77 elapsed: {:?}", start.elapsed());
+        println!("lz77 factors: {}", factors.len());
+
+        writeln!(out_file, ">{}", entry.description).unwrap();
+        writeln!(out_file, "{}", mask(&genome, &regions, Masking::Lowercase)).unwrap();
+    }
+}
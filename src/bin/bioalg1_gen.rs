@@ -1,34 +1,44 @@
+use bioinformatics::assembly::genome::{
+    GenomeProperties, MarkovModel, RepeatProperties, generate_genome,
+};
+use bioinformatics::assembly::simulation::ErrorProfile;
 use bioinformatics::polymers::DnaNt;
-use bioinformatics::string_model::AString;
-use rand::Rng;
+use bioinformatics::util::fasta_polymers_file;
+use itertools::Itertools;
 use std::fs::File;
 use std::io::Write;
 
 fn main() {
     let length = 10000;
-    let output_path = "src/bin/bioalg1_seq_sim_data.txt";
+    // FASTA to train an order 3 model on, or bases are independent with the GC ratio
+    let training_path: Option<&str> = None;
     let gc = 0.4;
+    let output_path = "src/bin/bioalg1_seq_sim_data.txt";
+    let annotation_output_path = "src/bin/bioalg1_gen_repeats.txt";
+
+    let model = match training_path {
+        Some(path) => {
+            let training = fasta_polymers_file::<DnaNt>(path)
+                .map(|entry| entry.polymer)
+                .collect_vec();
+            MarkovModel::train(&training, 3, 1.0)
+        }
+        None => MarkovModel::iid(gc),
+    };
+    let divergence = ErrorProfile::default()
+        .substitution_rate(0.01)
+        .insertion_rate(0.002)
+        .deletion_rate(0.002);
+    // the assemblers assume reads from the forward strand, so repeats are not inverted
+    let props = GenomeProperties::default()
+        .len(length)
+        .model(model)
+        .repeat(RepeatProperties::tandem(40, 6))
+        .repeat(RepeatProperties::interspersed(300, 3).divergence(divergence));
+    let genome = generate_genome(&props);
 
     let mut file = File::create(output_path).unwrap();
     writeln!(file, ">generated dna").unwrap();
-    let mut rng = rand::rng();
-    let data: AString<_> = (0..length)
-        .map(|_| {
-            #[allow(clippy::collapsible_else_if)]
-            if rng.random::<f64>() < gc {
-                if rng.random::<bool>() {
-                    DnaNt::C
-                } else {
-                    DnaNt::G
-                }
-            } else {
-                if rng.random::<bool>() {
-                    DnaNt::A
-                } else {
-                    DnaNt::T
-                }
-            }
-        })
-        .collect();
-    writeln!(file, "{}", data).unwrap();
+    writeln!(file, "{}", genome.seq).unwrap();
+    genome.write_annotation(&mut File::create(annotation_output_path).unwrap());
 }
@@ -0,0 +1,34 @@
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::string::kmer::{KmerCounterProperties, count_canonical_kmers};
+use bioinformatics::util::{fasta_polymers_file, print_histogram};
+use itertools::Itertools;
+use std::fs::File;
+use std::io::BufWriter;
+
+fn main() {
+    let k = 21;
+    let input_path = "src/bin/bioalg1_assemble_data.txt";
+    let output_path = "src/bin/bioalg1_kmer_spectrum_out.txt";
+
+    let reads = fasta_polymers_file::<DnaNt>(input_path)
+        .map(|entry| entry.polymer)
+        .collect_vec();
+    let props = KmerCounterProperties::default().k(k).parallel(true);
+    let counts = count_canonical_kmers(&reads, props);
+    println!("distinct {}-mers: {}", k, counts.len());
+    println!("total {}-mers: {}", k, counts.total());
+
+    let spectrum = counts.spectrum();
+    print_histogram("k-mer count", &spectrum.histogram());
+    spectrum.write(&mut BufWriter::new(File::create(output_path).unwrap()));
+
+    match spectrum.estimate() {
+        Some(estimate) => {
+            println!("error threshold: {}", estimate.error_threshold);
+            println!("k-mer coverage: {:.2}", estimate.coverage);
+            println!("genome size: {:.0}", estimate.genome_size);
+            println!("heterozygosity: {:.5}", estimate.heterozygosity);
+        }
+        None => println!("no peak in the spectrum, coverage too low for estimates"),
+    }
+}
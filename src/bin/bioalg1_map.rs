@@ -0,0 +1,68 @@
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::string::read_mapper::{Mapper, MapperProperties, sam_record};
+use bioinformatics::util::sam::{SamHeader, SamProgram, SamReference};
+use bioinformatics::util::{fasta_polymers_file, fastq_entries_file};
+use std::fs::File;
+use std::io::BufWriter;
+use std::time::Instant;
+
+fn main() {
+    let reference_path = "src/bin/bioalg1_seq_sim_data.txt";
+    let reads_path = "src/bin/bioalg1_seq_sim_out.fastq";
+    let output_path = "src/bin/bioalg1_map_out.sam";
+    let reference_name = "genome";
+    // indels near the start of a read shift its mapped position
+    let max_position_offset = 10;
+
+    let reference = fasta_polymers_file::<DnaNt>(reference_path)
+        .next()
+        .unwrap()
+        .polymer;
+    let mapper = Mapper::new(reference, MapperProperties::default());
+
+    let mut out_file = BufWriter::new(File::create(output_path).unwrap());
+    let header = SamHeader {
+        references: vec![SamReference {
+            name: reference_name.to_string(),
+            len: mapper.reference().len(),
+        }],
+        programs: vec![SamProgram {
+            id: "bioalg1_map".to_string(),
+            name: Some("bioalg1_map".to_string()),
+            ..Default::default()
+        }],
+    };
+    header.write(&mut out_file);
+
+    let start = Instant::now();
+    let mut num_reads = 0;
+    let mut num_mapped = 0;
+    let mut num_correct = 0;
+    for read in fastq_entries_file::<DnaNt>(reads_path) {
+        let mapping = mapper.map(&read.polymer);
+
+        // read descriptions are "index:position:length" with 1-based position
+        let true_position: usize = read.description.split(':').nth(1).unwrap().parse().unwrap();
+        num_reads += 1;
+        if let Some(mapping) = &mapping {
+            num_mapped += 1;
+            if (mapping.position + 1).abs_diff(true_position) <= max_position_offset {
+                num_correct += 1;
+            }
+        }
+
+        sam_record(
+            reference_name,
+            &read.description,
+            &read.polymer,
+            Some(&read.quality),
+            mapping.as_ref(),
+        )
+        .write(&mut out_file);
+    }
+    println!("map elapsed: {:?}", start.elapsed());
+    println!(
+        "reads: {}, mapped: {}, correct position: {}",
+        num_reads, num_mapped, num_correct
+    );
+}
@@ -0,0 +1,43 @@
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::string::repeats::{TandemRepeatProperties, tandem_repeats};
+use bioinformatics::util::fasta_polymers_file;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+fn main() {
+    // genome from bioalg1_gen, compare with the planted repeats in bioalg1_gen_repeats.txt
+    let input_path = "src/bin/bioalg1_seq_sim_data.txt";
+    let output_path = "src/bin/bioalg1_repeats_out.txt";
+
+    let genome = fasta_polymers_file::<DnaNt>(input_path)
+        .next()
+        .unwrap()
+        .polymer;
+
+    let microsatellites = tandem_repeats(&genome, TandemRepeatProperties::default());
+    let minisatellites = tandem_repeats(
+        &genome,
+        TandemRepeatProperties::default()
+            .min_period(7)
+            .max_period(100)
+            .min_copies(2.0)
+            .min_len(20),
+    );
+    println!("microsatellites: {}", microsatellites.len());
+    println!("minisatellites: {}", minisatellites.len());
+
+    let mut out_file = BufWriter::new(File::create(output_path).unwrap());
+    writeln!(out_file, "#start\tend\tperiod\tcopies\tunit").unwrap();
+    for run in microsatellites.iter().chain(&minisatellites) {
+        writeln!(
+            out_file,
+            "{}\t{}\t{}\t{:.1}\t{}",
+            run.start,
+            run.end,
+            run.period,
+            run.copies(),
+            run.unit(&genome)
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+use bioinformatics::assembly::simulation::{ErrorProfile, add_errors};
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::string::bwt::build_bwt;
+use bioinformatics::string::r_index::build_r_index;
+use bioinformatics::string_model::AString;
+use bioinformatics::util::fasta_polymers_file;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::time::Instant;
+
+fn main() {
+    // strains of the genome from bioalg1_gen with a few point mutations each, indexed together
+    let input_path = "src/bin/bioalg1_seq_sim_data.txt";
+    let strain_count = 20;
+    let mutations = ErrorProfile::default()
+        .substitution_rate(0.001)
+        .insertion_rate(0.0001)
+        .deletion_rate(0.0001);
+    let kmer_len = 12;
+
+    let genome = fasta_polymers_file::<DnaNt>(input_path)
+        .next()
+        .unwrap()
+        .polymer;
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut collection = genome.to_vec();
+    for _ in 1..strain_count {
+        collection.extend_from_slice(&add_errors(&genome, mutations, &mut rng));
+    }
+    let collection = AString::from(collection);
+
+    let start = Instant::now();
+    let r_index = build_r_index(&collection);
+    println!("build r-index elapsed: {:?}", start.elapsed());
+    let stats = r_index.stats();
+    println!(
+        "n: {}, r: {}, n/r: {:.1}",
+        stats.len,
+        stats.runs,
+        stats.avg_run_len()
+    );
+
+    let bwt = build_bwt(&collection);
+    let kmers = (0..genome.len() - kmer_len)
+        .step_by(100)
+        .map(|start| &genome[start..start + kmer_len])
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let bwt_occurrences: usize = kmers
+        .iter()
+        .map(|kmer| bwt.indexes_substr(kmer).len())
+        .sum();
+    println!("bwt locate elapsed: {:?}", start.elapsed());
+    let start = Instant::now();
+    let r_index_occurrences: usize = kmers
+        .iter()
+        .map(|kmer| r_index.indexes_substr(kmer).len())
+        .sum();
+    println!("r-index locate elapsed: {:?}", start.elapsed());
+    assert_eq!(bwt_occurrences, r_index_occurrences);
+    println!(
+        "occurrences of {} kmers: {}",
+        kmers.len(),
+        r_index_occurrences
+    );
+}
@@ -0,0 +1,57 @@
+use bioinformatics::assembly::scaffold::{ScaffoldProperties, scaffold};
+use bioinformatics::assembly::simulation::{InsertSize, ReadSimulatorProperties, simulate_reads};
+use bioinformatics::polymers::DnaNt;
+use bioinformatics::util::fasta_polymers_file;
+use itertools::Itertools;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+fn main() {
+    let genome_path = "src/bin/bioalg1_seq_sim_data.txt";
+    let contigs_path = "src/bin/bioalg1_assemble_olc_out.txt";
+    let output_path = "src/bin/bioalg1_scaffold_out.txt";
+    let insert_size = InsertSize {
+        mean: 1000.0,
+        sd: 100.0,
+    };
+
+    let genome = fasta_polymers_file::<DnaNt>(genome_path)
+        .next()
+        .unwrap()
+        .polymer;
+    let contigs = fasta_polymers_file::<DnaNt>(contigs_path)
+        .map(|entry| entry.polymer)
+        .collect_vec();
+
+    // mates are adjacent in the simulated reads
+    let read_props = ReadSimulatorProperties::default()
+        .read_len(100)
+        .depth(10.0)
+        .paired(Some(insert_size));
+    let pairs = simulate_reads(&genome, read_props)
+        .into_iter()
+        .map(|read| read.entry.polymer)
+        .tuples()
+        .collect_vec();
+
+    let props = ScaffoldProperties::default().insert_size(insert_size);
+    let scaffolds = scaffold(&contigs, &pairs, props);
+    println!("contigs: {}, scaffolds: {}", contigs.len(), scaffolds.len());
+
+    let mut file = BufWriter::new(File::create(output_path).unwrap());
+    for (idx, scaffold) in scaffolds.iter().enumerate() {
+        let layout = scaffold
+            .parts
+            .iter()
+            .map(|part| {
+                format!(
+                    "{}{:?}:{}±{}:{}",
+                    part.contig, part.strand, part.gap, part.gap_sd, part.links
+                )
+            })
+            .join(",");
+        let seq = scaffold.sequence(&contigs, props.min_gap);
+        writeln!(file, ">{}:{}:{}", idx, seq.len(), layout).unwrap();
+        writeln!(file, "{}", seq).unwrap();
+    }
+}
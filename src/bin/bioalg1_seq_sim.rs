@@ -1,99 +1,99 @@
+use bioinformatics::assembly::simulation::{
+    ErrorProfile, ReadSimulatorProperties, coverage_stats, lander_waterman, simulate_reads,
+    write_fastq, write_truth,
+};
 use bioinformatics::polymers::DnaNt;
 use bioinformatics::util::fasta_polymers_file;
-use rand::Rng;
+use itertools::Itertools;
 use std::fs::File;
-use std::io::Write;
-use std::iter;
+use std::io::{BufWriter, Write};
 
 fn main() {
     let read_len = 200;
-    let target_depth = 2.5;
     let theta = 0.1;
     let input_path = "src/bin/bioalg1_seq_sim_data.txt";
     let output_path = "src/bin/bioalg1_assemble_data.txt";
+    let errors_output_path = "src/bin/bioalg1_assemble_olc_data.txt";
+    let fastq_output_path = "src/bin/bioalg1_seq_sim_out.fastq";
+    let truth_output_path = "src/bin/bioalg1_seq_sim_truth.txt";
     let stats_output_path = "src/bin/bioalg1_assemble_data_stats.txt";
 
     let polymer = fasta_polymers_file::<DnaNt>(input_path)
         .next()
         .unwrap()
         .polymer;
-    let mut out_file = File::create(output_path).unwrap();
-    let genome_length = polymer.len();
-    let num_reads = (target_depth * genome_length as f64 / read_len as f64).ceil() as usize;
-    let mut rng = rand::rng();
-    let mut read_start_ends = vec![StartEnd::default(); polymer.len()];
-    for i in 0..num_reads {
-        let start = rng.random_range(0..genome_length - read_len);
-        read_start_ends[start].start += 1;
-        read_start_ends[start + read_len].end += 1;
-        writeln!(out_file, ">{}:{}:{}", i, start + 1, read_len).unwrap();
-        writeln!(out_file, "{}", &polymer[start..start + read_len]).unwrap();
+    // the assemblers assume reads from the forward strand
+    let props = ReadSimulatorProperties::default()
+        .read_len(read_len)
+        .depth(2.5)
+        .both_strands(false)
+        .errors(
+            ErrorProfile::default()
+                .substitution_rate(0.005)
+                .insertion_rate(0.0025)
+                .deletion_rate(0.0025),
+        );
+    let reads = simulate_reads(&polymer, props);
+
+    // the exact overlap assemblers get the reads without errors
+    let mut out_file = BufWriter::new(File::create(output_path).unwrap());
+    for read in &reads {
+        writeln!(out_file, ">{}", read.entry.description).unwrap();
+        writeln!(out_file, "{}", &polymer[read.origin.span.clone()]).unwrap();
+    }
+    let mut errors_out_file = BufWriter::new(File::create(errors_output_path).unwrap());
+    for read in &reads {
+        writeln!(errors_out_file, ">{}", read.entry.description).unwrap();
+        writeln!(errors_out_file, "{}", read.entry.polymer).unwrap();
     }
+    write_fastq(
+        &reads,
+        &mut BufWriter::new(File::create(fastq_output_path).unwrap()),
+    );
+    write_truth(
+        &reads,
+        &mut BufWriter::new(File::create(truth_output_path).unwrap()),
+    );
 
-    let bases_covered2 = cover_depth(read_start_ends.iter().copied())
-        .filter(|depth| depth.depth != 0)
-        .count();
-    let avg_depth = (num_reads * read_len) as f64 / genome_length as f64;
-    let var_depth2 = cover_depth(read_start_ends.iter().copied())
-        .map(|base_depth| (base_depth.depth as f64 - avg_depth).powi(2))
-        .sum::<f64>()
-        / (genome_length - 1) as f64;
+    let genome_length = polymer.len();
+    let spans = reads
+        .iter()
+        .map(|read| read.origin.span.clone())
+        .collect_vec();
     let overlap_length = (read_len as f64 * theta).ceil() as usize;
-    let num_islands = cover_depth(
-        read_start_ends
-            .iter()
-            .copied()
-            .zip(
-                read_start_ends
-                    .iter()
-                    .copied()
-                    .skip(overlap_length)
-                    .chain(iter::repeat(StartEnd::default())),
-            )
-            .map(|(start_end, start_end_shifted)| StartEnd {
-                start: start_end.start,
-                end: start_end_shifted.end,
-            }),
-    )
-    .fold((0, false), |(mut num_islands, mut on_island), cover| {
-        if cover.depth != 0 && !on_island {
-            on_island = true;
-            num_islands += 1;
-        } else if cover.depth == 0 && on_island {
-            on_island = false;
-        };
-
-        // println!("num: {}, cov: {}, isl: {}", num_islands, cover.depth, on_island);
-
-        (num_islands, on_island)
-    })
-    .0;
+    let stats = coverage_stats(genome_length, &spans, overlap_length);
+    let expected = lander_waterman(genome_length, reads.len(), read_len, theta);
 
     let mut stats_out_file = File::create(stats_output_path).unwrap();
-    writeln!(stats_out_file, "genome_length: {}", genome_length).unwrap();
-    writeln!(stats_out_file, "num_reads: {}", num_reads).unwrap();
-    writeln!(stats_out_file, "bases_covered: {}", bases_covered2).unwrap();
-    writeln!(stats_out_file, "avg_depth: {}", avg_depth).unwrap();
-    writeln!(stats_out_file, "var_depth: {}", var_depth2).unwrap();
-    writeln!(stats_out_file, "num_islands: {}", num_islands).unwrap();
-}
-
-fn cover_depth(iter: impl IntoIterator<Item = StartEnd>) -> impl Iterator<Item = CoverDepth> {
-    iter.into_iter()
-        .scan(CoverDepth::default(), |cur_depth, start_end| {
-            cur_depth.depth += start_end.start;
-            cur_depth.depth -= start_end.end;
-            Some(*cur_depth)
-        })
-}
-
-#[derive(Debug, Clone, Default, Copy)]
-struct StartEnd {
-    start: usize,
-    end: usize,
-}
-
-#[derive(Debug, Clone, Default, Copy)]
-struct CoverDepth {
-    depth: usize,
+    writeln!(stats_out_file, "genome_length: {}", stats.genome_len).unwrap();
+    writeln!(stats_out_file, "num_reads: {}", stats.num_reads).unwrap();
+    writeln!(stats_out_file, "bases_covered: {}", stats.bases_covered).unwrap();
+    writeln!(stats_out_file, "avg_depth: {}", stats.avg_depth).unwrap();
+    writeln!(stats_out_file, "var_depth: {}", stats.var_depth).unwrap();
+    writeln!(stats_out_file, "num_islands: {}", stats.num_islands).unwrap();
+    writeln!(stats_out_file, "expected_coverage: {}", expected.coverage).unwrap();
+    writeln!(
+        stats_out_file,
+        "expected_bases_covered: {:.1}",
+        expected.expected_bases_covered
+    )
+    .unwrap();
+    writeln!(
+        stats_out_file,
+        "expected_islands: {:.1}",
+        expected.expected_islands
+    )
+    .unwrap();
+    writeln!(
+        stats_out_file,
+        "expected_reads_per_island: {:.1}",
+        expected.expected_reads_per_island
+    )
+    .unwrap();
+    writeln!(
+        stats_out_file,
+        "expected_island_len: {:.1}",
+        expected.expected_island_len
+    )
+    .unwrap();
 }
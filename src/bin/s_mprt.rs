@@ -1,26 +1,26 @@
 use bioinformatics::polymers::ProteinAa;
-use bioinformatics::string::indexes_regex;
+use bioinformatics::string::motif::Motif;
 use bioinformatics::string_model::AString;
 use bioinformatics::util::{fasta_polymers, lines};
-use regex::Regex;
 use reqwest::Url;
 
 fn main() {
     let data = include_str!("s_mprt_data.txt");
 
     let client = reqwest::blocking::Client::new();
-    let regex = Regex::new("N[^P][ST][^P]").unwrap();
+    let motif: Motif<ProteinAa> = "N-{P}-[ST]-{P}".parse().unwrap();
     for prot_id in lines(data) {
         let db_id = prot_id.split("_").next().unwrap();
-        let aas = prot_aas(&client, db_id).to_string();
+        let aas = prot_aas(&client, db_id);
 
         let mut any_matches = false;
-        for position in indexes_regex(&aas, &regex) {
+        for position in motif.indexes(&aas) {
             if !any_matches {
                 println!("{}", prot_id);
                 any_matches = true;
             }
-            print!("{} ", position);
+            // positions are 1-based in the output
+            print!("{} ", position + 1);
         }
         if any_matches {
             println!();
@@ -50,4 +50,4 @@ fn prot_aas(client: &reqwest::blocking::Client, prot_id: &str) -> AString<Protei
     }
 }
 
-//alg regex substr
+//alg motif substr
@@ -1,5 +1,7 @@
 use bioinformatics::polymers::DnaNt;
+use bioinformatics::string::repeats::reverse_complement_palindromes;
 use bioinformatics::util::fasta_polymers_file;
+use itertools::Itertools;
 
 fn main() {
     let dna = fasta_polymers_file::<DnaNt>("src/bin/s_revp_data.txt")
@@ -7,28 +9,18 @@ fn main() {
         .unwrap()
         .polymer;
 
-    for i in 0..dna.len() {
-        for l in 4..=12 {
-            if i + l > dna.len() {
-                break;
-            }
-
-            if dna[i..i + l]
-                .iter()
-                .copied()
-                .zip(
-                    dna[i..i + l]
-                        .iter()
-                        .copied()
-                        .rev()
-                        .map(DnaNt::bonding_complement),
-                )
-                .all(|(it1, it2)| it1 == it2)
-            {
-                println!("{} {}", i + 1, l);
-            }
-        }
+    // the palindromes of length 4 to 12 are the centered substrings of the maximal ones
+    let positions = reverse_complement_palindromes(&dna, 4)
+        .into_iter()
+        .flat_map(|palindrome| {
+            (4..=palindrome.len().min(12))
+                .step_by(2)
+                .map(move |l| (palindrome.start + (palindrome.len() - l) / 2, l))
+        })
+        .sorted();
+    for (i, l) in positions {
+        println!("{} {}", i + 1, l);
     }
 }
 
-//alg reverse complement substr
+//alg reverse complement palindrome manacher
@@ -4,6 +4,7 @@ extern crate core;
 
 pub mod alphabet_model;
 pub mod ascii;
+pub mod assembly;
 pub mod genotype;
 pub mod polymers;
 pub mod probability;
@@ -1,5 +1,5 @@
 use crate::enum_char;
-use crate::string_model::AString;
+use crate::string_model::{AStr, AString};
 use itertools::Itertools;
 use ordered_float::NotNan;
 use std::collections::{BTreeMap, Bound};
@@ -28,6 +28,21 @@ impl DnaNt {
     }
 }
 
+pub fn reverse_complement(dna: &AStr<DnaNt>) -> AString<DnaNt> {
+    dna.iter()
+        .rev()
+        .copied()
+        .map(DnaNt::bonding_complement)
+        .collect()
+}
+
+/// Strand of double stranded DNA
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
 enum_char!(RnaNt; A, C, G, U);
 
 enum_char!(ProteinAa; A, C, D, E, F, G, H, I, K, L, M, N, P, Q, R, S, T, V, W, Y);
@@ -1,8 +1,23 @@
+pub mod aho_corasick;
 pub mod alignment;
+pub mod bitap;
 pub mod border_array;
 pub mod bwt;
+pub mod complexity;
+pub mod kmer;
 pub mod lcs;
+pub mod lyndon;
+pub mod lz77;
+pub mod matcher;
+pub mod maximal_match;
+pub mod motif;
+pub mod r_index;
+pub mod read_mapper;
+pub mod repeats;
+pub mod sketch;
 pub mod suffix_array;
+pub mod suffix_automaton;
+pub mod suffix_prefix_overlap;
 pub mod suffix_trie_compact;
 pub mod suffix_trie_mcc_arena;
 pub mod suffix_trie_mcc_petgraph;
@@ -11,8 +26,10 @@ pub mod suffix_trie_ukn;
 mod superstring_petgraph;
 mod superstring_rcrefcell;
 pub mod trie_compact;
+pub mod unique_substrings;
 
 use crate::alphabet_model::CharT;
+use crate::string::matcher::{Horspool, Matcher};
 use crate::string_model::{AStr, AString};
 use regex::Regex;
 use std::ops::Sub;
@@ -20,37 +37,27 @@ pub use superstring_petgraph::scs as sc_supstr_petgraph;
 pub use superstring_rcrefcell::scs;
 
 pub fn indexes<C: CharT>(s: &AStr<C>, t: &AStr<C>) -> Vec<usize> {
-    let mut res = Vec::new();
-
-    let mut offset = 0;
-    while let Some(index) = find(AStr::from_slice(&s[offset..]), t) {
-        res.push(offset + index);
-        offset += index + 1;
-    }
-
-    res
+    // Horspool is the fastest of the matchers for patterns up to a few tens of chars, see the
+    // substr benches
+    Horspool::new(t).find_iter(s).collect()
 }
 
 pub fn find<C: CharT>(s: &AStr<C>, t: &AStr<C>) -> Option<usize> {
-    'outer: for i in 0..s.len() {
-        for j in 0..t.len() {
-            if i + j >= s.len() || s[i + j] != t[j] {
-                continue 'outer;
-            }
-        }
-        return Some(i);
-    }
-    None
+    Horspool::new(t).find(s)
 }
 
 pub fn replace_all<C: CharT>(s: &AStr<C>, t: &AStr<C>, u: &AStr<C>) -> AString<C> {
     let mut res = AString::default();
 
     let mut i = 0;
-    while let Some(idx) = find(&s[i..], t) {
-        res.push_str(&s[i..i + idx]);
+    // occurrences overlapping a replaced one are skipped
+    for idx in Horspool::new(t).find_iter(s) {
+        if idx < i {
+            continue;
+        }
+        res.push_str(&s[i..idx]);
         res.push_str(u);
-        i += idx + t.len();
+        i = idx + t.len();
     }
     res.push_str(&s[i..]);
 
@@ -110,13 +117,15 @@ pub fn indexes_str(s: &str, t: &str) -> impl Iterator<Item = usize> {
     res.into_iter()
 }
 
+/// 0-based start positions (byte offsets) of all matches of `regex` in `s`, including
+/// overlapping matches
 pub fn indexes_regex(s: &str, regex: &Regex) -> impl Iterator<Item = usize> {
     let mut res = Vec::new();
 
     let mut offset = 0;
     while let Some(mtch) = regex.find_at(s, offset) {
+        res.push(mtch.start());
         offset = mtch.start() + 1;
-        res.push(offset);
     }
 
     res.into_iter()
@@ -125,7 +134,8 @@ pub fn indexes_regex(s: &str, regex: &Regex) -> impl Iterator<Item = usize> {
 #[cfg(test)]
 mod test {
     use crate::ascii::ascii;
-    use crate::string::{find, indexes, lcp, lcs_simple, overlap, replace_all};
+    use crate::string::{find, indexes, indexes_regex, lcp, lcs_simple, overlap, replace_all};
+    use regex::Regex;
 
     #[test]
     fn test_lcs() {
@@ -161,12 +171,36 @@ mod test {
             replace_all(ascii("abcdabcd"), ascii("bc"), ascii("lmn")),
             ascii("almndalmnd").to_owned()
         );
+        assert_eq!(
+            replace_all(ascii("aaaaa"), ascii("aa"), ascii("b")),
+            ascii("bba").to_owned()
+        );
+        assert_eq!(
+            replace_all(ascii("ab"), ascii(""), ascii("x")),
+            ascii("xaxbx").to_owned()
+        );
     }
 
     #[test]
     fn test_indexes() {
         assert_eq!(indexes(ascii("abcd"), ascii("ijk")), vec![]);
         assert_eq!(indexes(ascii("ijkabcdijk"), ascii("ijk")), vec![0, 7]);
+        assert_eq!(indexes(ascii("aaaa"), ascii("aa")), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_indexes_regex() {
+        let regex = Regex::new("aa").unwrap();
+        assert_eq!(
+            indexes_regex("baaaab", &regex).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        let regex = Regex::new("N[^P][ST][^P]").unwrap();
+        assert_eq!(
+            indexes_regex("NNSTSP", &regex).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(indexes_regex("NPST", &regex).count(), 0);
     }
 
     #[test]
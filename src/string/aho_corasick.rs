@@ -0,0 +1,401 @@
+//! Aho–Corasick multi-pattern search. The keyword trie of the patterns is stored uncompacted
+//! (unlike [`trie_compact`](crate::string::trie_compact)), so that failure and output links
+//! can point at any prefix of a pattern. Failure links are folded into a full transition
+//! table, so the text is scanned with one table lookup per char
+
+use crate::alphabet_model::CharT;
+use crate::string_model::AStr;
+use generic_array::typenum::Unsigned;
+use std::collections::VecDeque;
+use std::iter;
+use std::marker::PhantomData;
+
+const ROOT: usize = 0;
+
+/// Which occurrences the search reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchKind {
+    /// All occurrences, including overlapping ones. Occurrences are ordered by end position
+    /// and then from longest to shortest
+    #[default]
+    Overlapping,
+    /// Non-overlapping occurrences, taking the occurrence that ends first. Among
+    /// occurrences ending at the same position, the longest is taken
+    NonOverlapping,
+    /// Non-overlapping occurrences, taking the occurrence that starts first. Among
+    /// occurrences starting at the same position, the longest is taken
+    LeftmostLongest,
+}
+
+/// Occurrence of pattern number `pattern` at `text[start..end]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PatternMatch {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    depth: usize,
+    fail: usize,
+    /// Patterns equal to the prefix represented by the node
+    patterns: Vec<usize>,
+    /// Nearest node on the failure path with patterns
+    output: Option<usize>,
+}
+
+impl Node {
+    fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            fail: ROOT,
+            patterns: Vec::new(),
+            output: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AhoCorasick<C> {
+    nodes: Vec<Node>,
+    /// Transition for node `n` and char `ch` at `n * alphabet size + ch.index()`
+    transitions: Vec<usize>,
+    pattern_lens: Vec<usize>,
+    _char: PhantomData<C>,
+}
+
+/// Builds the automaton for the given patterns. Patterns are numbered by their position in
+/// `patterns`. Empty patterns never match
+pub fn build_automaton<'s, C: CharT>(
+    patterns: impl IntoIterator<Item = &'s AStr<C>>,
+) -> AhoCorasick<C> {
+    let alphabet_size = C::AlphabetSize::USIZE;
+    let mut nodes = vec![Node::new(0)];
+    // transitions of the keyword trie, None is no child
+    let mut children: Vec<Option<usize>> = vec![None; alphabet_size];
+    let mut pattern_lens = Vec::new();
+
+    for (pattern_index, pattern) in patterns.into_iter().enumerate() {
+        pattern_lens.push(pattern.len());
+        if pattern.is_empty() {
+            continue;
+        }
+        let mut node = ROOT;
+        for ch in pattern.iter() {
+            let child = &mut children[node * alphabet_size + ch.index()];
+            node = match *child {
+                Some(child) => child,
+                None => {
+                    let new_node = nodes.len();
+                    *child = Some(new_node);
+                    nodes.push(Node::new(nodes[node].depth + 1));
+                    children.resize(children.len() + alphabet_size, None);
+                    new_node
+                }
+            };
+        }
+        nodes[node].patterns.push(pattern_index);
+    }
+
+    // breadth first, so failure links point to nodes that are already complete
+    let mut transitions = vec![ROOT; children.len()];
+    let mut queue = VecDeque::new();
+    for ch_index in 0..alphabet_size {
+        if let Some(child) = children[ch_index] {
+            transitions[ch_index] = child;
+            queue.push_back(child);
+        }
+    }
+    while let Some(node) = queue.pop_front() {
+        let fail = nodes[node].fail;
+        nodes[node].output = if nodes[fail].patterns.is_empty() {
+            nodes[fail].output
+        } else {
+            Some(fail)
+        };
+        for ch_index in 0..alphabet_size {
+            let fail_transition = transitions[fail * alphabet_size + ch_index];
+            transitions[node * alphabet_size + ch_index] =
+                match children[node * alphabet_size + ch_index] {
+                    Some(child) => {
+                        nodes[child].fail = fail_transition;
+                        queue.push_back(child);
+                        child
+                    }
+                    None => fail_transition,
+                };
+        }
+    }
+
+    AhoCorasick {
+        nodes,
+        transitions,
+        pattern_lens,
+        _char: PhantomData,
+    }
+}
+
+impl<C: CharT> AhoCorasick<C> {
+    /// Number of patterns
+    pub fn pattern_count(&self) -> usize {
+        self.pattern_lens.len()
+    }
+
+    fn next_node(&self, node: usize, ch: C) -> usize {
+        self.transitions[node * C::AlphabetSize::USIZE + ch.index()]
+    }
+
+    /// Patterns that are suffixes of the prefix represented by `node`, longest first
+    fn outputs(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        let first = if self.nodes[node].patterns.is_empty() {
+            self.nodes[node].output
+        } else {
+            Some(node)
+        };
+        iter::successors(first, |&node| self.nodes[node].output)
+            .flat_map(|node| self.nodes[node].patterns.iter().copied())
+    }
+
+    fn pattern_match(&self, pattern: usize, end: usize) -> PatternMatch {
+        PatternMatch {
+            pattern,
+            start: end - self.pattern_lens[pattern],
+            end,
+        }
+    }
+
+    /// Occurrences of the patterns in `text` according to `kind`, found in one pass over
+    /// the text
+    pub fn find_iter<'a>(
+        &'a self,
+        text: &'a AStr<C>,
+        kind: MatchKind,
+    ) -> impl Iterator<Item = PatternMatch> + 'a {
+        let mut node = ROOT;
+        let mut pos = 0;
+        let mut pending = VecDeque::new();
+        iter::from_fn(move || {
+            if let Some(mtch) = pending.pop_front() {
+                return Some(mtch);
+            }
+            match kind {
+                MatchKind::Overlapping => {
+                    while pos < text.len() {
+                        node = self.next_node(node, text[pos]);
+                        pos += 1;
+                        pending.extend(
+                            self.outputs(node)
+                                .map(|pattern| self.pattern_match(pattern, pos)),
+                        );
+                        if let Some(mtch) = pending.pop_front() {
+                            return Some(mtch);
+                        }
+                    }
+                    None
+                }
+                MatchKind::NonOverlapping => {
+                    while pos < text.len() {
+                        node = self.next_node(node, text[pos]);
+                        pos += 1;
+                        if let Some(pattern) = self.outputs(node).next() {
+                            node = ROOT;
+                            return Some(self.pattern_match(pattern, pos));
+                        }
+                    }
+                    None
+                }
+                MatchKind::LeftmostLongest => {
+                    let mut best: Option<PatternMatch> = None;
+                    while pos < text.len() {
+                        node = self.next_node(node, text[pos]);
+                        pos += 1;
+                        for pattern in self.outputs(node) {
+                            let mtch = self.pattern_match(pattern, pos);
+                            if best.is_none_or(|best| {
+                                mtch.start < best.start
+                                    || mtch.start == best.start && mtch.end > best.end
+                            }) {
+                                best = Some(mtch);
+                            }
+                        }
+                        // no later occurrence can start before the prefix matched by the node
+                        if let Some(best) = best
+                            && best.start < pos - self.nodes[node].depth
+                        {
+                            break;
+                        }
+                    }
+                    // scanning restarts after the occurrence
+                    if let Some(best) = best {
+                        node = ROOT;
+                        pos = best.end;
+                    }
+                    best
+                }
+            }
+        })
+    }
+
+    /// Whether any pattern occurs in `text`
+    pub fn is_match(&self, text: &AStr<C>) -> bool {
+        self.find_iter(text, MatchKind::NonOverlapping)
+            .next()
+            .is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string_model::test_util::Char;
+    use crate::string_model::{AString, arb_astring};
+    use itertools::Itertools;
+    use proptest::collection::vec;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+
+    fn naive_overlapping<C: CharT>(text: &AStr<C>, patterns: &[AString<C>]) -> Vec<PatternMatch> {
+        (1..=text.len())
+            .flat_map(|end| {
+                patterns
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, pattern)| {
+                        !pattern.is_empty() && text[..end].ends_with(pattern)
+                    })
+                    .map(move |(pattern_index, pattern)| PatternMatch {
+                        pattern: pattern_index,
+                        start: end - pattern.len(),
+                        end,
+                    })
+                    .sorted_by_key(|mtch| (mtch.start, mtch.pattern))
+            })
+            .collect()
+    }
+
+    fn naive_leftmost_longest<C: CharT>(
+        text: &AStr<C>,
+        patterns: &[AString<C>],
+    ) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        let mut pos = 0;
+        while pos < text.len() {
+            let longest = patterns
+                .iter()
+                .filter(|pattern| !pattern.is_empty() && text[pos..].starts_with(pattern))
+                .map(|pattern| pattern.len())
+                .max();
+            match longest {
+                Some(len) => {
+                    result.push((pos, pos + len));
+                    pos += len;
+                }
+                None => pos += 1,
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let patterns = [ascii("he"), ascii("she"), ascii("his"), ascii("hers")];
+        let automaton = build_automaton(patterns);
+        let matches = automaton
+            .find_iter(ascii("ushers"), MatchKind::Overlapping)
+            .collect_vec();
+        assert_eq!(
+            matches,
+            vec![
+                PatternMatch {
+                    pattern: 1,
+                    start: 1,
+                    end: 4
+                },
+                PatternMatch {
+                    pattern: 0,
+                    start: 2,
+                    end: 4
+                },
+                PatternMatch {
+                    pattern: 3,
+                    start: 2,
+                    end: 6
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_overlapping() {
+        let patterns = [ascii("abcd"), ascii("bc"), ascii("cdef"), ascii("b")];
+        let automaton = build_automaton(patterns);
+        let spans = |kind| {
+            automaton
+                .find_iter(ascii("abcdefbc"), kind)
+                .map(|mtch| (mtch.pattern, mtch.start, mtch.end))
+                .collect_vec()
+        };
+        assert_eq!(
+            spans(MatchKind::NonOverlapping),
+            vec![(3, 1, 2), (2, 2, 6), (3, 6, 7)]
+        );
+        assert_eq!(
+            spans(MatchKind::LeftmostLongest),
+            vec![(0, 0, 4), (1, 6, 8)]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_and_empty_patterns() {
+        let patterns = [ascii("ab"), ascii(""), ascii("ab")];
+        let automaton = build_automaton(patterns);
+        assert_eq!(automaton.pattern_count(), 3);
+        let matches = automaton
+            .find_iter(ascii("xaby"), MatchKind::Overlapping)
+            .map(|mtch| mtch.pattern)
+            .collect_vec();
+        assert_eq!(matches, vec![0, 2]);
+        assert!(!automaton.is_match(ascii("xa")));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn prop_test_overlapping(text in arb_astring::<Char>(0..40), patterns in vec(arb_astring::<Char>(0..5), 1..8)) {
+            let automaton = build_automaton(patterns.iter().map(|pattern| pattern.as_str()));
+            let matches = automaton
+                .find_iter(&text, MatchKind::Overlapping)
+                .sorted_by_key(|mtch| (mtch.end, mtch.start, mtch.pattern))
+                .collect_vec();
+            prop_assert_eq!(matches, naive_overlapping(&text, &patterns));
+        }
+
+        #[test]
+        fn prop_test_leftmost_longest(text in arb_astring::<Char>(0..40), patterns in vec(arb_astring::<Char>(0..5), 1..8)) {
+            let automaton = build_automaton(patterns.iter().map(|pattern| pattern.as_str()));
+            let spans = automaton
+                .find_iter(&text, MatchKind::LeftmostLongest)
+                .map(|mtch| (mtch.start, mtch.end))
+                .collect_vec();
+            prop_assert_eq!(spans, naive_leftmost_longest(&text, &patterns));
+        }
+
+        #[test]
+        fn prop_test_non_overlapping(text in arb_astring::<Char>(0..40), patterns in vec(arb_astring::<Char>(0..5), 1..8)) {
+            let automaton = build_automaton(patterns.iter().map(|pattern| pattern.as_str()));
+            let matches = automaton
+                .find_iter(&text, MatchKind::NonOverlapping)
+                .collect_vec();
+            for (mtch1, mtch2) in matches.iter().tuple_windows() {
+                prop_assert!(mtch1.end <= mtch2.start);
+            }
+            for mtch in &matches {
+                prop_assert_eq!(&text[mtch.start..mtch.end], patterns[mtch.pattern].as_str());
+            }
+            prop_assert_eq!(matches.is_empty(), !automaton.is_match(&text));
+        }
+    }
+}
@@ -2,12 +2,18 @@ use crate::alphabet_model::CharT;
 use crate::string_model::{AStr, AString};
 use core::fmt::{Display, Formatter, Write};
 use generic_array::typenum::U4;
+use itertools::Itertools;
 use std::ops::Range;
 
-mod global_alignment_hirschberg;
-mod global_alignment_wagner_fischer;
+pub mod batch;
+pub mod co_optimal;
+pub mod global_alignment_hirschberg;
+pub mod global_alignment_wagner_fischer;
+pub mod local_alignment_banded;
 // mod local_alignment_hirschberg;
-mod local_alignment_wagner_fischer;
+pub mod local_alignment_wagner_fischer;
+pub mod msa;
+pub mod overlap_alignment;
 
 #[derive(Debug, Copy, Clone)]
 pub struct AlignmentProperties {
@@ -43,6 +49,22 @@ impl Default for AlignmentProperties {
     }
 }
 
+/// Dynamic programming matrices that can be reused between alignments
+#[derive(Debug, Default)]
+pub struct AlignmentBuffer {
+    global: Vec<Vec<usize>>,
+    local: Vec<Vec<isize>>,
+}
+
+/// Resizes `c` to `rows` x `cols` and zeroes it, keeping allocated rows
+fn reset_matrix<T: Copy + Default>(c: &mut Vec<Vec<T>>, rows: usize, cols: usize) {
+    c.resize_with(rows, Vec::new);
+    for row in c.iter_mut() {
+        row.clear();
+        row.resize(cols, T::default());
+    }
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub enum Edit {
     Match,
@@ -64,6 +86,16 @@ impl CharT for Edit {
         self as usize
     }
 
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => Edit::Match,
+            1 => Edit::Mismatch,
+            2 => Edit::Insert,
+            3 => Edit::Delete,
+            _ => panic!("invalid index {}", index),
+        }
+    }
+
     fn from_char(ch: char) -> Option<Self> {
         match ch {
             '=' => Some(Edit::Match),
@@ -97,6 +129,38 @@ pub struct LocalAlignment {
     pub edits: AString<Edit>,
 }
 
+/// Alignment of `x[x_start..]` with `y[..y_end]`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OverlapAlignment {
+    pub penalty: isize,
+    pub x_start: usize,
+    pub y_end: usize,
+    pub edits: AString<Edit>,
+}
+
+/// CIGAR string for edits aligning `y` (query) to `x` (reference), `*` if there are no edits
+pub fn cigar(edits: &AStr<Edit>) -> String {
+    if edits.is_empty() {
+        return "*".to_string();
+    }
+    edits
+        .iter()
+        .map(|edit| match edit {
+            Edit::Match | Edit::Mismatch => 'M',
+            Edit::Insert => 'I',
+            Edit::Delete => 'D',
+        })
+        .chunk_by(|op| *op)
+        .into_iter()
+        .map(|(op, ops)| format!("{}{}", ops.count(), op))
+        .collect()
+}
+
+/// Number of mismatches, inserts and deletes (edit distance) in the edits
+pub fn edit_distance(edits: &AStr<Edit>) -> usize {
+    edits.iter().filter(|edit| **edit != Edit::Match).count()
+}
+
 fn is_edit<C: PartialEq>(x: &AStr<C>, y: &AStr<C>, edits: &AStr<Edit>) -> bool {
     let mut i = 0;
     let mut j = 0;
@@ -128,3 +192,17 @@ fn is_edit<C: PartialEq>(x: &AStr<C>, y: &AStr<C>, edits: &AStr<Edit>) -> bool {
 
     i == x.len() && j == y.len()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn test_cigar() {
+        let edits = AString::<Edit>::from_str("==X=IID==").unwrap();
+        assert_eq!(cigar(&edits), "4M2I1D2M");
+        assert_eq!(edit_distance(&edits), 4);
+        assert_eq!(cigar(AStr::empty()), "*");
+    }
+}
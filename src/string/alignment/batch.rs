@@ -0,0 +1,82 @@
+//! Alignment of many sequence pairs across the rayon thread pool
+
+use crate::alphabet_model::CharT;
+use crate::string::alignment::global_alignment_wagner_fischer::global_alignment_with_buffer;
+use crate::string::alignment::local_alignment_wagner_fischer::local_alignment_with_buffer;
+use crate::string::alignment::{
+    AlignmentBuffer, AlignmentProperties, GlobalAlignment, LocalAlignment,
+};
+use crate::string_model::AStr;
+use itertools::Itertools;
+use rayon::prelude::*;
+
+/// Global alignment of each `(x, y)` pair. Results are in input order
+pub fn global_alignments<'a, C: CharT + Sync>(
+    pairs: impl IntoIterator<Item = (&'a AStr<C>, &'a AStr<C>)>,
+    props: AlignmentProperties,
+) -> Vec<GlobalAlignment> {
+    let pairs = pairs.into_iter().collect_vec();
+    pairs
+        .par_iter()
+        .map_init(AlignmentBuffer::default, |buffer, (x, y)| {
+            global_alignment_with_buffer(x, y, props, buffer)
+        })
+        .collect()
+}
+
+/// Local alignment of each `y` inside `x` for the `(x, y)` pairs. Results are in input order
+pub fn local_alignments<'a, C: CharT + Sync>(
+    pairs: impl IntoIterator<Item = (&'a AStr<C>, &'a AStr<C>)>,
+    props: AlignmentProperties,
+) -> Vec<LocalAlignment> {
+    let pairs = pairs.into_iter().collect_vec();
+    pairs
+        .par_iter()
+        .map_init(AlignmentBuffer::default, |buffer, (x, y)| {
+            local_alignment_with_buffer(x, y, props, buffer)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::string::alignment::{
+        global_alignment_wagner_fischer, local_alignment_wagner_fischer,
+    };
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use proptest::collection::vec;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(200))]
+
+        #[test]
+        fn prop_test_global_alignments(
+            pairs in vec((arb_astring::<Char>(0..20), arb_astring::<Char>(0..20)), 0..50))
+        {
+            let props = AlignmentProperties::default();
+            let expected = pairs
+                .iter()
+                .map(|(x, y)| global_alignment_wagner_fischer::global_alignment(x, y, props))
+                .collect_vec();
+            let aligns = global_alignments(pairs.iter().map(|(x, y)| (x.as_str(), y.as_str())), props);
+            prop_assert_eq!(aligns, expected);
+        }
+
+        #[test]
+        fn prop_test_local_alignments(
+            pairs in vec((arb_astring::<Char>(0..20), arb_astring::<Char>(0..10)), 0..50))
+        {
+            let props = AlignmentProperties::default();
+            let expected = pairs
+                .iter()
+                .map(|(x, y)| local_alignment_wagner_fischer::local_alignment(x, y, props))
+                .collect_vec();
+            let aligns = local_alignments(pairs.iter().map(|(x, y)| (x.as_str(), y.as_str())), props);
+            prop_assert_eq!(aligns, expected);
+        }
+    }
+}
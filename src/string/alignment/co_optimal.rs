@@ -0,0 +1,365 @@
+//! Counting and enumeration of optimal and near optimal global alignments
+
+use crate::alphabet_model::CharT;
+use crate::string::alignment::global_alignment_wagner_fischer::penalty_matrix;
+use crate::string::alignment::{AlignmentProperties, Edit, GlobalAlignment};
+use crate::string_model::AStr;
+
+/// Number of optimal global alignments of `x` and `y`, modulo `modulus`
+pub fn count_optimal_global_alignments<C: CharT>(
+    x: &AStr<C>,
+    y: &AStr<C>,
+    props: AlignmentProperties,
+    modulus: u64,
+) -> u64 {
+    assert!(modulus > 0);
+
+    let c = penalty_matrix(x, y, props);
+    let steps = Steps { x, y, props };
+
+    let mut count = vec![vec![0u64; y.len() + 1]; x.len() + 1];
+    count[0][0] = 1 % modulus;
+
+    for i in 0..=x.len() {
+        for j in 0..=y.len() {
+            if i == 0 && j == 0 {
+                continue;
+            }
+            let paths = steps
+                .predecessors(i, j)
+                .filter(|step| c[step.i][step.j] + step.penalty == c[i][j])
+                .map(|step| count[step.i][step.j] as u128)
+                .sum::<u128>();
+            count[i][j] = (paths % modulus as u128) as u64;
+        }
+    }
+
+    count[x.len()][y.len()]
+}
+
+/// Lazily enumerates all optimal global alignments of `x` and `y`. The first
+/// alignment is the one chosen by the single traceback aligners
+pub fn optimal_global_alignments<'s, C: CharT>(
+    x: &'s AStr<C>,
+    y: &'s AStr<C>,
+    props: AlignmentProperties,
+) -> GlobalAlignments<'s, C> {
+    suboptimal_global_alignments(x, y, props, 0)
+}
+
+/// Lazily enumerates all global alignments of `x` and `y` with penalty at most `delta`
+/// above the optimum. Alignments are not ordered by penalty
+pub fn suboptimal_global_alignments<'s, C: CharT>(
+    x: &'s AStr<C>,
+    y: &'s AStr<C>,
+    props: AlignmentProperties,
+    delta: usize,
+) -> GlobalAlignments<'s, C> {
+    let c = penalty_matrix(x, y, props);
+    let max_penalty = c[x.len()][y.len()] + delta;
+
+    GlobalAlignments {
+        steps: Steps { x, y, props },
+        c,
+        max_penalty,
+        stack: vec![Frame {
+            i: x.len(),
+            j: y.len(),
+            suffix_penalty: 0,
+            next_step: 0,
+        }],
+        edits: Vec::new(),
+    }
+}
+
+pub struct GlobalAlignments<'s, C> {
+    steps: Steps<'s, C>,
+    c: Vec<Vec<usize>>,
+    max_penalty: usize,
+    stack: Vec<Frame>,
+    /// Edits on the current path, last edit first
+    edits: Vec<Edit>,
+}
+
+/// Cell on the traceback path currently explored
+struct Frame {
+    i: usize,
+    j: usize,
+    /// Penalty of the path from this cell to the end cell
+    suffix_penalty: usize,
+    /// Index of next step to try: diagonal, up, left
+    next_step: usize,
+}
+
+const STEPS: usize = 3;
+
+impl<C: CharT> Iterator for GlobalAlignments<'_, C> {
+    type Item = GlobalAlignment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+
+            if frame.i == 0 && frame.j == 0 && frame.next_step == 0 {
+                frame.next_step = STEPS;
+                return Some(GlobalAlignment {
+                    penalty: frame.suffix_penalty,
+                    edits: self.edits.iter().rev().copied().collect(),
+                });
+            }
+
+            if frame.next_step == STEPS {
+                self.stack.pop();
+                if !self.stack.is_empty() {
+                    self.edits.pop();
+                }
+                continue;
+            }
+
+            let step = self.steps.step(frame.i, frame.j, frame.next_step);
+            frame.next_step += 1;
+            let Some(step) = step else {
+                continue;
+            };
+
+            let suffix_penalty = frame.suffix_penalty + step.penalty;
+            if self.c[step.i][step.j] + suffix_penalty <= self.max_penalty {
+                self.edits.push(step.edit);
+                self.stack.push(Frame {
+                    i: step.i,
+                    j: step.j,
+                    suffix_penalty,
+                    next_step: 0,
+                });
+            }
+        }
+    }
+}
+
+struct Steps<'s, C> {
+    x: &'s AStr<C>,
+    y: &'s AStr<C>,
+    props: AlignmentProperties,
+}
+
+/// Step from a predecessor cell
+struct Step {
+    i: usize,
+    j: usize,
+    edit: Edit,
+    penalty: usize,
+}
+
+impl<C: PartialEq> Steps<'_, C> {
+    /// Step number `step_idx` into cell `(i, j)` in the order diagonal, up, left. Steps from
+    /// outside the matrix are `None`
+    fn step(&self, i: usize, j: usize, step_idx: usize) -> Option<Step> {
+        match step_idx {
+            0 if i != 0 && j != 0 => Some(if self.x[i - 1] == self.y[j - 1] {
+                Step {
+                    i: i - 1,
+                    j: j - 1,
+                    edit: Edit::Match,
+                    penalty: 0,
+                }
+            } else {
+                Step {
+                    i: i - 1,
+                    j: j - 1,
+                    edit: Edit::Mismatch,
+                    penalty: self.props.mismatch_penalty,
+                }
+            }),
+            1 if i != 0 => Some(Step {
+                i: i - 1,
+                j,
+                edit: Edit::Delete,
+                penalty: self.props.gap_penalty,
+            }),
+            2 if j != 0 => Some(Step {
+                i,
+                j: j - 1,
+                edit: Edit::Insert,
+                penalty: self.props.gap_penalty,
+            }),
+            _ => None,
+        }
+    }
+
+    fn predecessors(&self, i: usize, j: usize) -> impl Iterator<Item = Step> + '_ {
+        (0..STEPS).filter_map(move |step_idx| self.step(i, j, step_idx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string::alignment::{global_alignment_wagner_fischer, is_edit};
+    use crate::string_model::test_util::Char;
+    use crate::string_model::{AString, arb_astring};
+    use core::str::FromStr;
+    use itertools::Itertools;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+
+    fn edit(edits: &str) -> AString<Edit> {
+        AString::from_str(edits).unwrap()
+    }
+
+    /// All alignments of `x` and `y` with their penalty
+    fn all_alignments<C: CharT>(
+        x: &AStr<C>,
+        y: &AStr<C>,
+        props: AlignmentProperties,
+    ) -> Vec<GlobalAlignment> {
+        fn rec<C: CharT>(
+            x: &AStr<C>,
+            y: &AStr<C>,
+            props: AlignmentProperties,
+            prefix: GlobalAlignment,
+            res: &mut Vec<GlobalAlignment>,
+        ) {
+            let step = |edit: Edit, penalty: usize| {
+                let mut align = prefix.clone();
+                align.edits.push(edit);
+                align.penalty += penalty;
+                align
+            };
+            if x.is_empty() && y.is_empty() {
+                res.push(prefix.clone());
+            }
+            if !x.is_empty() && !y.is_empty() {
+                let align = if x[0] == y[0] {
+                    step(Edit::Match, 0)
+                } else {
+                    step(Edit::Mismatch, props.mismatch_penalty)
+                };
+                rec(&x[1..], &y[1..], props, align, res);
+            }
+            if !x.is_empty() {
+                rec(
+                    &x[1..],
+                    y,
+                    props,
+                    step(Edit::Delete, props.gap_penalty),
+                    res,
+                );
+            }
+            if !y.is_empty() {
+                rec(
+                    x,
+                    &y[1..],
+                    props,
+                    step(Edit::Insert, props.gap_penalty),
+                    res,
+                );
+            }
+        }
+
+        let mut res = Vec::new();
+        let empty = GlobalAlignment {
+            penalty: 0,
+            edits: AString::default(),
+        };
+        rec(x, y, props, empty, &mut res);
+        res
+    }
+
+    fn sorted_edits(aligns: impl IntoIterator<Item = GlobalAlignment>) -> Vec<String> {
+        aligns
+            .into_iter()
+            .map(|align| align.edits.to_string())
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn test_count_optimal_global_alignments() {
+        let props = AlignmentProperties::default();
+        assert_eq!(
+            count_optimal_global_alignments(ascii("ab"), ascii("ba"), props, u64::MAX),
+            3
+        );
+        assert_eq!(
+            count_optimal_global_alignments(ascii(""), ascii(""), props, u64::MAX),
+            1
+        );
+        assert_eq!(
+            count_optimal_global_alignments(ascii("abcd"), ascii("abcd"), props, u64::MAX),
+            1
+        );
+        assert_eq!(
+            count_optimal_global_alignments(ascii("aaaa"), ascii("aa"), props, 4),
+            6 % 4
+        );
+    }
+
+    #[test]
+    fn test_optimal_global_alignments() {
+        let props = AlignmentProperties::default();
+        let aligns = optimal_global_alignments(ascii("ab"), ascii("ba"), props).collect_vec();
+        assert_eq!(
+            aligns.iter().map(|align| align.edits.clone()).collect_vec(),
+            vec![edit("XX"), edit("I=D"), edit("D=I")]
+        );
+        assert!(aligns.iter().all(|align| align.penalty == 2));
+    }
+
+    #[test]
+    fn test_suboptimal_global_alignments() {
+        let props = AlignmentProperties::default();
+        let aligns = suboptimal_global_alignments(ascii("a"), ascii("a"), props, 2).collect_vec();
+        assert_eq!(
+            aligns
+                .iter()
+                .map(|align| (align.edits.clone(), align.penalty))
+                .collect_vec(),
+            vec![(edit("="), 0), (edit("ID"), 2), (edit("DI"), 2)]
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn prop_test_optimal_global_alignments(
+            x in arb_astring::<Char>(0..6),
+            y in arb_astring::<Char>(0..6),
+            gap_penalty in 1..4usize,
+            mismatch_penalty in 1..4usize)
+        {
+            let props = AlignmentProperties::default().gap_penalty(gap_penalty).mismatch_penalty(mismatch_penalty);
+            let all = all_alignments(&x, &y, props);
+            let optimum = all.iter().map(|align| align.penalty).min().unwrap();
+            let expected = all.iter().filter(|align| align.penalty == optimum).cloned().collect_vec();
+
+            let aligns = optimal_global_alignments(&x, &y, props).collect_vec();
+            prop_assert_eq!(&aligns[0], &global_alignment_wagner_fischer::global_alignment(&x, &y, props));
+            for align in &aligns {
+                prop_assert!(is_edit(&x, &y, &align.edits));
+            }
+            prop_assert_eq!(sorted_edits(aligns), sorted_edits(expected.clone()));
+            prop_assert_eq!(count_optimal_global_alignments(&x, &y, props, u64::MAX), expected.len() as u64);
+            prop_assert_eq!(count_optimal_global_alignments(&x, &y, props, 3), expected.len() as u64 % 3);
+        }
+
+        #[test]
+        fn prop_test_suboptimal_global_alignments(
+            x in arb_astring::<Char>(0..5),
+            y in arb_astring::<Char>(0..5),
+            delta in 0..3usize)
+        {
+            let props = AlignmentProperties::default();
+            let all = all_alignments(&x, &y, props);
+            let optimum = all.iter().map(|align| align.penalty).min().unwrap();
+            let expected = all.into_iter().filter(|align| align.penalty <= optimum + delta).collect_vec();
+
+            let aligns = suboptimal_global_alignments(&x, &y, props, delta).collect_vec();
+            for align in &aligns {
+                prop_assert!(expected.contains(align));
+            }
+            prop_assert_eq!(sorted_edits(aligns), sorted_edits(expected));
+        }
+    }
+}
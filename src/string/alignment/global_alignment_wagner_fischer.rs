@@ -1,14 +1,28 @@
 use crate::alphabet_model::CharT;
-use crate::string::alignment::{AlignmentProperties, Edit, GlobalAlignment};
+use crate::string::alignment::{
+    AlignmentBuffer, AlignmentProperties, Edit, GlobalAlignment, reset_matrix,
+};
 use crate::string_model::{AStr, AString};
 use core::fmt::{Display, Write};
 
-pub fn global_alignment<C: CharT>(
+/// Matrix of minimal penalties for aligning prefixes `x[..i]` and `y[..j]`
+pub(super) fn penalty_matrix<C: CharT>(
     x: &AStr<C>,
     y: &AStr<C>,
     props: AlignmentProperties,
-) -> GlobalAlignment {
-    let mut c = vec![vec![0; y.len() + 1]; x.len() + 1];
+) -> Vec<Vec<usize>> {
+    let mut c = Vec::new();
+    fill_penalty_matrix(x, y, props, &mut c);
+    c
+}
+
+fn fill_penalty_matrix<C: CharT>(
+    x: &AStr<C>,
+    y: &AStr<C>,
+    props: AlignmentProperties,
+    c: &mut Vec<Vec<usize>>,
+) {
+    reset_matrix(c, x.len() + 1, y.len() + 1);
 
     let pen = Penalties { x, y, props };
 
@@ -23,10 +37,31 @@ pub fn global_alignment<C: CharT>(
     for i in 1..=x.len() {
         for j in 1..=y.len() {
             c[i][j] = pen
-                .diag(&c, i, j)
-                .min(pen.up(&c, i, j).min(pen.left(&c, i, j)));
+                .diag(c, i, j)
+                .min(pen.up(c, i, j).min(pen.left(c, i, j)));
         }
     }
+}
+
+pub fn global_alignment<C: CharT>(
+    x: &AStr<C>,
+    y: &AStr<C>,
+    props: AlignmentProperties,
+) -> GlobalAlignment {
+    global_alignment_with_buffer(x, y, props, &mut AlignmentBuffer::default())
+}
+
+/// Global alignment reusing the matrix allocated in `buffer`
+pub fn global_alignment_with_buffer<C: CharT>(
+    x: &AStr<C>,
+    y: &AStr<C>,
+    props: AlignmentProperties,
+    buffer: &mut AlignmentBuffer,
+) -> GlobalAlignment {
+    fill_penalty_matrix(x, y, props, &mut buffer.global);
+    let c = &buffer.global;
+
+    let pen = Penalties { x, y, props };
 
     let mut edits = AString::with_capacity(x.len());
     let mut i = x.len();
@@ -38,7 +73,7 @@ pub fn global_alignment<C: CharT>(
         } else if j == 0 {
             edits.push(Edit::Delete);
             i -= 1;
-        } else if pen.diag(&c, i, j) == c[i][j] {
+        } else if pen.diag(c, i, j) == c[i][j] {
             if x[i - 1] == y[j - 1] {
                 edits.push(Edit::Match);
             } else {
@@ -46,10 +81,10 @@ pub fn global_alignment<C: CharT>(
             }
             i -= 1;
             j -= 1;
-        } else if pen.up(&c, i, j) == c[i][j] {
+        } else if pen.up(c, i, j) == c[i][j] {
             edits.push(Edit::Delete);
             i -= 1;
-        } else if pen.left(&c, i, j) == c[i][j] {
+        } else if pen.left(c, i, j) == c[i][j] {
             edits.push(Edit::Insert);
             j -= 1;
         } else {
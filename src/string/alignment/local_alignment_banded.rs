@@ -0,0 +1,216 @@
+use crate::alphabet_model::CharT;
+use crate::string::alignment::{AlignmentProperties, Edit, LocalAlignment};
+use crate::string_model::{AStr, AString};
+use std::ops::{Range, RangeInclusive};
+
+const INFINITE: isize = isize::MAX / 4;
+
+/// Local alignment of y inside x (deletes in x at start and end are "free"), restricted to
+/// the cells `(i, j)` with diagonal `i - j` in `diagonals`. Returns `None` if no alignment
+/// fits in the band
+pub fn local_alignment_banded<C: CharT>(
+    x: &AStr<C>,
+    y: &AStr<C>,
+    props: AlignmentProperties,
+    diagonals: RangeInclusive<isize>,
+) -> Option<LocalAlignment> {
+    // diagonals outside of these contain no cells
+    let low = (*diagonals.start()).max(-(y.len() as isize));
+    let high = (*diagonals.end()).min(x.len() as isize);
+    if low > high {
+        return None;
+    }
+    let mut c = Band::new(x.len(), low, high);
+
+    let pen = Penalties { x, y, props };
+
+    for i in 0..=x.len() {
+        if c.contains(i, 0) {
+            c.set(i, 0, 0);
+        }
+    }
+
+    for j in 1..=y.len() {
+        if c.contains(0, j) {
+            c.set(0, j, (j * props.gap_penalty) as isize);
+        }
+    }
+
+    for i in 1..=x.len() {
+        let Some(j_range) = c.row_range(i, y.len()) else {
+            continue;
+        };
+        for j in j_range.start.max(1)..j_range.end {
+            let penalty = pen
+                .diag(&c, i, j)
+                .min(pen.up(&c, i, j).min(pen.left(&c, i, j)));
+            c.set(i, j, penalty);
+        }
+    }
+
+    let i_end = (0..=x.len()).min_by_key(|&i| c.get(i, y.len()))?;
+    if c.get(i_end, y.len()) >= INFINITE {
+        return None;
+    }
+
+    let mut edits = AString::with_capacity(y.len());
+    let mut i = i_end;
+    let mut j = y.len();
+    while j != 0 {
+        if i == 0 {
+            edits.push(Edit::Insert);
+            j -= 1;
+        } else if pen.diag(&c, i, j) == c.get(i, j) {
+            if x[i - 1] == y[j - 1] {
+                edits.push(Edit::Match);
+            } else {
+                edits.push(Edit::Mismatch);
+            }
+            i -= 1;
+            j -= 1;
+        } else if pen.up(&c, i, j) == c.get(i, j) {
+            edits.push(Edit::Delete);
+            i -= 1;
+        } else if pen.left(&c, i, j) == c.get(i, j) {
+            edits.push(Edit::Insert);
+            j -= 1;
+        } else {
+            unreachable!()
+        }
+    }
+
+    edits.reverse();
+
+    Some(LocalAlignment {
+        penalty: c.get(i_end, y.len()),
+        range: i..i_end,
+        edits,
+    })
+}
+
+/// Penalty matrix with only the cells `(i, j)` with diagonal `i - j` in `low..=high` stored,
+/// `high - low + 1` cells per row
+struct Band {
+    low: isize,
+    high: isize,
+    width: usize,
+    cells: Vec<isize>,
+}
+
+impl Band {
+    fn new(x_len: usize, low: isize, high: isize) -> Self {
+        let width = (high - low + 1) as usize;
+        Self {
+            low,
+            high,
+            width,
+            cells: vec![INFINITE; (x_len + 1) * width],
+        }
+    }
+
+    fn contains(&self, i: usize, j: usize) -> bool {
+        (self.low..=self.high).contains(&(i as isize - j as isize))
+    }
+
+    /// Range of `j` in the band in row `i`, if not empty
+    fn row_range(&self, i: usize, y_len: usize) -> Option<Range<usize>> {
+        let start = (i as isize - self.high).max(0) as usize;
+        let end = (i as isize - self.low + 1).min(y_len as isize + 1);
+        (end > start as isize).then_some(start..end as usize)
+    }
+
+    fn index(&self, i: usize, j: usize) -> usize {
+        i * self.width + (j as isize + self.high - i as isize) as usize
+    }
+
+    fn get(&self, i: usize, j: usize) -> isize {
+        if self.contains(i, j) {
+            self.cells[self.index(i, j)]
+        } else {
+            INFINITE
+        }
+    }
+
+    fn set(&mut self, i: usize, j: usize, penalty: isize) {
+        let index = self.index(i, j);
+        self.cells[index] = penalty;
+    }
+}
+
+struct Penalties<'s, C> {
+    x: &'s AStr<C>,
+    y: &'s AStr<C>,
+    props: AlignmentProperties,
+}
+
+impl<C: PartialEq> Penalties<'_, C> {
+    fn up(&self, c: &Band, i: usize, j: usize) -> isize {
+        c.get(i - 1, j) + self.props.gap_penalty as isize
+    }
+
+    fn left(&self, c: &Band, i: usize, j: usize) -> isize {
+        c.get(i, j - 1) + self.props.gap_penalty as isize
+    }
+
+    fn diag(&self, c: &Band, i: usize, j: usize) -> isize {
+        c.get(i - 1, j - 1)
+            + if self.x[i - 1] == self.y[j - 1] {
+                -(self.props.match_score as isize)
+            } else {
+                self.props.mismatch_penalty as isize
+            }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string::alignment::{is_edit, local_alignment_wagner_fischer};
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use core::str::FromStr;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+
+    fn edit(edits: &str) -> AString<Edit> {
+        AString::from_str(edits).unwrap()
+    }
+
+    #[test]
+    fn test_local_alignment_banded() {
+        let x = ascii("abcdabcd");
+        let y = ascii("cdcbc");
+        let align = local_alignment_banded(x, y, AlignmentProperties::default(), 1..=3).unwrap();
+        assert_eq!(align.penalty, -3);
+        assert_eq!(align.range, 2..7);
+        assert_eq!(align.edits, edit("==X=="));
+        assert!(is_edit(&x[align.range], y, &align.edits));
+
+        let align = local_alignment_banded(x, y, AlignmentProperties::default(), 3..=3).unwrap();
+        assert_eq!(align.range, 3..8);
+        assert!(is_edit(&x[align.range], y, &align.edits));
+
+        assert_eq!(
+            local_alignment_banded(x, y, AlignmentProperties::default(), 4..=10),
+            None
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn prop_test_local_alignment_banded(
+            x in arb_astring::<Char>(0..20),
+            y in arb_astring::<Char>(0..10))
+        {
+            let props = AlignmentProperties::default();
+            let expected = local_alignment_wagner_fischer::local_alignment(&x, &y, props);
+            let diagonals = -(y.len() as isize)..=x.len() as isize;
+            let align = local_alignment_banded(&x, &y, props, diagonals).unwrap();
+            prop_assert_eq!(align.penalty, expected.penalty);
+            prop_assert!(is_edit(&x[align.range], &y, &align.edits));
+        }
+    }
+}
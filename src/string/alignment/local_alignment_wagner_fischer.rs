@@ -1,5 +1,7 @@
 use crate::alphabet_model::CharT;
-use crate::string::alignment::{AlignmentProperties, Edit, LocalAlignment};
+use crate::string::alignment::{
+    AlignmentBuffer, AlignmentProperties, Edit, LocalAlignment, reset_matrix,
+};
 use crate::string_model::{AStr, AString};
 use core::fmt::{Display, Write};
 
@@ -9,7 +11,18 @@ pub fn local_alignment<C: CharT>(
     y: &AStr<C>,
     props: AlignmentProperties,
 ) -> LocalAlignment {
-    let mut c = vec![vec![0; y.len() + 1]; x.len() + 1];
+    local_alignment_with_buffer(x, y, props, &mut AlignmentBuffer::default())
+}
+
+/// Local alignment reusing the matrix allocated in `buffer`
+pub fn local_alignment_with_buffer<C: CharT>(
+    x: &AStr<C>,
+    y: &AStr<C>,
+    props: AlignmentProperties,
+    buffer: &mut AlignmentBuffer,
+) -> LocalAlignment {
+    let c = &mut buffer.local;
+    reset_matrix(c, x.len() + 1, y.len() + 1);
 
     let pen = Penalties { x, y, props };
 
@@ -20,8 +33,8 @@ pub fn local_alignment<C: CharT>(
     for i in 1..=x.len() {
         for j in 1..=y.len() {
             c[i][j] = pen
-                .diag(&c, i, j)
-                .min(pen.up(&c, i, j).min(pen.left(&c, i, j)));
+                .diag(c, i, j)
+                .min(pen.up(c, i, j).min(pen.left(c, i, j)));
         }
     }
 
@@ -42,7 +55,7 @@ pub fn local_alignment<C: CharT>(
         } else if i == 0 {
             edits.push(Edit::Insert);
             j -= 1;
-        } else if pen.diag(&c, i, j) == c[i][j] {
+        } else if pen.diag(c, i, j) == c[i][j] {
             if x[i - 1] == y[j - 1] {
                 edits.push(Edit::Match);
             } else {
@@ -50,10 +63,10 @@ pub fn local_alignment<C: CharT>(
             }
             i -= 1;
             j -= 1;
-        } else if pen.up(&c, i, j) == c[i][j] {
+        } else if pen.up(c, i, j) == c[i][j] {
             edits.push(Edit::Delete);
             i -= 1;
-        } else if pen.left(&c, i, j) == c[i][j] {
+        } else if pen.left(c, i, j) == c[i][j] {
             edits.push(Edit::Insert);
             j -= 1;
         } else {
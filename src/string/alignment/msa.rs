@@ -0,0 +1,654 @@
+//! Progressive multiple sequence alignment
+
+use crate::alphabet_model::{CharT, WithSpecial};
+use crate::string::alignment::AlignmentProperties;
+use crate::string::alignment::global_alignment_wagner_fischer::global_alignment;
+use crate::string_model::{AStr, AString};
+use crate::util::FastaEntry;
+use generic_array::GenericArray;
+use itertools::Itertools;
+use std::io::Write;
+
+pub type WithGap<C> = WithSpecial<C, '-', false>;
+
+const CLUSTAL_LINE_WIDTH: usize = 60;
+
+/// Multiple sequence alignment. All rows have the same length
+#[derive(Debug, Clone)]
+pub struct Msa<C> {
+    pub rows: Vec<MsaRow<C>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MsaRow<C> {
+    pub description: String,
+    pub chars: AString<WithGap<C>>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum GuideTreeMethod {
+    #[default]
+    Upgma,
+    NeighborJoining,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct MsaProperties {
+    pub alignment: AlignmentProperties,
+    pub guide_tree: GuideTreeMethod,
+}
+
+impl MsaProperties {
+    pub fn alignment(mut self, alignment: AlignmentProperties) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn guide_tree(mut self, guide_tree: GuideTreeMethod) -> Self {
+        self.guide_tree = guide_tree;
+        self
+    }
+}
+
+/// Binary guide tree over sequence indexes
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GuideTree {
+    Leaf(usize),
+    Join(Box<GuideTree>, Box<GuideTree>),
+}
+
+impl GuideTree {
+    fn join(left: GuideTree, right: GuideTree) -> Self {
+        GuideTree::Join(Box::new(left), Box::new(right))
+    }
+
+    /// Sequence indexes in the leaves, left to right
+    pub fn leaves(&self) -> Vec<usize> {
+        let mut res = Vec::new();
+        self.leaves_rec(&mut res);
+        res
+    }
+
+    fn leaves_rec(&self, res: &mut Vec<usize>) {
+        match self {
+            GuideTree::Leaf(idx) => res.push(*idx),
+            GuideTree::Join(left, right) => {
+                left.leaves_rec(res);
+                right.leaves_rec(res);
+            }
+        }
+    }
+}
+
+/// Character frequencies in a single alignment column
+#[derive(Debug, Clone)]
+pub struct Freq<C: CharT> {
+    counts: GenericArray<usize, C::AlphabetSize>,
+    gaps: usize,
+}
+
+impl<C: CharT> Default for Freq<C> {
+    fn default() -> Self {
+        Self {
+            counts: Default::default(),
+            gaps: 0,
+        }
+    }
+}
+
+impl<C: CharT> Freq<C> {
+    pub fn observe(&mut self, ch: C) {
+        self.counts[ch.index()] += 1;
+    }
+
+    pub fn observe_gap(&mut self) {
+        self.gaps += 1;
+    }
+
+    pub fn count(&self, ch: C) -> usize {
+        self.counts[ch.index()]
+    }
+
+    pub fn gaps(&self) -> usize {
+        self.gaps
+    }
+
+    /// Number of observed non-gap characters
+    pub fn chars(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Most frequent (non-gap) character. Ties are broken by alphabet order
+    pub fn most_frequent(&self) -> Option<C> {
+        let (idx, count) = self
+            .counts
+            .iter()
+            .copied()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, count)| *count)?;
+        (count != 0).then(|| C::from_index(idx))
+    }
+
+    fn pair_penalty(&self, other: &Self, props: AlignmentProperties) -> usize {
+        let same = self
+            .counts
+            .iter()
+            .zip(other.counts.iter())
+            .map(|(count, other_count)| count * other_count)
+            .sum::<usize>();
+        let mismatches = self.chars() * other.chars() - same;
+        let gaps = self.gaps * other.chars() + self.chars() * other.gaps;
+        mismatches * props.mismatch_penalty + gaps * props.gap_penalty
+    }
+
+    fn gap_penalty(&self, other_rows: usize, props: AlignmentProperties) -> usize {
+        self.chars() * other_rows * props.gap_penalty
+    }
+}
+
+/// Column profile of aligned rows
+#[derive(Debug, Clone)]
+pub struct Profile<C: CharT> {
+    pub columns: Vec<Freq<C>>,
+}
+
+impl<C: CharT> Profile<C> {
+    /// Profile of equal length, ungapped rows
+    pub fn from_rows<'a>(rows: impl IntoIterator<Item = &'a AStr<C>>) -> Self {
+        let mut columns = Vec::new();
+        for row in rows {
+            columns.resize_with(columns.len().max(row.len()), Freq::default);
+            for (freq, ch) in columns.iter_mut().zip(row.iter()) {
+                freq.observe(*ch);
+            }
+        }
+        Self { columns }
+    }
+
+    /// Profile of equal length, gapped rows
+    pub fn from_gapped_rows<'a>(rows: impl IntoIterator<Item = &'a AStr<WithGap<C>>>) -> Self {
+        let mut columns = Vec::new();
+        for row in rows {
+            columns.resize_with(columns.len().max(row.len()), Freq::default);
+            for (freq, ch) in columns.iter_mut().zip(row.iter()) {
+                match ch {
+                    WithGap::Char(ch) => freq.observe(*ch),
+                    WithGap::Special => freq.observe_gap(),
+                }
+            }
+        }
+        Self { columns }
+    }
+
+    /// Most frequent character in each column. Columns where gaps are in majority are left out
+    pub fn consensus(&self) -> AString<C> {
+        self.columns
+            .iter()
+            .filter_map(|freq| {
+                freq.most_frequent()
+                    .filter(|&ch| freq.count(ch) >= freq.gaps())
+            })
+            .collect()
+    }
+}
+
+impl<C: CharT> Msa<C>
+where
+    WithGap<C>: CharT,
+{
+    /// Number of columns
+    pub fn len(&self) -> usize {
+        self.rows.first().map(|row| row.chars.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn profile(&self) -> Profile<C> {
+        Profile::from_gapped_rows(self.rows.iter().map(|row| row.chars.as_str()))
+    }
+
+    pub fn consensus(&self) -> AString<C> {
+        self.profile().consensus()
+    }
+
+    pub fn write_fasta(&self, write: &mut impl Write) {
+        for row in &self.rows {
+            writeln!(write, ">{}", row.description).unwrap();
+            writeln!(write, "{}", row.chars).unwrap();
+        }
+    }
+
+    pub fn write_clustal(&self, write: &mut impl Write) {
+        let names = self
+            .rows
+            .iter()
+            .map(|row| row.description.split_whitespace().next().unwrap_or(""))
+            .collect_vec();
+        let name_width = names.iter().map(|name| name.len()).max().unwrap_or(0) + 4;
+
+        writeln!(write, "CLUSTAL W multiple sequence alignment").unwrap();
+        writeln!(write).unwrap();
+
+        for start in (0..self.len()).step_by(CLUSTAL_LINE_WIDTH) {
+            let end = self.len().min(start + CLUSTAL_LINE_WIDTH);
+            writeln!(write).unwrap();
+            for (name, row) in names.iter().zip(self.rows.iter()) {
+                writeln!(
+                    write,
+                    "{:width$}{}",
+                    name,
+                    &row.chars[start..end],
+                    width = name_width
+                )
+                .unwrap();
+            }
+            let conservation: String = (start..end)
+                .map(|col| {
+                    let first = self.rows[0].chars[col];
+                    if first != WithGap::Special
+                        && self.rows.iter().all(|row| row.chars[col] == first)
+                    {
+                        '*'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect();
+            writeln!(write, "{:width$}{}", "", conservation, width = name_width).unwrap();
+        }
+    }
+}
+
+/// Pairwise distances as global alignment penalty per alignment column
+pub fn distance_matrix<C: CharT>(strs: &[&AStr<C>], props: AlignmentProperties) -> Vec<Vec<f64>> {
+    let mut dist = vec![vec![0.0; strs.len()]; strs.len()];
+    for i in 0..strs.len() {
+        for j in i + 1..strs.len() {
+            let align = global_alignment(strs[i], strs[j], props);
+            let d = align.penalty as f64 / align.edits.len().max(1) as f64;
+            dist[i][j] = d;
+            dist[j][i] = d;
+        }
+    }
+    dist
+}
+
+/// Unweighted pair group method with arithmetic mean
+pub fn upgma(dist: &[Vec<f64>]) -> Option<GuideTree> {
+    let mut clusters = (0..dist.len())
+        .map(|idx| (GuideTree::Leaf(idx), 1usize))
+        .map(Some)
+        .collect_vec();
+    let mut dist = dist.to_vec();
+
+    for _ in 1..clusters.len() {
+        let (i, j) = (0..clusters.len())
+            .tuple_combinations()
+            .filter(|&(i, j)| clusters[i].is_some() && clusters[j].is_some())
+            .min_by(|&(i1, j1), &(i2, j2)| dist[i1][j1].total_cmp(&dist[i2][j2]))
+            .expect("at least two clusters");
+
+        let (tree_i, size_i) = clusters[i].take().unwrap();
+        let (tree_j, size_j) = clusters[j].take().unwrap();
+        for k in 0..clusters.len() {
+            if clusters[k].is_some() {
+                let d = (dist[i][k] * size_i as f64 + dist[j][k] * size_j as f64)
+                    / (size_i + size_j) as f64;
+                dist[i][k] = d;
+                dist[k][i] = d;
+            }
+        }
+        clusters[i] = Some((GuideTree::join(tree_i, tree_j), size_i + size_j));
+    }
+
+    clusters.into_iter().flatten().next().map(|(tree, _)| tree)
+}
+
+/// Neighbor joining. The unrooted tree is rooted at the last join
+pub fn neighbor_joining(dist: &[Vec<f64>]) -> Option<GuideTree> {
+    let mut clusters = (0..dist.len()).map(GuideTree::Leaf).map(Some).collect_vec();
+    let mut dist = dist.to_vec();
+
+    for remaining in (3..=clusters.len()).rev() {
+        let active = (0..clusters.len())
+            .filter(|&i| clusters[i].is_some())
+            .collect_vec();
+        let row_sums = (0..clusters.len())
+            .map(|i| active.iter().map(|&k| dist[i][k]).sum::<f64>())
+            .collect_vec();
+        let q =
+            |i: usize, j: usize| (remaining - 2) as f64 * dist[i][j] - row_sums[i] - row_sums[j];
+
+        let (i, j) = active
+            .iter()
+            .copied()
+            .tuple_combinations()
+            .min_by(|&(i1, j1), &(i2, j2)| q(i1, j1).total_cmp(&q(i2, j2)))
+            .expect("at least three clusters");
+
+        let tree_i = clusters[i].take().unwrap();
+        let tree_j = clusters[j].take().unwrap();
+        for &k in &active {
+            if k != i && k != j {
+                let d = (dist[i][k] + dist[j][k] - dist[i][j]) / 2.0;
+                dist[i][k] = d;
+                dist[k][i] = d;
+            }
+        }
+        clusters[i] = Some(GuideTree::join(tree_i, tree_j));
+    }
+
+    let mut remaining = clusters.into_iter().flatten();
+    match (remaining.next(), remaining.next()) {
+        (Some(left), Some(right)) => Some(GuideTree::join(left, right)),
+        (tree, _) => tree,
+    }
+}
+
+/// Progressive alignment: pairwise distances, guide tree and profile-profile alignment up the tree
+pub fn progressive_alignment<C: CharT>(entries: &[FastaEntry<C>], props: MsaProperties) -> Msa<C>
+where
+    WithGap<C>: CharT,
+{
+    let strs = entries
+        .iter()
+        .map(|entry| entry.polymer.as_str())
+        .collect_vec();
+    let dist = distance_matrix(&strs, props.alignment);
+    let tree = match props.guide_tree {
+        GuideTreeMethod::Upgma => upgma(&dist),
+        GuideTreeMethod::NeighborJoining => neighbor_joining(&dist),
+    };
+
+    let Some(tree) = tree else {
+        return Msa { rows: Vec::new() };
+    };
+
+    let group = align_tree(&tree, &strs, props.alignment);
+
+    let mut rows = vec![None; entries.len()];
+    for (idx, chars) in group.members.into_iter().zip(group.rows) {
+        rows[idx] = Some(MsaRow {
+            description: entries[idx].description.clone(),
+            chars,
+        });
+    }
+
+    Msa {
+        rows: rows.into_iter().map(|row| row.unwrap()).collect(),
+    }
+}
+
+/// Aligned rows of a subtree
+struct Group<C> {
+    members: Vec<usize>,
+    rows: Vec<AString<WithGap<C>>>,
+}
+
+fn align_tree<C: CharT>(tree: &GuideTree, strs: &[&AStr<C>], props: AlignmentProperties) -> Group<C>
+where
+    WithGap<C>: CharT,
+{
+    match tree {
+        GuideTree::Leaf(idx) => Group {
+            members: vec![*idx],
+            rows: vec![strs[*idx].iter().copied().map(WithGap::Char).collect()],
+        },
+        GuideTree::Join(left, right) => {
+            let left = align_tree(left, strs, props);
+            let right = align_tree(right, strs, props);
+            align_groups(left, right, props)
+        }
+    }
+}
+
+/// Aligns two groups of aligned rows by minimizing the sum of pairs penalty
+fn align_groups<C: CharT>(x: Group<C>, y: Group<C>, props: AlignmentProperties) -> Group<C>
+where
+    WithGap<C>: CharT,
+{
+    let x_prof = Profile::from_gapped_rows(x.rows.iter().map(|row| row.as_str()));
+    let y_prof = Profile::from_gapped_rows(y.rows.iter().map(|row| row.as_str()));
+    let x_cols = &x_prof.columns;
+    let y_cols = &y_prof.columns;
+
+    let up = |c: &Vec<Vec<usize>>, i: usize, j: usize| {
+        c[i - 1][j] + x_cols[i - 1].gap_penalty(y.rows.len(), props)
+    };
+    let left = |c: &Vec<Vec<usize>>, i: usize, j: usize| {
+        c[i][j - 1] + y_cols[j - 1].gap_penalty(x.rows.len(), props)
+    };
+    let diag = |c: &Vec<Vec<usize>>, i: usize, j: usize| {
+        c[i - 1][j - 1] + x_cols[i - 1].pair_penalty(&y_cols[j - 1], props)
+    };
+
+    let mut c = vec![vec![0; y_cols.len() + 1]; x_cols.len() + 1];
+    for i in 1..=x_cols.len() {
+        c[i][0] = up(&c, i, 0);
+    }
+    for j in 1..=y_cols.len() {
+        c[0][j] = left(&c, 0, j);
+    }
+    for i in 1..=x_cols.len() {
+        for j in 1..=y_cols.len() {
+            c[i][j] = diag(&c, i, j).min(up(&c, i, j).min(left(&c, i, j)));
+        }
+    }
+
+    let mut x_rows = vec![AString::default(); x.rows.len()];
+    let mut y_rows = vec![AString::default(); y.rows.len()];
+    let mut i = x_cols.len();
+    let mut j = y_cols.len();
+    while i != 0 || j != 0 {
+        let (x_col, y_col) = if i != 0 && j != 0 && diag(&c, i, j) == c[i][j] {
+            i -= 1;
+            j -= 1;
+            (Some(i), Some(j))
+        } else if i != 0 && (j == 0 || up(&c, i, j) == c[i][j]) {
+            i -= 1;
+            (Some(i), None)
+        } else {
+            j -= 1;
+            (None, Some(j))
+        };
+
+        for (row, src) in x_rows.iter_mut().zip(x.rows.iter()) {
+            row.push(x_col.map_or(WithGap::Special, |col| src[col]));
+        }
+        for (row, src) in y_rows.iter_mut().zip(y.rows.iter()) {
+            row.push(y_col.map_or(WithGap::Special, |col| src[col]));
+        }
+    }
+
+    Group {
+        members: x.members.into_iter().chain(y.members).collect(),
+        rows: x_rows
+            .into_iter()
+            .chain(y_rows)
+            .map(|mut row| {
+                row.reverse();
+                row
+            })
+            .collect(),
+    }
+}
+
+impl<C: CharT> Msa<C> {
+    /// Rows with gaps removed
+    pub fn ungapped_rows(&self) -> impl Iterator<Item = AString<C>> + '_ {
+        self.rows.iter().map(|row| {
+            row.chars
+                .iter()
+                .filter_map(|ch| match ch {
+                    WithGap::Char(ch) => Some(*ch),
+                    WithGap::Special => None,
+                })
+                .collect()
+        })
+    }
+}
+
+impl<C: CharT> FromIterator<MsaRow<C>> for Msa<C> {
+    fn from_iter<T: IntoIterator<Item = MsaRow<C>>>(iter: T) -> Self {
+        Self {
+            rows: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::polymers::DnaNt;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use core::str::FromStr;
+    use proptest::collection::vec;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+
+    fn entries(strs: &[&str]) -> Vec<FastaEntry<DnaNt>> {
+        strs.iter()
+            .enumerate()
+            .map(|(idx, str)| FastaEntry {
+                description: format!("seq{}", idx),
+                polymer: AString::from_str(str).unwrap(),
+            })
+            .collect()
+    }
+
+    fn row_strs(msa: &Msa<DnaNt>) -> Vec<String> {
+        msa.rows.iter().map(|row| row.chars.to_string()).collect()
+    }
+
+    #[test]
+    fn test_upgma() {
+        let dist = vec![
+            vec![0.0, 1.0, 4.0],
+            vec![1.0, 0.0, 4.0],
+            vec![4.0, 4.0, 0.0],
+        ];
+        assert_eq!(
+            upgma(&dist),
+            Some(GuideTree::join(
+                GuideTree::join(GuideTree::Leaf(0), GuideTree::Leaf(1)),
+                GuideTree::Leaf(2)
+            ))
+        );
+        assert_eq!(upgma(&[]), None);
+    }
+
+    #[test]
+    fn test_neighbor_joining() {
+        let dist = vec![
+            vec![0.0, 5.0, 9.0, 9.0, 8.0],
+            vec![5.0, 0.0, 10.0, 10.0, 9.0],
+            vec![9.0, 10.0, 0.0, 8.0, 7.0],
+            vec![9.0, 10.0, 8.0, 0.0, 3.0],
+            vec![8.0, 9.0, 7.0, 3.0, 0.0],
+        ];
+        let tree = neighbor_joining(&dist).unwrap();
+        assert!(has_clade(&tree, &[0, 1]));
+        assert!(has_clade(&tree, &[0, 1, 2]));
+        assert_eq!(
+            tree.leaves().into_iter().sorted().collect_vec(),
+            vec![0, 1, 2, 3, 4]
+        );
+    }
+
+    fn has_clade(tree: &GuideTree, clade: &[usize]) -> bool {
+        match tree {
+            GuideTree::Leaf(_) => false,
+            GuideTree::Join(left, right) => {
+                tree.leaves().into_iter().sorted().collect_vec() == clade
+                    || has_clade(left, clade)
+                    || has_clade(right, clade)
+            }
+        }
+    }
+
+    #[test]
+    fn test_progressive_alignment() {
+        let entries = entries(&["ACGTACGT", "ACGTACGT", "ACGACGT"]);
+        let msa = progressive_alignment(&entries, MsaProperties::default());
+        assert_eq!(row_strs(&msa), vec!["ACGTACGT", "ACGTACGT", "ACG-ACGT"]);
+        assert_eq!(
+            msa.consensus(),
+            AString::<DnaNt>::from_str("ACGTACGT").unwrap()
+        );
+        assert_eq!(msa.rows[2].description, "seq2");
+    }
+
+    #[test]
+    fn test_progressive_alignment_neighbor_joining() {
+        let entries = entries(&["ACGTACGT", "ACGTAGT", "ACGACGT", "ACGTACGT"]);
+        let msa = progressive_alignment(
+            &entries,
+            MsaProperties::default().guide_tree(GuideTreeMethod::NeighborJoining),
+        );
+        assert_eq!(msa.len(), 8);
+        assert_eq!(
+            msa.ungapped_rows().collect_vec(),
+            entries
+                .iter()
+                .map(|entry| entry.polymer.clone())
+                .collect_vec()
+        );
+    }
+
+    #[test]
+    fn test_profile_consensus() {
+        let rows = ["ACGT", "AGGT", "ACTT"].map(|str| AString::<DnaNt>::from_str(str).unwrap());
+        let profile = Profile::from_rows(rows.iter().map(|row| row.as_str()));
+        assert_eq!(profile.columns[1].count(DnaNt::C), 2);
+        assert_eq!(profile.columns[1].count(DnaNt::G), 1);
+        assert_eq!(
+            profile.consensus(),
+            AString::<DnaNt>::from_str("ACGT").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_clustal() {
+        let msa: Msa<DnaNt> = [("a x", "AC-T"), ("bb", "ACGT")]
+            .into_iter()
+            .map(|(description, chars)| MsaRow {
+                description: description.to_string(),
+                chars: AString::from_str(chars).unwrap(),
+            })
+            .collect();
+        let mut out = Vec::new();
+        msa.write_clustal(&mut out);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "CLUSTAL W multiple sequence alignment\n\n\na     AC-T\nbb    ACGT\n      ** *\n"
+        );
+
+        let mut out = Vec::new();
+        msa.write_fasta(&mut out);
+        assert_eq!(String::from_utf8(out).unwrap(), ">a x\nAC-T\n>bb\nACGT\n");
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn prop_test_progressive_alignment(strs in vec(arb_astring::<Char>(0..10), 1..6)) {
+            let entries = strs
+                .iter()
+                .map(|str| FastaEntry { description: String::new(), polymer: str.clone() })
+                .collect_vec();
+            let msa = progressive_alignment(&entries, MsaProperties::default());
+            prop_assert_eq!(msa.ungapped_rows().collect_vec(), strs);
+            for row in &msa.rows {
+                prop_assert_eq!(row.chars.len(), msa.len());
+            }
+            for col in 0..msa.len() {
+                prop_assert!(msa.rows.iter().any(|row| row.chars[col] != WithGap::Special));
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::alphabet_model::CharT;
+use crate::string::alignment::{AlignmentProperties, Edit, OverlapAlignment};
+use crate::string_model::{AStr, AString};
+
+/// Overlap alignment of a suffix of x with a prefix of y (deletes in x at start and inserts
+/// of y at end are "free")
+pub fn overlap_alignment<C: CharT>(
+    x: &AStr<C>,
+    y: &AStr<C>,
+    props: AlignmentProperties,
+) -> OverlapAlignment {
+    let mut c = vec![vec![0isize; y.len() + 1]; x.len() + 1];
+
+    let pen = Penalties { x, y, props };
+
+    for (j, cell) in c[0].iter_mut().enumerate() {
+        *cell = (j * props.gap_penalty) as isize;
+    }
+
+    for i in 1..=x.len() {
+        for j in 1..=y.len() {
+            c[i][j] = pen
+                .diag(&c, i, j)
+                .min(pen.up(&c, i, j).min(pen.left(&c, i, j)));
+        }
+    }
+
+    // prefer longest overlap among the best
+    let mut j_end = 0;
+    for j in 0..=y.len() {
+        if c[x.len()][j] <= c[x.len()][j_end] {
+            j_end = j;
+        }
+    }
+    let j_end = j_end;
+
+    let mut edits = AString::with_capacity(y.len());
+    let mut i = x.len();
+    let mut j = j_end;
+    while j != 0 {
+        if i == 0 {
+            edits.push(Edit::Insert);
+            j -= 1;
+        } else if pen.diag(&c, i, j) == c[i][j] {
+            if x[i - 1] == y[j - 1] {
+                edits.push(Edit::Match);
+            } else {
+                edits.push(Edit::Mismatch);
+            }
+            i -= 1;
+            j -= 1;
+        } else if pen.up(&c, i, j) == c[i][j] {
+            edits.push(Edit::Delete);
+            i -= 1;
+        } else if pen.left(&c, i, j) == c[i][j] {
+            edits.push(Edit::Insert);
+            j -= 1;
+        } else {
+            unreachable!()
+        }
+    }
+
+    edits.reverse();
+
+    OverlapAlignment {
+        penalty: c[x.len()][j_end],
+        x_start: i,
+        y_end: j_end,
+        edits,
+    }
+}
+
+struct Penalties<'s, C> {
+    x: &'s AStr<C>,
+    y: &'s AStr<C>,
+    props: AlignmentProperties,
+}
+
+impl<C: PartialEq> Penalties<'_, C> {
+    fn up(&self, c: &[Vec<isize>], i: usize, j: usize) -> isize {
+        c[i - 1][j] + self.props.gap_penalty as isize
+    }
+
+    fn left(&self, c: &[Vec<isize>], i: usize, j: usize) -> isize {
+        c[i][j - 1] + self.props.gap_penalty as isize
+    }
+
+    fn diag(&self, c: &[Vec<isize>], i: usize, j: usize) -> isize {
+        c[i - 1][j - 1]
+            + if self.x[i - 1] == self.y[j - 1] {
+                -(self.props.match_score as isize)
+            } else {
+                self.props.mismatch_penalty as isize
+            }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string::alignment::is_edit;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use core::str::FromStr;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+
+    fn edit(edits: &str) -> AString<Edit> {
+        AString::from_str(edits).unwrap()
+    }
+
+    #[test]
+    fn test_overlap_alignment() {
+        let align = overlap_alignment(
+            ascii("xxxabcdef"),
+            ascii("abcxefyyyy"),
+            AlignmentProperties::default(),
+        );
+        assert_eq!(align.penalty, -4);
+        assert_eq!(align.x_start, 3);
+        assert_eq!(align.y_end, 6);
+        assert_eq!(align.edits, edit("===X=="));
+
+        let align = overlap_alignment(ascii("abc"), ascii("xyz"), AlignmentProperties::default());
+        assert_eq!(align.penalty, 0);
+        assert_eq!(align.x_start, 3);
+        assert_eq!(align.y_end, 0);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn prop_test_overlap_alignment(
+            x in arb_astring::<Char>(0..15),
+            y in arb_astring::<Char>(0..15))
+        {
+            let props = AlignmentProperties::default();
+            let align = overlap_alignment(&x, &y, props);
+            prop_assert!(is_edit(&x[align.x_start..], &y[..align.y_end], &align.edits));
+
+            // exact overlaps are no better than the optimal alignment
+            for len in 0..=x.len().min(y.len()) {
+                if x[x.len() - len..] == y[..len] {
+                    prop_assert!(align.penalty <= -(len as isize));
+                }
+            }
+            let matches = align.edits.iter().filter(|&&edit| edit == Edit::Match).count();
+            prop_assert_eq!(
+                align.penalty,
+                (align.edits.len() - matches) as isize - matches as isize
+            );
+        }
+    }
+}
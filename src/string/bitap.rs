@@ -0,0 +1,310 @@
+//! Bit-parallel approximate matching of patterns of up to 64 chars (Shift-And, and its
+//! extension to mismatches and edits by Wu and Manber). Bit `j` of the state for distance `d`
+//! is set if `pattern[..=j]` matches a suffix of the text read so far with at most `d`
+//! mismatches or edits
+
+use crate::alphabet_model::CharT;
+use crate::string_model::AStr;
+use generic_array::typenum::Unsigned;
+use std::iter;
+use std::marker::PhantomData;
+
+/// Longest pattern supported
+pub const MAX_PATTERN_LEN: usize = u64::BITS as usize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Distance {
+    /// Mismatches only
+    Hamming,
+    /// Mismatches, insertions and deletions
+    Levenshtein,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BitapProperties {
+    /// Largest distance of an occurrence
+    pub max_distance: usize,
+    pub distance: Distance,
+}
+
+impl BitapProperties {
+    pub fn max_distance(mut self, max_distance: usize) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    pub fn distance(mut self, distance: Distance) -> Self {
+        self.distance = distance;
+        self
+    }
+}
+
+impl Default for BitapProperties {
+    fn default() -> Self {
+        Self {
+            max_distance: 1,
+            distance: Distance::Levenshtein,
+        }
+    }
+}
+
+/// Occurrence of the pattern ending at `end` (exclusive) with the smallest distance of the
+/// occurrences ending there
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitapMatch {
+    pub end: usize,
+    pub distance: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bitap<C> {
+    pattern_len: usize,
+    /// Bit `j` of the mask for a char, by index, is set if `pattern[j]` is the char
+    masks: Vec<u64>,
+    props: BitapProperties,
+    _char: PhantomData<C>,
+}
+
+/// Mask with the lowest `len` bits set
+fn low_bits(len: usize) -> u64 {
+    if len >= MAX_PATTERN_LEN {
+        u64::MAX
+    } else {
+        (1 << len) - 1
+    }
+}
+
+impl<C: CharT> Bitap<C> {
+    pub fn new(pattern: &AStr<C>, props: BitapProperties) -> Self {
+        assert!(
+            (1..=MAX_PATTERN_LEN).contains(&pattern.len()),
+            "pattern length must be 1 to {}",
+            MAX_PATTERN_LEN
+        );
+        let mut masks = vec![0; C::AlphabetSize::USIZE];
+        for (pos, ch) in pattern.iter().enumerate() {
+            masks[ch.index()] |= 1 << pos;
+        }
+        Self {
+            pattern_len: pattern.len(),
+            masks,
+            props,
+            _char: PhantomData,
+        }
+    }
+
+    /// Occurrences of the pattern in `text` by increasing end position
+    pub fn find_iter<'a>(&'a self, text: &'a AStr<C>) -> impl Iterator<Item = BitapMatch> + 'a {
+        let max_distance = self.props.max_distance;
+        let levenshtein = self.props.distance == Distance::Levenshtein;
+        let accept = 1 << (self.pattern_len - 1);
+        // with edits, the first d chars of the pattern can be deleted before the text
+        let mut states = (0..=max_distance)
+            .map(|distance| if levenshtein { low_bits(distance) } else { 0 })
+            .collect::<Vec<u64>>();
+        let mut pos = 0;
+        let mut started = false;
+        iter::from_fn(move || {
+            loop {
+                if started {
+                    let ch = text.get(pos)?;
+                    let mask = self.masks[ch.index()];
+                    pos += 1;
+
+                    let mut prev_state = states[0];
+                    states[0] = ((states[0] << 1) | 1) & mask;
+                    for distance in 1..=max_distance {
+                        let state = states[distance];
+                        let matched = ((state << 1) | 1) & mask;
+                        let mismatched = (prev_state << 1) | 1;
+                        states[distance] = if levenshtein {
+                            let deleted = (states[distance - 1] << 1) | 1;
+                            let inserted = prev_state;
+                            matched | mismatched | deleted | inserted
+                        } else {
+                            matched | mismatched
+                        };
+                        prev_state = state;
+                    }
+                }
+                started = true;
+
+                if let Some(distance) = states.iter().position(|state| state & accept != 0) {
+                    return Some(BitapMatch { end: pos, distance });
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string;
+    use crate::string_model::test_util::Char;
+    use crate::string_model::{AString, arb_astring};
+    use hashbrown::HashMap;
+    use itertools::Itertools;
+    use proptest::prelude::{ProptestConfig, any};
+    use proptest::{prop_assert_eq, proptest};
+    use std::collections::BTreeMap;
+    use std::hash::Hash;
+
+    /// Strings within the given distance of `pattern`, with their distance
+    fn neighbourhood<C: CharT + Hash>(
+        pattern: &AStr<C>,
+        max_distance: usize,
+        distance: Distance,
+    ) -> HashMap<AString<C>, usize> {
+        let mut result = HashMap::from([(pattern.to_owned(), 0)]);
+        let mut current = vec![pattern.to_owned()];
+        for edits in 1..=max_distance {
+            let mut next = Vec::new();
+            for s in &current {
+                let mut edited = Vec::new();
+                for pos in 0..=s.len() {
+                    for ch_index in 0..C::AlphabetSize::USIZE {
+                        let ch = C::from_index(ch_index);
+                        if pos < s.len() {
+                            let mut substituted = s.to_vec();
+                            substituted[pos] = ch;
+                            edited.push(AString::from(substituted));
+                        }
+                        if distance == Distance::Levenshtein {
+                            let mut inserted = s.to_vec();
+                            inserted.insert(pos, ch);
+                            edited.push(AString::from(inserted));
+                        }
+                    }
+                    if distance == Distance::Levenshtein && pos < s.len() {
+                        let mut deleted = s.to_vec();
+                        deleted.remove(pos);
+                        edited.push(AString::from(deleted));
+                    }
+                }
+                for s in edited {
+                    if !result.contains_key(&s) {
+                        result.insert(s.clone(), edits);
+                        next.push(s);
+                    }
+                }
+            }
+            current = next;
+        }
+        result
+    }
+
+    /// Finds each string of the neighbourhood of the pattern in the text
+    fn brute_force<C: CharT + Hash>(
+        text: &AStr<C>,
+        pattern: &AStr<C>,
+        props: BitapProperties,
+    ) -> Vec<BitapMatch> {
+        let mut ends = BTreeMap::new();
+        for (s, distance) in neighbourhood(pattern, props.max_distance, props.distance) {
+            for index in string::indexes(text, &s) {
+                let end_distance = ends.entry(index + s.len()).or_insert(distance);
+                *end_distance = distance.min(*end_distance);
+            }
+        }
+        ends.into_iter()
+            .map(|(end, distance)| BitapMatch { end, distance })
+            .collect()
+    }
+
+    #[test]
+    fn test_exact() {
+        let bitap = Bitap::new(ascii("abc"), BitapProperties::default().max_distance(0));
+        assert_eq!(
+            bitap.find_iter(ascii("xabcabcy")).collect_vec(),
+            vec![
+                BitapMatch {
+                    end: 4,
+                    distance: 0
+                },
+                BitapMatch {
+                    end: 7,
+                    distance: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hamming() {
+        let props = BitapProperties::default()
+            .max_distance(1)
+            .distance(Distance::Hamming);
+        let bitap = Bitap::new(ascii("abcd"), props);
+        assert_eq!(
+            bitap.find_iter(ascii("xxabxdxxabcd")).collect_vec(),
+            vec![
+                BitapMatch {
+                    end: 6,
+                    distance: 1
+                },
+                BitapMatch {
+                    end: 12,
+                    distance: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        let props = BitapProperties::default()
+            .max_distance(1)
+            .distance(Distance::Levenshtein);
+        let bitap = Bitap::new(ascii("abcd"), props);
+        let ends = bitap
+            .find_iter(ascii("xxabdxxabxcdx"))
+            .map(|mtch| (mtch.end, mtch.distance))
+            .collect_vec();
+        // "abd" with "c" deleted, and "abxcd" with "x" inserted
+        assert_eq!(ends, vec![(5, 1), (12, 1)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_pattern_too_long() {
+        let pattern = AString::from(vec![Char::A; MAX_PATTERN_LEN + 1]);
+        Bitap::new(&pattern, BitapProperties::default());
+    }
+
+    #[test]
+    fn test_long_pattern() {
+        let pattern = AString::from(
+            (0..MAX_PATTERN_LEN)
+                .map(|i| Char::from_index(i % 2))
+                .collect_vec(),
+        );
+        let mut text = pattern.to_vec();
+        text[10] = Char::from_index(1 - text[10].index());
+        let text = AString::from(text);
+        let bitap = Bitap::new(&pattern, BitapProperties::default().max_distance(2));
+        assert_eq!(
+            bitap.find_iter(&text).map(|mtch| mtch.distance).min(),
+            Some(1)
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn prop_test_bitap(
+            text in arb_astring::<Char>(0..30),
+            pattern in arb_astring::<Char>(1..6),
+            max_distance in 0..3usize,
+            levenshtein in any::<bool>(),
+        ) {
+            let props = BitapProperties::default()
+                .max_distance(max_distance)
+                .distance(if levenshtein { Distance::Levenshtein } else { Distance::Hamming });
+            let bitap = Bitap::new(&pattern, props);
+            prop_assert_eq!(bitap.find_iter(&text).collect_vec(), brute_force(&text, &pattern, props));
+        }
+    }
+}
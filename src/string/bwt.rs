@@ -11,6 +11,7 @@ use std::borrow::Borrow;
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::iter;
+use std::ops::Range;
 
 type Ranks<C: CharT2> = GenericArray<usize, C::AlphabetSizeP1>;
 
@@ -30,7 +31,7 @@ pub struct BWT<C: CharT2> {
     pub suffix_offset_hist: RefCell<Histogram<u64>>,
 }
 
-type WithTerminal<C> = WithSpecial<C, '$', true>;
+pub(crate) type WithTerminal<C> = WithSpecial<C, '$', true>;
 
 pub fn build_bwt<'s, C: CharT2>(s: &'s AStr<C>) -> BWT<C>
 where
@@ -39,11 +40,10 @@ where
     build_bwt_with(s, 5, 10)
 }
 
-pub fn build_bwt_with<'s, C: CharT2>(
-    s: &'s AStr<C>,
-    rank_sparse_factor: usize,
-    suffix_array_sparse_factor: usize,
-) -> BWT<C>
+/// BWT transform and suffix array of `s` terminated by `$`
+pub(crate) fn build_l_and_suffix_array<C: CharT2>(
+    s: &AStr<C>,
+) -> (AString<WithTerminal<C>>, Vec<usize>)
 where
     WithTerminal<C>: CharT,
 {
@@ -82,17 +82,39 @@ where
         }
     }
 
+    (l, suffix_array)
+}
+
+/// Index of first occurrence of each char in f, the sorted chars of `l`
+pub(crate) fn f_char_indexes<C: CharT2>(
+    l: &AStr<WithTerminal<C>>,
+) -> GenericArray<usize, C::AlphabetSizeP2>
+where
+    WithTerminal<C>: CharT,
+{
     let mut char_count = vec![0; <WithTerminal::<C> as CharT>::AlphabetSize::USIZE];
     for char in l.iter().copied() {
         char_count[char.index()] += 1;
     }
 
-    let f_char_indexes: GenericArray<_, _> = iter::once(0)
+    iter::once(0)
         .chain(char_count.iter().copied().scan(0, |cumulated, count| {
             *cumulated += count;
             Some(*cumulated)
         }))
-        .collect();
+        .collect()
+}
+
+pub fn build_bwt_with<'s, C: CharT2>(
+    s: &'s AStr<C>,
+    rank_sparse_factor: usize,
+    suffix_array_sparse_factor: usize,
+) -> BWT<C>
+where
+    WithTerminal<C>: CharT,
+{
+    let (l, suffix_array) = build_l_and_suffix_array(s);
+    let f_char_indexes = f_char_indexes::<C>(&l);
 
     let l_ranks_sparse = l
         .iter()
@@ -143,43 +165,71 @@ where
         self.f_char_indexes[ch.index()] + self.l_rank(idx, ch) - 1
     }
 
-    pub fn indexes_substr(&self, t: &AStr<C>) -> HashSet<usize> {
-        let mut low = 0;
-        let mut high = self.l.len() - 1;
+    /// Number of occurrences of `ch` in `l[..idx]`
+    fn l_rank_exclusive(&self, idx: usize, ch: WithTerminal<C>) -> usize {
+        if idx == 0 {
+            0
+        } else {
+            self.l_rank(idx - 1, ch)
+        }
+    }
+
+    /// Range of all rows in the sorted suffixes (matches the empty string)
+    pub fn full_range(&self) -> Range<usize> {
+        0..self.l.len()
+    }
+
+    /// Narrows range of rows matching `t` to the rows matching `ch` followed by `t`
+    pub fn extend_backward(&self, range: Range<usize>, ch: C) -> Range<usize> {
+        let ch_w = WithTerminal::Char(ch);
+        let low = self.f_char_indexes[ch_w.index()] + self.l_rank_exclusive(range.start, ch_w);
+        let high = self.f_char_indexes[ch_w.index()] + self.l_rank_exclusive(range.end, ch_w);
+        low..high
+    }
+
+    /// Range of rows in the sorted suffixes that have `t` as prefix
+    pub fn suffix_range(&self, t: &AStr<C>) -> Range<usize> {
+        let mut range = self.full_range();
 
         for ch in t.iter().copied().rev() {
-            let ch_w = WithTerminal::Char(ch);
-            low = self.f_char_indexes[ch_w.index()]
-                + if low == 0 {
-                    0
-                } else {
-                    self.l_rank(low - 1, ch_w)
-                };
-            high = self.f_char_indexes[ch_w.index()] + self.l_rank(high, ch_w) - 1;
-
-            if high < low {
-                return Default::default();
+            range = self.extend_backward(range, ch);
+
+            if range.is_empty() {
+                break;
             }
         }
 
-        (low..=high)
-            .into_iter()
-            .map(|mut idx| {
-                let mut suffix_offset = 0;
-                while idx % self.suffix_array_sparse_factor != 0 {
-                    suffix_offset += 1;
-                    idx = self.lf_map(idx);
-                }
-
-                self.suffix_offset_hist
-                    .borrow_mut()
-                    .record(suffix_offset as u64)
-                    .unwrap();
-
-                (self.suffix_array_sparse[idx / self.suffix_array_sparse_factor] + suffix_offset)
-                    % self.l.len()
-            })
-            .collect()
+        range
+    }
+
+    /// Index in s of the suffix in row `idx` of the sorted suffixes
+    pub fn locate(&self, mut idx: usize) -> usize {
+        let mut suffix_offset = 0;
+        while idx % self.suffix_array_sparse_factor != 0 {
+            suffix_offset += 1;
+            idx = self.lf_map(idx);
+        }
+
+        self.suffix_offset_hist
+            .borrow_mut()
+            .record(suffix_offset as u64)
+            .unwrap();
+
+        (self.suffix_array_sparse[idx / self.suffix_array_sparse_factor] + suffix_offset)
+            % self.l.len()
+    }
+
+    pub fn indexes_substr(&self, t: &AStr<C>) -> HashSet<usize> {
+        self.suffix_range(t).map(|idx| self.locate(idx)).collect()
+    }
+
+    /// Length of the indexed string (without terminal)
+    pub fn len(&self) -> usize {
+        self.l.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
@@ -0,0 +1,266 @@
+//! Sequence complexity measures and masking of low complexity regions, such as
+//! microsatellites and poly-A tails, before seeding alignments. Windows are scored by the
+//! DUST score (Morgulis et al.) and by linguistic complexity (Trifonov)
+
+use crate::alphabet_model::CharT;
+use crate::string::suffix_array::build_array;
+use crate::string_model::AStr;
+use alloc::borrow::Cow;
+use generic_array::typenum::Unsigned;
+use hashbrown::HashMap;
+use std::iter;
+use std::ops::Range;
+
+/// Number of distinct non-empty substrings of `s`, from the suffix array and LCP array
+pub fn distinct_substrings<C: CharT>(s: &AStr<C>) -> usize {
+    let array = build_array(Cow::Borrowed(s));
+    // each suffix adds its prefixes longer than the common prefix with the previous suffix
+    array
+        .sorted_suffixes()
+        .iter()
+        .zip(array.lcp_array())
+        .map(|(&suffix, lcp)| s.len() - suffix - lcp)
+        .sum()
+}
+
+/// Number of distinct non-empty substrings of a string of length `len` with all substrings of
+/// each length as distinct as possible
+fn max_distinct_substrings<C: CharT>(len: usize) -> usize {
+    let alphabet_size = C::AlphabetSize::USIZE;
+    let mut max = 0;
+    let mut words = 1usize;
+    for k in 1..=len {
+        words = words.saturating_mul(alphabet_size);
+        max += words.min(len - k + 1);
+    }
+    max
+}
+
+/// Distinct substrings of `s` relative to the most possible for its length, from 0 to 1. Low
+/// values indicate repetitive sequence
+pub fn linguistic_complexity<C: CharT>(s: &AStr<C>) -> f64 {
+    if s.is_empty() {
+        return 1.0;
+    }
+    distinct_substrings(s) as f64 / max_distinct_substrings::<C>(s.len()) as f64
+}
+
+/// DUST score of `s`, the number of pairs of equal words of length `word_len` divided by the
+/// number of words less one. Random DNA scores below 1 for words of length 3, and a
+/// homopolymer of length `n` scores `(n - word_len + 1) / 2`
+pub fn dust_score<C: CharT>(s: &AStr<C>, word_len: usize) -> f64 {
+    let word_len = word_len.max(1);
+    if s.len() < word_len + 1 {
+        return 0.0;
+    }
+    let alphabet_size = C::AlphabetSize::USIZE;
+    let mut counts = HashMap::<usize, usize>::new();
+    for word in s.windows(word_len) {
+        let code = word.iter().fold(0usize, |code, ch| {
+            code.wrapping_mul(alphabet_size) + ch.index()
+        });
+        *counts.entry(code).or_default() += 1;
+    }
+    let pairs: usize = counts.values().map(|&count| count * (count - 1) / 2).sum();
+    let words = s.len() - word_len + 1;
+    pairs as f64 / (words - 1) as f64
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct LowComplexityProperties {
+    /// Length of the scored windows
+    pub window: usize,
+    /// Distance between the starts of consecutive windows
+    pub step: usize,
+    /// Word length for the DUST score
+    pub word_len: usize,
+    /// Windows with a higher DUST score are masked
+    pub max_dust_score: f64,
+    /// Windows with a lower linguistic complexity are masked
+    pub min_linguistic_complexity: f64,
+}
+
+impl LowComplexityProperties {
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn step(mut self, step: usize) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn word_len(mut self, word_len: usize) -> Self {
+        self.word_len = word_len;
+        self
+    }
+
+    pub fn max_dust_score(mut self, max_dust_score: f64) -> Self {
+        self.max_dust_score = max_dust_score;
+        self
+    }
+
+    pub fn min_linguistic_complexity(mut self, min_linguistic_complexity: f64) -> Self {
+        self.min_linguistic_complexity = min_linguistic_complexity;
+        self
+    }
+}
+
+impl Default for LowComplexityProperties {
+    fn default() -> Self {
+        Self {
+            window: 64,
+            step: 16,
+            word_len: 3,
+            max_dust_score: 2.0,
+            min_linguistic_complexity: 0.5,
+        }
+    }
+}
+
+/// Start positions of the windows of `s`, with the last window ending at the end of `s`
+fn window_starts(len: usize, window: usize, step: usize) -> impl Iterator<Item = usize> {
+    let last = len.saturating_sub(window);
+    (0..last).step_by(step.max(1)).chain(iter::once(last))
+}
+
+/// Linguistic complexity of the windows of `s` by start position
+pub fn windowed_linguistic_complexity<C: CharT>(
+    s: &AStr<C>,
+    props: LowComplexityProperties,
+) -> Vec<(usize, f64)> {
+    window_starts(s.len(), props.window, props.step)
+        .map(|start| {
+            let end = (start + props.window).min(s.len());
+            (start, linguistic_complexity(&s[start..end]))
+        })
+        .collect()
+}
+
+/// Low complexity regions of `s`, the union of the windows with a too high DUST score or a
+/// too low linguistic complexity, ordered and not overlapping
+pub fn low_complexity_regions<C: CharT>(
+    s: &AStr<C>,
+    props: LowComplexityProperties,
+) -> Vec<Range<usize>> {
+    let mut regions: Vec<Range<usize>> = Vec::new();
+    if s.is_empty() {
+        return regions;
+    }
+    for start in window_starts(s.len(), props.window, props.step) {
+        let end = (start + props.window).min(s.len());
+        let window = &s[start..end];
+        if dust_score(window, props.word_len) > props.max_dust_score
+            || linguistic_complexity(window) < props.min_linguistic_complexity
+        {
+            match regions.last_mut() {
+                Some(last) if last.end >= start => last.end = end,
+                _ => regions.push(start..end),
+            }
+        }
+    }
+    regions
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Masking {
+    /// Lowercase the masked chars (soft masking)
+    Lowercase,
+    /// Replace the masked chars by the given char (hard masking), e.g. `N` for DNA
+    Replace(char),
+}
+
+/// `s` as text with the chars in the given ordered regions masked
+pub fn mask<C: CharT>(s: &AStr<C>, regions: &[Range<usize>], masking: Masking) -> String {
+    let mut regions = regions.iter().peekable();
+    s.iter()
+        .enumerate()
+        .map(|(pos, ch)| {
+            while regions.next_if(|region| region.end <= pos).is_some() {}
+            let masked = regions.peek().is_some_and(|region| region.contains(&pos));
+            match masking {
+                _ if !masked => ch.to_char(),
+                Masking::Lowercase => ch.to_char().to_ascii_lowercase(),
+                Masking::Replace(replacement) => replacement,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::polymers::DnaNt;
+    use crate::string_model::test_util::Char;
+    use crate::string_model::test_util::random_dna;
+    use crate::string_model::{AString, arb_astring};
+    use hashbrown::HashSet;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_distinct_substrings() {
+        assert_eq!(distinct_substrings(ascii("")), 0);
+        assert_eq!(distinct_substrings(ascii("aaa")), 3);
+        assert_eq!(distinct_substrings(ascii("abab")), 7);
+    }
+
+    #[test]
+    fn test_linguistic_complexity() {
+        let homopolymer: AString<DnaNt> = "AAAAAAAA".parse().unwrap();
+        assert_eq!(
+            linguistic_complexity(&homopolymer),
+            8.0 / (4 + 7 + 6 + 5 + 4 + 3 + 2 + 1) as f64
+        );
+        let full: AString<DnaNt> = "ACGT".parse().unwrap();
+        assert_eq!(linguistic_complexity(&full), 1.0);
+        assert!(linguistic_complexity(&random_dna(&mut StdRng::seed_from_u64(1), 200)) > 0.9);
+    }
+
+    #[test]
+    fn test_dust_score() {
+        let homopolymer: AString<DnaNt> = "AAAAAAAAAAAA".parse().unwrap();
+        assert_eq!(dust_score(&homopolymer, 3), (12.0 - 3.0 + 1.0) / 2.0);
+        assert_eq!(dust_score(ascii("abcdef"), 3), 0.0);
+        assert!(dust_score(&random_dna(&mut StdRng::seed_from_u64(2), 64), 3) < 1.0);
+    }
+
+    #[test]
+    fn test_low_complexity_regions() {
+        let mut dna = random_dna(&mut StdRng::seed_from_u64(3), 300).to_vec();
+        let microsatellite: AString<DnaNt> = "CA".repeat(40).parse().unwrap();
+        dna.splice(100..180, microsatellite.iter().copied());
+        let dna = AString::from(dna);
+
+        let regions = low_complexity_regions(&dna, LowComplexityProperties::default());
+        assert_eq!(regions.len(), 1);
+        assert!(regions[0].start <= 100 && regions[0].end >= 180);
+        assert!(regions[0].len() < 80 + 2 * 64);
+    }
+
+    #[test]
+    fn test_mask() {
+        let dna: AString<DnaNt> = "ACGTACGT".parse().unwrap();
+        assert_eq!(mask(&dna, &[1..3, 5..6], Masking::Lowercase), "AcgTAcGT");
+        assert_eq!(mask(&dna, &[1..3, 5..6], Masking::Replace('N')), "ANNTANGT");
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn prop_test_distinct_substrings(s in arb_astring::<Char>(0..30)) {
+            let substrings = (0..s.len())
+                .flat_map(|start| (start + 1..=s.len()).map(move |end| (start, end)))
+                .map(|(start, end)| s[start..end].to_vec())
+                .collect::<HashSet<_>>();
+            prop_assert_eq!(distinct_substrings(&s), substrings.len());
+            let complexity = linguistic_complexity(&s);
+            prop_assert!(complexity > 0.0 && complexity <= 1.0);
+        }
+    }
+}
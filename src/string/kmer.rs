@@ -0,0 +1,659 @@
+//! K-mer counting. K-mers are packed into a `u64` with the fewest bits per char that fit the
+//! alphabet (2 bits for DNA), and the packed k-mer is updated in rolling fashion along the
+//! sequence. The k-mer spectrum (number of distinct k-mers with each count) is used to estimate
+//! k-mer coverage, genome size and heterozygosity by fitting a mixture of the heterozygous and
+//! homozygous Poisson peaks, in the spirit of GenomeScope
+
+use crate::alphabet_model::CharT;
+use crate::polymers::DnaNt;
+use crate::string_model::{AStr, AString};
+use generic_array::typenum::Unsigned;
+use hashbrown::HashMap;
+use hdrhistogram::Histogram;
+use rayon::prelude::*;
+use std::io::Write;
+use std::marker::PhantomData;
+
+/// Sequences are split into chunks of this many k-mers, counted independently
+const CHUNK_LEN: usize = 1 << 16;
+
+/// Number of candidate coverages tried around each spectrum peak
+const COVERAGE_STEPS: usize = 100;
+
+#[derive(Debug, Copy, Clone)]
+pub struct KmerCounterProperties {
+    pub k: usize,
+    /// Count chunks of the sequences across the rayon thread pool
+    pub parallel: bool,
+}
+
+impl KmerCounterProperties {
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+}
+
+impl Default for KmerCounterProperties {
+    fn default() -> Self {
+        Self {
+            k: 21,
+            parallel: false,
+        }
+    }
+}
+
+/// Packing of k-mers into a `u64`, with the first char in the most significant bits, so that
+/// packed k-mers are ordered like the k-mers
+#[derive(Debug, Copy, Clone)]
+struct Encoding {
+    k: usize,
+    bits: usize,
+    mask: u64,
+}
+
+impl Encoding {
+    fn new<C: CharT>(k: usize) -> Self {
+        let alphabet_size = C::AlphabetSize::USIZE;
+        let bits = ((usize::BITS - (alphabet_size - 1).leading_zeros()) as usize).max(1);
+        assert!(k >= 1, "k must be at least 1");
+        assert!(k * bits <= u64::BITS as usize, "k-mers must fit in 64 bits");
+        let mask = if k * bits == u64::BITS as usize {
+            u64::MAX
+        } else {
+            (1 << (k * bits)) - 1
+        };
+        Self { k, bits, mask }
+    }
+
+    fn encode<C: CharT>(&self, kmer: &AStr<C>) -> u64 {
+        kmer.iter()
+            .fold(0, |code, ch| (code << self.bits) | ch.index() as u64)
+    }
+
+    fn decode<C: CharT>(&self, code: u64) -> AString<C> {
+        (0..self.k)
+            .rev()
+            .map(|idx| {
+                C::from_index(((code >> (idx * self.bits)) & ((1 << self.bits) - 1)) as usize)
+            })
+            .collect()
+    }
+}
+
+/// Counts of the k-mers in a collection of sequences
+#[derive(Debug, Clone)]
+pub struct KmerCounts<C> {
+    encoding: Encoding,
+    /// Complement of char indexes, if k-mers are counted together with their reverse
+    /// complement
+    complement: Option<fn(usize) -> usize>,
+    counts: HashMap<u64, usize>,
+    _char: PhantomData<C>,
+}
+
+impl<C: CharT> KmerCounts<C> {
+    pub fn k(&self) -> usize {
+        self.encoding.k
+    }
+
+    /// Whether k-mers are counted together with their reverse complement
+    pub fn is_canonical(&self) -> bool {
+        self.complement.is_some()
+    }
+
+    /// Number of distinct k-mers
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Number of k-mers counted
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Count of `kmer`, including its reverse complement for canonical counts
+    pub fn count(&self, kmer: &AStr<C>) -> usize {
+        if kmer.len() != self.k() {
+            return 0;
+        }
+        let mut code = self.encoding.encode(kmer);
+        if let Some(complement) = self.complement {
+            let rc: AString<C> = kmer
+                .iter()
+                .rev()
+                .map(|ch| C::from_index(complement(ch.index())))
+                .collect();
+            code = code.min(self.encoding.encode(&rc));
+        }
+        self.counts.get(&code).copied().unwrap_or_default()
+    }
+
+    /// Distinct k-mers and their counts in arbitrary order. For canonical counts, the k-mer is
+    /// the smallest of the k-mer and its reverse complement
+    pub fn iter(&self) -> impl Iterator<Item = (AString<C>, usize)> + '_ {
+        self.counts
+            .iter()
+            .map(|(&code, &count)| (self.encoding.decode(code), count))
+    }
+
+    pub fn spectrum(&self) -> Spectrum {
+        let max_count = self.counts.values().copied().max().unwrap_or_default();
+        let mut frequencies = vec![0; max_count + 1];
+        for &count in self.counts.values() {
+            frequencies[count] += 1;
+        }
+        Spectrum {
+            k: self.k(),
+            frequencies,
+        }
+    }
+}
+
+/// Packed k-mers of the sequence in order. With `complement`, each k-mer is packed as the
+/// smallest of the k-mer and its reverse complement
+pub(crate) fn packed_kmers<C: CharT>(
+    seq: &AStr<C>,
+    k: usize,
+    complement: Option<fn(usize) -> usize>,
+) -> impl Iterator<Item = u64> + '_ {
+    let encoding = Encoding::new::<C>(k);
+    let rc_shift = encoding.bits * (k - 1);
+    let mut code = 0;
+    let mut rc_code = 0;
+    seq.iter().enumerate().filter_map(move |(pos, ch)| {
+        code = ((code << encoding.bits) | ch.index() as u64) & encoding.mask;
+        let key = match complement {
+            Some(complement) => {
+                rc_code =
+                    (rc_code >> encoding.bits) | ((complement(ch.index()) as u64) << rc_shift);
+                code.min(rc_code)
+            }
+            None => code,
+        };
+        (pos + 1 >= k).then_some(key)
+    })
+}
+
+fn count_chunk<C: CharT>(
+    chunk: &AStr<C>,
+    k: usize,
+    complement: Option<fn(usize) -> usize>,
+    counts: &mut HashMap<u64, usize>,
+) {
+    for key in packed_kmers(chunk, k, complement) {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+}
+
+fn merge_counts(counts1: HashMap<u64, usize>, counts2: HashMap<u64, usize>) -> HashMap<u64, usize> {
+    let (mut larger, smaller) = if counts1.len() >= counts2.len() {
+        (counts1, counts2)
+    } else {
+        (counts2, counts1)
+    };
+    for (code, count) in smaller {
+        *larger.entry(code).or_insert(0) += count;
+    }
+    larger
+}
+
+fn count<C: CharT + Sync, S: AsRef<AStr<C>> + Sync>(
+    seqs: &[S],
+    props: KmerCounterProperties,
+    complement: Option<fn(usize) -> usize>,
+) -> KmerCounts<C> {
+    let encoding = Encoding::new::<C>(props.k);
+    let k = props.k;
+
+    // chunks overlap in k - 1 chars so that each k-mer is in exactly one chunk
+    let chunks: Vec<&AStr<C>> = seqs
+        .iter()
+        .flat_map(|seq| {
+            let seq = seq.as_ref();
+            (0..seq.len().saturating_sub(k - 1))
+                .step_by(CHUNK_LEN)
+                .map(move |start| &seq[start..(start + CHUNK_LEN + k - 1).min(seq.len())])
+        })
+        .collect();
+
+    let counts = if props.parallel {
+        chunks
+            .par_iter()
+            .fold(HashMap::new, |mut counts, chunk| {
+                count_chunk(chunk, k, complement, &mut counts);
+                counts
+            })
+            .reduce(HashMap::new, merge_counts)
+    } else {
+        let mut counts = HashMap::new();
+        for chunk in chunks {
+            count_chunk(chunk, k, complement, &mut counts);
+        }
+        counts
+    };
+
+    KmerCounts {
+        encoding,
+        complement,
+        counts,
+        _char: PhantomData,
+    }
+}
+
+/// Counts the k-mers in the sequences
+pub fn count_kmers<C: CharT + Sync, S: AsRef<AStr<C>> + Sync>(
+    seqs: &[S],
+    props: KmerCounterProperties,
+) -> KmerCounts<C> {
+    count(seqs, props, None)
+}
+
+pub(crate) fn dna_complement(idx: usize) -> usize {
+    DnaNt::from_index(idx).bonding_complement().index()
+}
+
+/// Counts the k-mers in the sequences together with their reverse complement, for reads
+/// sequenced from both strands
+pub fn count_canonical_kmers<S: AsRef<AStr<DnaNt>> + Sync>(
+    seqs: &[S],
+    props: KmerCounterProperties,
+) -> KmerCounts<DnaNt> {
+    count(seqs, props, Some(dna_complement))
+}
+
+/// Number of distinct k-mers with each count
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    pub k: usize,
+    /// Number of distinct k-mers occurring the number of times given by the index
+    pub frequencies: Vec<usize>,
+}
+
+/// Estimates from a k-mer spectrum
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpectrumEstimate {
+    /// K-mers occurring fewer times are considered sequencing errors
+    pub error_threshold: usize,
+    /// Mean count of k-mers occurring once in both haplotypes
+    pub coverage: f64,
+    /// Haploid genome size
+    pub genome_size: f64,
+    /// Fraction of bases differing between the haplotypes
+    pub heterozygosity: f64,
+}
+
+/// Number of distinct heterozygous and homozygous k-mers, and squared error, of least squares
+/// fit of the spectrum to a mixture of Poisson peaks at half and full `coverage`
+struct MixtureFit {
+    heterozygous: f64,
+    homozygous: f64,
+    error: f64,
+}
+
+impl Spectrum {
+    /// Number of distinct k-mers occurring `count` times
+    pub fn frequency(&self, count: usize) -> usize {
+        self.frequencies.get(count).copied().unwrap_or_default()
+    }
+
+    /// Histogram of the counts of the distinct k-mers, to be summarized by
+    /// [`crate::util::print_histogram`]
+    pub fn histogram(&self) -> Histogram<u64> {
+        let mut hist = Histogram::new(3).unwrap();
+        for (count, &frequency) in self.frequencies.iter().enumerate().skip(1) {
+            if frequency != 0 {
+                hist.record_n(count as u64, frequency as u64).unwrap();
+            }
+        }
+        hist
+    }
+
+    /// Writes a tab separated line with count and frequency for each count with non-zero
+    /// frequency
+    pub fn write(&self, write: &mut impl Write) {
+        for (count, &frequency) in self.frequencies.iter().enumerate().skip(1) {
+            if frequency != 0 {
+                writeln!(write, "{}\t{}", count, frequency).unwrap();
+            }
+        }
+    }
+
+    /// First local minimum of the spectrum, separating k-mers with sequencing errors from
+    /// k-mers in the genome. `None` if the spectrum is non-increasing
+    pub fn valley(&self) -> Option<usize> {
+        (1..self.frequencies.len().saturating_sub(1))
+            .find(|&count| self.frequencies[count] < self.frequencies[count + 1])
+    }
+
+    /// Estimates coverage, genome size and heterozygosity. The error threshold is the
+    /// [valley](Self::valley) of the spectrum, and the counts above it are fitted by a mixture of Poisson
+    /// peaks for heterozygous and homozygous k-mers. `None` if the spectrum has no peak after
+    /// the errors
+    pub fn estimate(&self) -> Option<SpectrumEstimate> {
+        let frequencies = &self.frequencies;
+        let max_count = frequencies.len().checked_sub(1)?;
+        let error_threshold = self.valley()?;
+        let peak = (error_threshold..=max_count)
+            .rev()
+            .max_by_key(|&count| frequencies[count])?;
+
+        // the highest peak is either the homozygous peak, or the heterozygous peak with the
+        // homozygous peak at twice its count. The homozygous interpretation is preferred
+        // unless the other fits clearly better
+        let ln_factorials: Vec<f64> = (0..=max_count)
+            .scan(0.0, |ln_factorial, count| {
+                if count > 0 {
+                    *ln_factorial += (count as f64).ln();
+                }
+                Some(*ln_factorial)
+            })
+            .collect();
+        let best_fit = |peak_coverage: f64| {
+            (0..=COVERAGE_STEPS)
+                .map(|step| {
+                    let coverage =
+                        peak_coverage * (0.8 + 0.4 * step as f64 / COVERAGE_STEPS as f64);
+                    (
+                        coverage,
+                        self.fit(error_threshold, coverage, &ln_factorials),
+                    )
+                })
+                .min_by(|(_, fit1), (_, fit2)| fit1.error.total_cmp(&fit2.error))
+                .unwrap()
+        };
+        let homozygous_peak = best_fit(peak as f64);
+        let heterozygous_peak = best_fit(2.0 * peak as f64);
+        let (coverage, fit) = if heterozygous_peak.1.error < 0.5 * homozygous_peak.1.error {
+            heterozygous_peak
+        } else {
+            homozygous_peak
+        };
+
+        let kmers: usize = (error_threshold..=max_count)
+            .map(|count| count * frequencies[count])
+            .sum();
+        let heterozygous_positions = fit.heterozygous / 2.0;
+        let heterozygous_fraction = heterozygous_positions
+            / (heterozygous_positions + fit.homozygous).max(f64::MIN_POSITIVE);
+
+        Some(SpectrumEstimate {
+            error_threshold,
+            coverage,
+            genome_size: kmers as f64 / coverage,
+            heterozygosity: 1.0 - (1.0 - heterozygous_fraction).powf(1.0 / self.k as f64),
+        })
+    }
+
+    fn fit(&self, error_threshold: usize, coverage: f64, ln_factorials: &[f64]) -> MixtureFit {
+        let poisson = |count: usize, mean: f64| {
+            (count as f64 * mean.ln() - mean - ln_factorials[count]).exp()
+        };
+        let max_count = ((2.5 * coverage).ceil() as usize).min(self.frequencies.len() - 1);
+        let points: Vec<(f64, f64, f64)> = (error_threshold..=max_count)
+            .map(|count| {
+                (
+                    poisson(count, coverage / 2.0),
+                    poisson(count, coverage),
+                    self.frequencies[count] as f64,
+                )
+            })
+            .collect();
+
+        let dot = |f: &dyn Fn(&(f64, f64, f64)) -> f64| points.iter().map(f).sum::<f64>();
+        let het_het = dot(&|(het, _, _)| het * het);
+        let het_hom = dot(&|(het, hom, _)| het * hom);
+        let hom_hom = dot(&|(_, hom, _)| hom * hom);
+        let het_freq = dot(&|(het, _, freq)| het * freq);
+        let hom_freq = dot(&|(_, hom, freq)| hom * freq);
+
+        let det = het_het * hom_hom - het_hom * het_hom;
+        let (mut heterozygous, mut homozygous) = if det.abs() > f64::EPSILON {
+            (
+                (het_freq * hom_hom - hom_freq * het_hom) / det,
+                (hom_freq * het_het - het_freq * het_hom) / det,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        if heterozygous < 0.0 || det.abs() <= f64::EPSILON {
+            heterozygous = 0.0;
+            homozygous = hom_freq / hom_hom.max(f64::MIN_POSITIVE);
+        }
+        if homozygous < 0.0 {
+            homozygous = 0.0;
+            heterozygous = het_freq / het_het.max(f64::MIN_POSITIVE);
+        }
+
+        let error = dot(&|(het, hom, freq)| (freq - heterozygous * het - homozygous * hom).powi(2));
+        MixtureFit {
+            heterozygous,
+            homozygous,
+            error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembly::simulation::{
+        ErrorProfile, ReadSimulatorProperties, add_errors, simulate_reads,
+    };
+    use crate::polymers::{ProteinAa, reverse_complement};
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use crate::string_model::test_util::random_dna;
+    use itertools::Itertools;
+    use proptest::collection::vec;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn dna(s: &str) -> AString<DnaNt> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_count_kmers() {
+        let seqs = [dna("ACGTAC"), dna("GTA"), dna("A")];
+        let counts = count_kmers(&seqs, KmerCounterProperties::default().k(2));
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts.total(), 7);
+        assert_eq!(counts.count(&dna("AC")), 2);
+        assert_eq!(counts.count(&dna("GT")), 2);
+        assert_eq!(counts.count(&dna("TA")), 2);
+        assert_eq!(counts.count(&dna("CG")), 1);
+        assert_eq!(counts.count(&dna("CA")), 0);
+        assert_eq!(counts.count(&dna("ACG")), 0);
+        assert_eq!(
+            counts
+                .iter()
+                .map(|(kmer, count)| (kmer.to_string(), count))
+                .sorted()
+                .collect_vec(),
+            vec![
+                ("AC".to_string(), 2),
+                ("CG".to_string(), 1),
+                ("GT".to_string(), 2),
+                ("TA".to_string(), 2)
+            ]
+        );
+
+        // AC and GT, and CG and TA, are reverse complements
+        let counts = count_canonical_kmers(&seqs, KmerCounterProperties::default().k(2));
+        assert!(counts.is_canonical());
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts.count(&dna("AC")), 4);
+        assert_eq!(counts.count(&dna("GT")), 4);
+        assert_eq!(counts.count(&dna("TA")), 2);
+        assert_eq!(counts.count(&dna("CG")), 1);
+        assert_eq!(
+            counts
+                .iter()
+                .map(|(kmer, count)| (kmer.to_string(), count))
+                .sorted()
+                .collect_vec(),
+            vec![
+                ("AC".to_string(), 4),
+                ("CG".to_string(), 1),
+                ("TA".to_string(), 2)
+            ]
+        );
+
+        // 5 bits per amino acid
+        let protein: AString<ProteinAa> = "MKVLAAGMKVL".parse().unwrap();
+        let counts = count_kmers(&[&protein], KmerCounterProperties::default().k(12));
+        assert!(counts.is_empty());
+        let counts = count_kmers(&[&protein], KmerCounterProperties::default().k(3));
+        assert_eq!(
+            counts.count(&"MKV".parse::<AString<ProteinAa>>().unwrap()),
+            2
+        );
+        assert_eq!(counts.len(), 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "fit in 64 bits")]
+    fn test_count_kmers_too_long() {
+        count_kmers(&[dna("ACGT")], KmerCounterProperties::default().k(33));
+    }
+
+    #[test]
+    fn test_count_kmers_parallel() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let genome = random_dna(&mut rng, 3 * CHUNK_LEN + 100);
+        let props = KmerCounterProperties::default().k(32);
+        let counts = count_canonical_kmers(&[&genome], props);
+        let counts_parallel = count_canonical_kmers(&[&genome], props.parallel(true));
+        assert_eq!(counts.total(), genome.len() - 31);
+        assert_eq!(counts_parallel.total(), genome.len() - 31);
+        assert_eq!(counts.counts, counts_parallel.counts);
+        assert_eq!(counts.count(&genome[CHUNK_LEN - 10..CHUNK_LEN + 22]), 1);
+        assert_eq!(counts.count(&reverse_complement(&genome[1000..1032])), 1);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn prop_test_count_kmers(seqs in vec(arb_astring::<Char>(0..20), 0..5), k in 1..5usize) {
+            let counts = count_kmers(&seqs, KmerCounterProperties::default().k(k));
+            let mut expected: HashMap<AString<Char>, usize> = HashMap::new();
+            for seq in &seqs {
+                for kmer in seq.windows(k) {
+                    *expected.entry(AStr::from_slice(kmer).to_owned()).or_default() += 1;
+                }
+            }
+            prop_assert_eq!(counts.len(), expected.len());
+            for (kmer, count) in counts.iter() {
+                prop_assert_eq!(expected.get(&kmer).copied(), Some(count));
+            }
+        }
+    }
+
+    #[test]
+    fn test_spectrum() {
+        let seqs = [dna("AAAAC"), dna("AAC")];
+        let spectrum = count_kmers(&seqs, KmerCounterProperties::default().k(2)).spectrum();
+        assert_eq!(
+            spectrum,
+            Spectrum {
+                k: 2,
+                frequencies: vec![0, 0, 1, 0, 1]
+            }
+        );
+        assert_eq!(spectrum.frequency(4), 1);
+        assert_eq!(spectrum.frequency(5), 0);
+        assert_eq!(spectrum.valley(), Some(1));
+        let hist = spectrum.histogram();
+        assert_eq!(hist.len(), 2);
+        assert_eq!(hist.max(), 4);
+
+        let mut written = Vec::new();
+        spectrum.write(&mut written);
+        assert_eq!(String::from_utf8(written).unwrap(), "2\t1\n4\t1\n");
+
+        // no peak after the errors
+        let spectrum = Spectrum {
+            k: 2,
+            frequencies: vec![0, 5, 3, 1],
+        };
+        assert_eq!(spectrum.valley(), None);
+        assert_eq!(spectrum.estimate(), None);
+    }
+
+    fn reads_spectrum(haplotypes: &[&AStr<DnaNt>], depth: f64, seed: u64) -> Spectrum {
+        let props = ReadSimulatorProperties::default()
+            .read_len(100)
+            .depth(depth)
+            .errors(ErrorProfile::default().substitution_rate(0.002));
+        let reads = haplotypes
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, haplotype)| {
+                simulate_reads(haplotype, props.seed(Some(seed + idx as u64)))
+            })
+            .map(|read| read.entry.polymer)
+            .collect_vec();
+        count_canonical_kmers(&reads, KmerCounterProperties::default().parallel(true)).spectrum()
+    }
+
+    #[test]
+    fn test_estimate_haploid() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let genome = random_dna(&mut rng, 50000);
+        let spectrum = reads_spectrum(&[&genome], 30.0, 3);
+
+        // k-mer coverage is read coverage times (100 - 21 + 1) / 100, times the fraction of
+        // k-mers without errors
+        let estimate = spectrum.estimate().unwrap();
+        assert!((estimate.coverage - 23.0).abs() < 1.0, "{:?}", estimate);
+        assert!(
+            (estimate.genome_size - 50000.0).abs() < 1500.0,
+            "{:?}",
+            estimate
+        );
+        assert!(estimate.heterozygosity < 0.001, "{:?}", estimate);
+        assert!(
+            (2..10).contains(&estimate.error_threshold),
+            "{:?}",
+            estimate
+        );
+    }
+
+    #[test]
+    fn test_estimate_diploid() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let haplotype1 = random_dna(&mut rng, 50000);
+        let haplotype2 = add_errors(
+            &haplotype1,
+            ErrorProfile::default().substitution_rate(0.01),
+            &mut rng,
+        );
+        let spectrum = reads_spectrum(&[&haplotype1, &haplotype2], 15.0, 5);
+
+        let estimate = spectrum.estimate().unwrap();
+        assert!((estimate.coverage - 23.0).abs() < 1.5, "{:?}", estimate);
+        assert!(
+            (estimate.genome_size - 50000.0).abs() < 2500.0,
+            "{:?}",
+            estimate
+        );
+        assert!(
+            (estimate.heterozygosity - 0.01).abs() < 0.002,
+            "{:?}",
+            estimate
+        );
+    }
+}
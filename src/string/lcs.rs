@@ -8,6 +8,7 @@ use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::iter;
 
+use crate::string::suffix_automaton::build_suffix_automaton;
 use crate::string::suffix_trie_mcc_arena;
 use bumpalo::Bump;
 use hashbrown::HashMap;
@@ -220,14 +221,13 @@ pub fn lcs_single_trie<'a, C: CharT>(s: &AStr<C>, t: &'a AStr<C>) -> &'a AStr<C>
 
     // let start = Instant::now();
     let mut substr: &AStr<C> = AStr::empty();
-    for i in 0..t.len() {
+    for (i, statistic) in trie.matching_statistics(t).enumerate() {
         if t.len() - i <= substr.len() {
             break;
         }
 
-        let m = trie.index_substr_maximal(&t[i..]);
-        if m.length > substr.len() {
-            substr = &t[i..i + m.length];
+        if statistic.length > substr.len() {
+            substr = &t[i..i + statistic.length];
         }
     }
     // println!("probe elapsed {:?}", start.elapsed());
@@ -235,6 +235,22 @@ pub fn lcs_single_trie<'a, C: CharT>(s: &AStr<C>, t: &'a AStr<C>) -> &'a AStr<C>
     substr
 }
 
+pub fn lcs_suffix_automaton<'a, C: CharT>(s: &AStr<C>, t: &'a AStr<C>) -> &'a AStr<C> {
+    let automaton = build_suffix_automaton(s);
+    let (end, len) = automaton
+        .longest_suffix_matches(t)
+        .into_iter()
+        .enumerate()
+        .fold((0, 0), |(end, len), (j, match_len)| {
+            if match_len > len {
+                (j + 1, match_len)
+            } else {
+                (end, len)
+            }
+        });
+    &t[end - len..end]
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -309,6 +325,47 @@ mod test {
         let _ = lcs_joined_trie(&s, &t);
     }
 
+    #[test]
+    fn test_lcs_suffix_automaton() {
+        use crate::string_model::test_util::Char::*;
+
+        let s = AStr::from_slice(&[B, A, B, A, A, B, A, B, A, A]);
+        let t = AStr::from_slice(&[B, B, A, A, B, A, A, A, A, B]);
+
+        assert_eq!(
+            lcs_suffix_automaton(s, t),
+            AStr::from_slice(&[B, A, A, B, A])
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn prop_test_lcs_suffix_automaton(s in arb_astring::<Char>(0..20), t in arb_astring::<Char>(0..20)) {
+            let lcs = lcs_suffix_automaton(&s, &t);
+            prop_assert_eq!(lcs.len(), lcs_joined_trie(&s, &t).len());
+            prop_assert_eq!(lcs.len(), lcs_single_trie(&s, &t).len());
+            prop_assert!(s.contains(lcs));
+            prop_assert!(t.contains(lcs));
+        }
+    }
+
+    #[test]
+    fn test_lcs_suffix_automaton_perf() {
+        let mut runner = proptest::test_runner::TestRunner::default();
+        let s = arb_astring::<Char>(10_000)
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        let t = arb_astring::<Char>(10_000)
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+
+        let _ = lcs_suffix_automaton(&s, &t);
+    }
+
     #[test]
     fn test_lcs_single_trie_perf() {
         let mut runner = proptest::test_runner::TestRunner::default();
@@ -0,0 +1,167 @@
+//! Lyndon words, strings strictly smaller than all their proper rotations, and the
+//! factorization of a string into non-increasing Lyndon words (Chen, Fox and Lyndon), found
+//! in linear time by Duval's algorithm. Chars are ordered by index
+
+use crate::alphabet_model::CharT;
+use crate::string_model::AStr;
+use std::cmp::Ordering;
+use std::ops::Range;
+
+/// Duval's algorithm on `s[..len]`, with `ch(i)` the char at `i` and `cmp` the order of
+/// chars. Calls `factors` with the start, length and number of each run of equal factors,
+/// stopping when it returns false
+fn duval(
+    len: usize,
+    ch: impl Fn(usize) -> usize,
+    cmp: impl Fn(usize, usize) -> Ordering,
+    mut factors: impl FnMut(usize, usize, usize) -> bool,
+) {
+    let mut start = 0;
+    while start < len {
+        // s[start..j] is a power of a Lyndon word of length j - k followed by a prefix of it
+        let mut k = start;
+        let mut j = start + 1;
+        while j < len {
+            match cmp(ch(k), ch(j)) {
+                Ordering::Less => k = start,
+                Ordering::Equal => k += 1,
+                Ordering::Greater => break,
+            }
+            j += 1;
+        }
+        let period = j - k;
+        let count = (k - start) / period + 1;
+        if !factors(start, period, count) {
+            return;
+        }
+        start += count * period;
+    }
+}
+
+/// Lyndon factorization of `s`, the unique factorization into Lyndon words with each factor
+/// not smaller than the next
+pub fn lyndon_factorization<C: CharT>(s: &AStr<C>) -> Vec<Range<usize>> {
+    let mut factors = Vec::new();
+    duval(
+        s.len(),
+        |i| s[i].index(),
+        |a, b| a.cmp(&b),
+        |start, len, count| {
+            factors.extend((0..count).map(|i| start + i * len..start + (i + 1) * len));
+            true
+        },
+    );
+    factors
+}
+
+/// Whether `s` is a Lyndon word
+pub fn is_lyndon_word<C: CharT>(s: &AStr<C>) -> bool {
+    !s.is_empty() && lyndon_factorization(s).len() == 1
+}
+
+/// Smallest start of a rotation of `s` that is minimal in the given order of char indexes
+fn least_rotation<C: CharT>(s: &AStr<C>, cmp: impl Fn(usize, usize) -> Ordering) -> usize {
+    let n = s.len();
+    let mut rotation = 0;
+    // the last run of factors starting in the first half of s + s starts the least rotation
+    duval(
+        2 * n,
+        |i| s[i % n].index(),
+        cmp,
+        |start, _, _| {
+            if start < n {
+                rotation = start;
+            }
+            start < n
+        },
+    );
+    rotation
+}
+
+/// Start of the lexicographically smallest rotation of `s`, the smallest such start if
+/// several rotations are equal
+pub fn min_rotation<C: CharT>(s: &AStr<C>) -> usize {
+    least_rotation(s, |a, b| a.cmp(&b))
+}
+
+/// Start of the lexicographically largest rotation of `s`, the smallest such start if
+/// several rotations are equal
+pub fn max_rotation<C: CharT>(s: &AStr<C>) -> usize {
+    least_rotation(s, |a, b| b.cmp(&a))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use itertools::Itertools;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+
+    fn indexes<C: CharT>(s: &AStr<C>) -> Vec<usize> {
+        s.iter().map(|ch| ch.index()).collect()
+    }
+
+    fn rotations<C: CharT>(s: &AStr<C>) -> impl Iterator<Item = (usize, Vec<usize>)> + '_ {
+        (0..s.len()).map(move |start| {
+            let mut rotation = indexes(s);
+            rotation.rotate_left(start);
+            (start, rotation)
+        })
+    }
+
+    fn is_lyndon_word_naive<C: CharT>(s: &AStr<C>) -> bool {
+        !s.is_empty()
+            && rotations(s)
+                .skip(1)
+                .all(|(_, rotation)| indexes(s) < rotation)
+    }
+
+    #[test]
+    fn test_lyndon_factorization() {
+        let s = ascii("banana");
+        let factors = lyndon_factorization(s)
+            .into_iter()
+            .map(|range| s[range].to_string())
+            .collect_vec();
+        assert_eq!(factors, vec!["b", "an", "an", "a"]);
+        assert!(is_lyndon_word(ascii("aabab")));
+        assert!(!is_lyndon_word(ascii("abab")));
+    }
+
+    #[test]
+    fn test_rotations() {
+        assert_eq!(min_rotation(ascii("banana")), 5);
+        assert_eq!(max_rotation(ascii("banana")), 2);
+        assert_eq!(min_rotation(ascii("abab")), 0);
+        assert_eq!(max_rotation(ascii("abab")), 1);
+        assert_eq!(min_rotation(ascii("")), 0);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn prop_test_lyndon_factorization(s in arb_astring::<Char>(0..30)) {
+            let factors = lyndon_factorization(&s);
+            prop_assert_eq!(factors.iter().map(|range| range.len()).sum::<usize>(), s.len());
+            prop_assert!(factors.windows(2).all(|pair| pair[0].end == pair[1].start));
+            for factor in &factors {
+                prop_assert!(is_lyndon_word_naive(&s[factor.clone()]));
+            }
+            for pair in factors.windows(2) {
+                prop_assert!(indexes(&s[pair[0].clone()]) >= indexes(&s[pair[1].clone()]));
+            }
+        }
+
+        #[test]
+        fn prop_test_rotations(s in arb_astring::<Char>(1..30)) {
+            let min = rotations(&s).min_by(|a, b| a.1.cmp(&b.1)).unwrap().0;
+            let max = rotations(&s).min_by(|a, b| b.1.cmp(&a.1)).unwrap().0;
+            prop_assert_eq!(min_rotation(&s), min);
+            prop_assert_eq!(max_rotation(&s), max);
+        }
+    }
+}
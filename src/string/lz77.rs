@@ -0,0 +1,175 @@
+//! LZ77 factorization (Ziv and Lempel), the greedy parse of a string into factors that are
+//! either the longest prefix of the rest of the string occurring earlier, or a single new
+//! char. The earlier occurrence may overlap the factor
+//!
+//! The earlier occurrence with the longest common prefix is the nearest suffix in the suffix
+//! array before or after the current suffix that starts earlier in the string
+
+use crate::alphabet_model::CharT;
+use crate::string::suffix_array::build_array;
+use crate::string_model::{AStr, AString};
+use alloc::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LzFactor<C> {
+    /// Char not occurring earlier
+    Literal(C),
+    /// Copy of `len` chars starting at the earlier position `source`
+    Copy { source: usize, len: usize },
+}
+
+impl<C> LzFactor<C> {
+    pub fn len(&self) -> usize {
+        match self {
+            LzFactor::Literal(_) => 1,
+            LzFactor::Copy { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// For each suffix by start position, the start of the nearest suffix before it in the suffix
+/// array that starts earlier in the string, and likewise after it
+fn nearest_earlier_suffixes(sorted_suffixes: &[usize]) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+    let n = sorted_suffixes.len();
+    let mut before = vec![None; n];
+    let mut after = vec![None; n];
+    // start positions increase from the bottom of the stack
+    let mut stack: Vec<usize> = Vec::new();
+    for &suffix in sorted_suffixes {
+        while let Some(&top) = stack.last()
+            && top > suffix
+        {
+            after[top] = Some(suffix);
+            stack.pop();
+        }
+        before[suffix] = stack.last().copied();
+        stack.push(suffix);
+    }
+    (before, after)
+}
+
+/// LZ77 factorization of `s`
+pub fn lz77_factorization<C: CharT>(s: &AStr<C>) -> Vec<LzFactor<C>> {
+    let array = build_array(Cow::Borrowed(s));
+    let (before, after) = nearest_earlier_suffixes(array.sorted_suffixes());
+
+    let common_prefix_len = |i: usize, source: usize| {
+        s[i..]
+            .iter()
+            .zip(s[source..].iter())
+            .take_while(|(a, b)| a == b)
+            .count()
+    };
+
+    let mut factors = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        let factor = before[i]
+            .into_iter()
+            .chain(after[i])
+            .map(|source| (source, common_prefix_len(i, source)))
+            .max_by_key(|&(_, len)| len)
+            .filter(|&(_, len)| len > 0)
+            .map_or(LzFactor::Literal(s[i]), |(source, len)| LzFactor::Copy {
+                source,
+                len,
+            });
+        i += factor.len();
+        factors.push(factor);
+    }
+    factors
+}
+
+/// String with the given LZ77 factorization
+pub fn lz77_decode<C: CharT>(factors: &[LzFactor<C>]) -> AString<C> {
+    let mut s = Vec::new();
+    for factor in factors {
+        match *factor {
+            LzFactor::Literal(ch) => s.push(ch),
+            LzFactor::Copy { source, len } => {
+                // chars are copied one by one since the source may overlap the factor
+                for i in source..source + len {
+                    s.push(s[i]);
+                }
+            }
+        }
+    }
+    AString::from(s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+
+    /// Length of the longest prefix of `s[i..]` occurring at an earlier position
+    fn longest_previous_factor<C: CharT>(s: &AStr<C>, i: usize) -> usize {
+        (0..i)
+            .map(|source| {
+                s[i..]
+                    .iter()
+                    .zip(s[source..].iter())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_lz77_factorization() {
+        let factors = lz77_factorization(ascii("abaababaabaab"));
+        assert_eq!(
+            factors,
+            vec![
+                LzFactor::Literal(ascii("a")[0]),
+                LzFactor::Literal(ascii("b")[0]),
+                LzFactor::Copy { source: 0, len: 1 },
+                LzFactor::Copy { source: 0, len: 3 },
+                LzFactor::Copy { source: 1, len: 5 },
+                LzFactor::Copy { source: 8, len: 2 },
+            ]
+        );
+        assert_eq!(lz77_decode(&factors), ascii("abaababaabaab").to_owned());
+    }
+
+    #[test]
+    fn test_lz77_factorization_overlapping() {
+        let factors = lz77_factorization(ascii("aaaaaaab"));
+        assert_eq!(
+            factors,
+            vec![
+                LzFactor::Literal(ascii("a")[0]),
+                LzFactor::Copy { source: 0, len: 6 },
+                LzFactor::Literal(ascii("b")[0]),
+            ]
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn prop_test_lz77_factorization(s in arb_astring::<Char>(0..40)) {
+            let factors = lz77_factorization(&s);
+            prop_assert_eq!(lz77_decode(&factors), s.clone());
+            let mut i = 0;
+            for factor in &factors {
+                let expected_len = longest_previous_factor(&s, i).max(1);
+                prop_assert_eq!(factor.len(), expected_len);
+                if let LzFactor::Copy { source, .. } = factor {
+                    prop_assert!(*source < i);
+                }
+                i += factor.len();
+            }
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! Exact pattern matchers. The pattern is preprocessed once, and occurrences in a text are
+//! found lazily from left to right, including overlapping occurrences. An empty pattern
+//! occurs at every position of the text, including the end
+
+use crate::alphabet_model::CharT;
+use crate::string::border_array::border_array;
+use crate::string_model::{AStr, AString};
+use generic_array::typenum::Unsigned;
+use std::iter;
+
+pub trait Matcher<C: CharT> {
+    /// Preprocesses the pattern
+    fn new(pattern: &AStr<C>) -> Self
+    where
+        Self: Sized;
+
+    fn pattern(&self) -> &AStr<C>;
+
+    /// Start positions of the occurrences of the pattern in `text` in increasing order
+    fn find_iter<'a>(&'a self, text: &'a AStr<C>) -> impl Iterator<Item = usize> + 'a;
+
+    /// Start of the first occurrence of the pattern in `text`
+    fn find(&self, text: &AStr<C>) -> Option<usize> {
+        self.find_iter(text).next()
+    }
+}
+
+/// Occurrences of the empty pattern
+fn empty_pattern_matches(text_len: usize) -> impl Iterator<Item = usize> {
+    0..=text_len
+}
+
+/// Knuth–Morris–Pratt: the text is scanned once, and on a mismatch the pattern is shifted
+/// to its longest border
+#[derive(Debug, Clone)]
+pub struct Kmp<C> {
+    pattern: AString<C>,
+    border: Vec<usize>,
+}
+
+impl<C: CharT> Matcher<C> for Kmp<C> {
+    fn new(pattern: &AStr<C>) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            border: border_array(pattern),
+        }
+    }
+
+    fn pattern(&self) -> &AStr<C> {
+        &self.pattern
+    }
+
+    fn find_iter<'a>(&'a self, text: &'a AStr<C>) -> impl Iterator<Item = usize> + 'a {
+        let m = self.pattern.len();
+        let mut empty = (m == 0).then(|| empty_pattern_matches(text.len()));
+        let mut pos = 0;
+        let mut matched = 0;
+        iter::from_fn(move || {
+            if let Some(empty) = &mut empty {
+                return empty.next();
+            }
+            while pos < text.len() {
+                let ch = text[pos];
+                pos += 1;
+                while matched > 0 && self.pattern[matched] != ch {
+                    matched = self.border[matched - 1];
+                }
+                if self.pattern[matched] == ch {
+                    matched += 1;
+                }
+                if matched == m {
+                    matched = self.border[m - 1];
+                    return Some(pos - m);
+                }
+            }
+            None
+        })
+    }
+}
+
+/// Z-array of `s`: the length of the longest common prefix of `s` and each suffix of `s`
+pub fn z_array<C: CharT>(s: &AStr<C>) -> Vec<usize> {
+    let n = s.len();
+    let mut z = vec![0; n];
+    if n == 0 {
+        return z;
+    }
+    z[0] = n;
+    // s[left..right] is a prefix of s with the rightmost end found so far
+    let (mut left, mut right) = (0, 0);
+    for i in 1..n {
+        let mut len = if i < right {
+            z[i - left].min(right - i)
+        } else {
+            0
+        };
+        while i + len < n && s[len] == s[i + len] {
+            len += 1;
+        }
+        if i + len > right {
+            (left, right) = (i, i + len);
+        }
+        z[i] = len;
+    }
+    z
+}
+
+/// Z-algorithm: the length of the longest prefix of the pattern at each text position is
+/// computed with the Z-array of the pattern, reusing the rightmost match found so far
+#[derive(Debug, Clone)]
+pub struct ZMatcher<C> {
+    pattern: AString<C>,
+    z: Vec<usize>,
+}
+
+impl<C: CharT> Matcher<C> for ZMatcher<C> {
+    fn new(pattern: &AStr<C>) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            z: z_array(pattern),
+        }
+    }
+
+    fn pattern(&self) -> &AStr<C> {
+        &self.pattern
+    }
+
+    fn find_iter<'a>(&'a self, text: &'a AStr<C>) -> impl Iterator<Item = usize> + 'a {
+        let m = self.pattern.len();
+        let mut empty = (m == 0).then(|| empty_pattern_matches(text.len()));
+        // text[left..right] is a prefix of the pattern with the rightmost end found so far
+        let (mut left, mut right) = (0, 0);
+        let mut pos = 0;
+        iter::from_fn(move || {
+            if let Some(empty) = &mut empty {
+                return empty.next();
+            }
+            while pos < text.len() {
+                let i = pos;
+                pos += 1;
+                let mut len = if i < right {
+                    self.z[i - left].min(right - i)
+                } else {
+                    0
+                };
+                while len < m && i + len < text.len() && self.pattern[len] == text[i + len] {
+                    len += 1;
+                }
+                if i + len > right {
+                    (left, right) = (i, i + len);
+                }
+                if len == m {
+                    return Some(i);
+                }
+            }
+            None
+        })
+    }
+}
+
+/// Shift of the pattern for each char under its last position, so that the char is aligned
+/// with its last occurrence in the pattern before the last position
+fn bad_char_shifts<C: CharT>(pattern: &AStr<C>) -> Vec<usize> {
+    let m = pattern.len();
+    let mut shifts = vec![m; C::AlphabetSize::USIZE];
+    for (pos, ch) in pattern.iter().enumerate().take(m.saturating_sub(1)) {
+        shifts[ch.index()] = m - 1 - pos;
+    }
+    shifts
+}
+
+/// Boyer–Moore–Horspool: the pattern is compared right to left, and shifted by the bad
+/// character rule for the text char under its last position
+#[derive(Debug, Clone)]
+pub struct Horspool<C> {
+    pattern: AString<C>,
+    shifts: Vec<usize>,
+}
+
+impl<C: CharT> Matcher<C> for Horspool<C> {
+    fn new(pattern: &AStr<C>) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            shifts: bad_char_shifts(pattern),
+        }
+    }
+
+    fn pattern(&self) -> &AStr<C> {
+        &self.pattern
+    }
+
+    fn find_iter<'a>(&'a self, text: &'a AStr<C>) -> impl Iterator<Item = usize> + 'a {
+        let m = self.pattern.len();
+        let mut empty = (m == 0).then(|| empty_pattern_matches(text.len()));
+        let mut pos = 0;
+        iter::from_fn(move || {
+            if let Some(empty) = &mut empty {
+                return empty.next();
+            }
+            while pos + m <= text.len() {
+                let start = pos;
+                pos += self.shifts[text[start + m - 1].index()];
+                if (0..m).rev().all(|j| self.pattern[j] == text[start + j]) {
+                    return Some(start);
+                }
+            }
+            None
+        })
+    }
+}
+
+/// Strong good suffix shifts: for a mismatch at pattern position `j`, the smallest shift
+/// aligning the matched suffix `pattern[j + 1..]` with an earlier occurrence preceded by a
+/// different char, or with a border of the pattern. The shift after a full match is at 0
+fn good_suffix_shifts<C: CharT>(pattern: &AStr<C>) -> Vec<usize> {
+    let m = pattern.len();
+    if m == 0 {
+        return Vec::new();
+    }
+
+    // suffix_len[i] is the length of the longest common suffix of pattern[..=i] and pattern
+    let mut suffix_len = vec![0; m];
+    suffix_len[m - 1] = m;
+    let mut g = m as isize - 1;
+    let mut f = m - 1;
+    for i in (0..m - 1).rev() {
+        if i as isize > g && suffix_len[i + m - 1 - f] < (i as isize - g) as usize {
+            suffix_len[i] = suffix_len[i + m - 1 - f];
+        } else {
+            g = g.min(i as isize);
+            f = i;
+            while g >= 0 && pattern[g as usize] == pattern[(g + (m - 1 - f) as isize) as usize] {
+                g -= 1;
+            }
+            suffix_len[i] = (f as isize - g) as usize;
+        }
+    }
+
+    let mut shifts = vec![m; m];
+    // matched suffixes longer than a border of the pattern shift to the border
+    let mut j = 0;
+    for i in (0..m).rev() {
+        if suffix_len[i] == i + 1 {
+            while j < m - 1 - i {
+                if shifts[j] == m {
+                    shifts[j] = m - 1 - i;
+                }
+                j += 1;
+            }
+        }
+    }
+    // matched suffixes also occurring ending at i
+    for i in 0..m - 1 {
+        shifts[m - 1 - suffix_len[i]] = m - 1 - i;
+    }
+    shifts
+}
+
+/// Boyer–Moore: the pattern is compared right to left, and shifted by the larger of the bad
+/// character and strong good suffix rules
+#[derive(Debug, Clone)]
+pub struct BoyerMoore<C> {
+    pattern: AString<C>,
+    bad_char: Vec<usize>,
+    good_suffix: Vec<usize>,
+}
+
+impl<C: CharT> Matcher<C> for BoyerMoore<C> {
+    fn new(pattern: &AStr<C>) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            bad_char: bad_char_shifts(pattern),
+            good_suffix: good_suffix_shifts(pattern),
+        }
+    }
+
+    fn pattern(&self) -> &AStr<C> {
+        &self.pattern
+    }
+
+    fn find_iter<'a>(&'a self, text: &'a AStr<C>) -> impl Iterator<Item = usize> + 'a {
+        let m = self.pattern.len();
+        let mut empty = (m == 0).then(|| empty_pattern_matches(text.len()));
+        let mut pos = 0;
+        iter::from_fn(move || {
+            if let Some(empty) = &mut empty {
+                return empty.next();
+            }
+            while pos + m <= text.len() {
+                let start = pos;
+                match (0..m).rev().find(|&j| self.pattern[j] != text[start + j]) {
+                    None => {
+                        pos += self.good_suffix[0];
+                        return Some(start);
+                    }
+                    Some(j) => {
+                        // the bad char rule aligns the mismatched text char with its last
+                        // occurrence in the pattern, if that is left of the mismatch
+                        let bad_char =
+                            (self.bad_char[text[start + j].index()] + j + 1).saturating_sub(m);
+                        pos += self.good_suffix[j].max(bad_char);
+                    }
+                }
+            }
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use itertools::Itertools;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+
+    fn naive_indexes<C: CharT>(text: &AStr<C>, pattern: &AStr<C>) -> Vec<usize> {
+        (0..=text.len())
+            .filter(|&pos| text[pos..].starts_with(pattern))
+            .collect()
+    }
+
+    fn indexes<M: Matcher<C>, C: CharT>(text: &AStr<C>, pattern: &AStr<C>) -> Vec<usize> {
+        M::new(pattern).find_iter(text).collect()
+    }
+
+    #[test]
+    fn test_z_array() {
+        assert_eq!(
+            z_array(ascii("aabcaabxaaaz")),
+            vec![12, 1, 0, 0, 3, 1, 0, 0, 2, 2, 1, 0]
+        );
+        assert_eq!(z_array(ascii("")), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_good_suffix_shifts() {
+        assert_eq!(
+            good_suffix_shifts(ascii("GCAGAGAG")),
+            vec![7, 7, 7, 2, 7, 4, 7, 1]
+        );
+        assert_eq!(good_suffix_shifts(ascii("aaaa")), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_matchers() {
+        let text = ascii("abracadabra abracadabra");
+        let pattern = ascii("abra");
+        let expected = vec![0, 7, 12, 19];
+        assert_eq!(indexes::<Kmp<_>, _>(text, pattern), expected);
+        assert_eq!(indexes::<ZMatcher<_>, _>(text, pattern), expected);
+        assert_eq!(indexes::<Horspool<_>, _>(text, pattern), expected);
+        assert_eq!(indexes::<BoyerMoore<_>, _>(text, pattern), expected);
+
+        // overlapping occurrences
+        let expected = vec![0, 1, 2];
+        assert_eq!(indexes::<Kmp<_>, _>(ascii("aaaa"), ascii("aa")), expected);
+        assert_eq!(
+            indexes::<BoyerMoore<_>, _>(ascii("aaaa"), ascii("aa")),
+            expected
+        );
+
+        assert_eq!(
+            indexes::<Horspool<_>, _>(ascii("abc"), ascii("")),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(Kmp::new(ascii("abcd")).find(ascii("abc")), None);
+        assert_eq!(ZMatcher::new(ascii("cad")).find(text), Some(4));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn prop_test_matchers(text in arb_astring::<Char>(0..40), pattern in arb_astring::<Char>(0..6)) {
+            let expected = naive_indexes(&text, &pattern);
+            prop_assert_eq!(indexes::<Kmp<_>, _>(&text, &pattern), expected.clone());
+            prop_assert_eq!(indexes::<ZMatcher<_>, _>(&text, &pattern), expected.clone());
+            prop_assert_eq!(indexes::<Horspool<_>, _>(&text, &pattern), expected.clone());
+            prop_assert_eq!(indexes::<BoyerMoore<_>, _>(&text, &pattern), expected);
+        }
+
+        #[test]
+        fn prop_test_z_array(s in arb_astring::<Char>(0..30)) {
+            let expected = (0..s.len())
+                .map(|pos| s.iter().zip_eq(s[pos..].iter().chain(iter::repeat_n(&s[0], pos))).take(s.len() - pos).take_while(|(ch1, ch2)| ch1 == ch2).count())
+                .collect_vec();
+            prop_assert_eq!(z_array(&s), expected);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Maximal exact matches (MEMs) and maximal unique matches (MUMs) between two strings, as
+//! anchors for whole genome alignment (MUMmer). Both are found from the matching statistics
+//! of the second string in the suffix trie of the first
+
+use crate::alphabet_model::CharT;
+use crate::string::suffix_automaton::build_suffix_automaton;
+use crate::string::suffix_trie_mcc_arena::{
+    Node, SuffixTrie, build_trie_with_allocator, terminals,
+};
+use crate::string_model::AStr;
+use bumpalo::Bump;
+use generic_array::ArrayLength;
+use std::ptr;
+
+/// Exact match `s[s_start..s_start + len] == t[t_start..t_start + len]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaximalMatch {
+    pub s_start: usize,
+    pub t_start: usize,
+    pub len: usize,
+}
+
+fn is_left_maximal<C: CharT>(s: &AStr<C>, t: &AStr<C>, s_start: usize, t_start: usize) -> bool {
+    s_start == 0 || t_start == 0 || s[s_start - 1] != t[t_start - 1]
+}
+
+/// Terminal below `node` if there is exactly one. In the compacted trie a node without a
+/// terminal has at least two children, so this is the case only for leaves
+fn unique_terminal<C, N: ArrayLength>(node: &Node<'_, '_, C, N>) -> Option<usize> {
+    if node.children.iter().any(|edge| edge.is_some()) {
+        None
+    } else {
+        node.terminal.as_ref().map(|terminal| terminal.suffix_index)
+    }
+}
+
+/// Maximal exact matches of length at least `min_len`, ordered by position in `t` and then
+/// in `s`. A match is maximal if it can not be extended to the left or right
+pub fn mems<C: CharT>(s: &AStr<C>, t: &AStr<C>, min_len: usize) -> Vec<MaximalMatch> {
+    let bump = Bump::new();
+    let trie = build_trie_with_allocator(s, &bump);
+    mems_in_trie(&trie, t, min_len)
+}
+
+/// Maximal exact matches as in `mems`, with `s` given by its suffix trie, for matching
+/// several strings against the same `s`
+pub fn mems_in_trie<C: CharT>(
+    trie: &SuffixTrie<'_, '_, C>,
+    t: &AStr<C>,
+    min_len: usize,
+) -> Vec<MaximalMatch> {
+    let min_len = min_len.max(1);
+    let s = trie.string();
+
+    let mut mems = Vec::new();
+    for (t_start, statistic) in trie.matching_statistics(t).enumerate() {
+        if statistic.length < min_len {
+            continue;
+        }
+        let mut add_match = |s_start: usize, len: usize| {
+            if is_left_maximal(s, t, s_start, t_start) {
+                mems.push(MaximalMatch {
+                    s_start,
+                    t_start,
+                    len,
+                });
+            }
+        };
+
+        // the suffixes of s below the locus share the whole match
+        terminals(&statistic.locus.borrow(), |s_start| {
+            add_match(s_start, statistic.length)
+        });
+
+        // the suffixes branching off above share the prefix up to the branching node
+        let mut child = statistic.locus;
+        let mut depth = statistic.locus_depth;
+        loop {
+            let parent_edge = child.borrow().parent;
+            let Some(parent_edge) = parent_edge else {
+                break;
+            };
+            let parent_edge_ref = parent_edge.borrow();
+            depth -= parent_edge_ref.chars.len();
+            if depth < min_len {
+                break;
+            }
+            let node = parent_edge_ref.source.borrow();
+            if let Some(terminal) = &node.terminal {
+                add_match(terminal.suffix_index, depth);
+            }
+            for edge in node.children.iter().flatten() {
+                let target = edge.borrow().target;
+                if !ptr::eq(target, child) {
+                    terminals(&target.borrow(), |s_start| add_match(s_start, depth));
+                }
+            }
+            child = parent_edge_ref.source;
+        }
+    }
+
+    mems.sort_by_key(|mem| (mem.t_start, mem.s_start));
+    mems
+}
+
+/// Maximal unique matches of length at least `min_len`, ordered by position in `t`. A MUM is
+/// a maximal exact match that occurs exactly once in `s` and once in `t`
+pub fn mums<C: CharT>(s: &AStr<C>, t: &AStr<C>, min_len: usize) -> Vec<MaximalMatch> {
+    let bump = Bump::new();
+    let trie = build_trie_with_allocator(s, &bump);
+    mums_in_trie(&trie, t, min_len)
+}
+
+/// Maximal unique matches as in `mums`, with `s` given by its suffix trie, for matching
+/// several strings against the same `s`
+pub fn mums_in_trie<C: CharT>(
+    trie: &SuffixTrie<'_, '_, C>,
+    t: &AStr<C>,
+    min_len: usize,
+) -> Vec<MaximalMatch> {
+    let min_len = min_len.max(1);
+    let s = trie.string();
+    let t_automaton = build_suffix_automaton(t);
+
+    // a match unique in s is maximal to the right only if it is the longest match from its
+    // position in t, so only the matching statistics are candidates
+    trie.matching_statistics(t)
+        .enumerate()
+        .filter(|(_, statistic)| statistic.length >= min_len)
+        .filter_map(|(t_start, statistic)| {
+            let s_start = unique_terminal(&statistic.locus.borrow())?;
+            let len = statistic.length;
+            (is_left_maximal(s, t, s_start, t_start)
+                && t_automaton.count(&t[t_start..t_start + len]) == 1)
+                .then_some(MaximalMatch {
+                    s_start,
+                    t_start,
+                    len,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use itertools::Itertools;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+
+    fn naive_mems<C: CharT>(s: &AStr<C>, t: &AStr<C>, min_len: usize) -> Vec<MaximalMatch> {
+        (0..t.len())
+            .cartesian_product(0..s.len())
+            .filter(|&(t_start, s_start)| is_left_maximal(s, t, s_start, t_start))
+            .map(|(t_start, s_start)| MaximalMatch {
+                s_start,
+                t_start,
+                len: s[s_start..]
+                    .iter()
+                    .zip(t[t_start..].iter())
+                    .take_while(|(a, b)| a == b)
+                    .count(),
+            })
+            .filter(|mem| mem.len >= min_len.max(1))
+            .collect()
+    }
+
+    fn naive_mums<C: CharT>(s: &AStr<C>, t: &AStr<C>, min_len: usize) -> Vec<MaximalMatch> {
+        let count = |text: &AStr<C>, pattern: &AStr<C>| {
+            text.windows(pattern.len())
+                .filter(|window| *window == pattern.as_slice())
+                .count()
+        };
+        naive_mems(s, t, min_len)
+            .into_iter()
+            .filter(|mem| {
+                let substr = &t[mem.t_start..mem.t_start + mem.len];
+                count(s, substr) == 1 && count(t, substr) == 1
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_mems() {
+        let s = ascii("xabcdyabcz");
+        let t = ascii("abcdwabc");
+        assert_eq!(
+            mems(s, t, 3),
+            vec![
+                MaximalMatch {
+                    s_start: 1,
+                    t_start: 0,
+                    len: 4
+                },
+                MaximalMatch {
+                    s_start: 6,
+                    t_start: 0,
+                    len: 3
+                },
+                MaximalMatch {
+                    s_start: 1,
+                    t_start: 5,
+                    len: 3
+                },
+                MaximalMatch {
+                    s_start: 6,
+                    t_start: 5,
+                    len: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mums() {
+        let s = ascii("xabcdyabcz");
+        let t = ascii("abcdwyabcz");
+        assert_eq!(
+            mums(s, t, 3),
+            vec![
+                MaximalMatch {
+                    s_start: 1,
+                    t_start: 0,
+                    len: 4
+                },
+                MaximalMatch {
+                    s_start: 5,
+                    t_start: 5,
+                    len: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matches_in_trie() {
+        let s = ascii("xabcdyabcz");
+        let bump = Bump::new();
+        let trie = build_trie_with_allocator(s, &bump);
+        for t in [ascii("abcdwyabcz"), ascii("yabcdy"), ascii("")] {
+            assert_eq!(mums_in_trie(&trie, t, 2), mums(s, t, 2));
+            assert_eq!(mems_in_trie(&trie, t, 2), mems(s, t, 2));
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn prop_test_mems(s in arb_astring::<Char>(0..20), t in arb_astring::<Char>(0..20), min_len in 0..4usize) {
+            let expected = naive_mems(&s, &t, min_len)
+                .into_iter()
+                .sorted_by_key(|mem| (mem.t_start, mem.s_start))
+                .collect_vec();
+            prop_assert_eq!(mems(&s, &t, min_len), expected);
+        }
+
+        #[test]
+        fn prop_test_mums(s in arb_astring::<Char>(0..20), t in arb_astring::<Char>(0..20), min_len in 0..4usize) {
+            let expected = naive_mums(&s, &t, min_len)
+                .into_iter()
+                .sorted_by_key(|mum| mum.t_start)
+                .collect_vec();
+            prop_assert_eq!(mums(&s, &t, min_len), expected);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! PROSITE patterns, e.g. `N-{P}-[ST]-{P}`. Supported are single chars, `x` for any char,
+//! `[..]` for any of the given chars, `{..}` for any char except the given ones, repeats
+//! `e(n)` and `e(n,m)`, the anchors `<` at the start and `>` at the end, and an optional
+//! terminating `.`. The `-` between elements may be left out
+//!
+//! The pattern is compiled to an NFA with one state per repeated element. The text is scanned
+//! right to left, so that all occurrences, including overlapping ones, are found in one pass
+
+use crate::alphabet_model::CharT;
+use crate::string_model::AStr;
+use generic_array::typenum::Unsigned;
+use std::iter::Peekable;
+use std::marker::PhantomData;
+use std::str::{Chars, FromStr};
+
+/// Occurrence of a motif at `text[start..end]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MotifMatch {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    /// Whether each char, by index, is accepted
+    class: Vec<bool>,
+    min: usize,
+    max: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    element: usize,
+    optional: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Motif<C> {
+    elements: Vec<Element>,
+    states: Vec<State>,
+    anchored_start: bool,
+    anchored_end: bool,
+    _char: PhantomData<C>,
+}
+
+impl<C: CharT> FromStr for Motif<C> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars().peekable();
+        let anchored_start = chars.next_if_eq(&'<').is_some();
+        let mut anchored_end = false;
+        let mut elements = Vec::new();
+        loop {
+            elements.push(parse_element::<C>(&mut chars)?);
+            match chars.peek() {
+                Some('-') => {
+                    chars.next();
+                }
+                Some('>') => {
+                    chars.next();
+                    anchored_end = true;
+                    break;
+                }
+                Some('.') | None => break,
+                Some(_) => {}
+            }
+        }
+        chars.next_if_eq(&'.');
+        if let Some(ch) = chars.next() {
+            return Err(format!("unexpected '{}' at end of motif", ch));
+        }
+
+        let states = elements
+            .iter()
+            .enumerate()
+            .flat_map(|(element_index, element)| {
+                (0..element.max).map(move |i| State {
+                    element: element_index,
+                    optional: i >= element.min,
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            elements,
+            states,
+            anchored_start,
+            anchored_end,
+            _char: PhantomData,
+        })
+    }
+}
+
+fn parse_char<C: CharT>(ch: char) -> Result<usize, String> {
+    C::from_char(ch)
+        .map(|ch| ch.index())
+        .ok_or_else(|| format!("invalid char '{}' in motif", ch))
+}
+
+fn parse_class<C: CharT>(
+    chars: &mut Peekable<Chars<'_>>,
+    close: char,
+) -> Result<Vec<bool>, String> {
+    let mut class = vec![false; C::AlphabetSize::USIZE];
+    loop {
+        match chars.next() {
+            Some(ch) if ch == close => return Ok(class),
+            Some(ch) => class[parse_char::<C>(ch)?] = true,
+            None => return Err(format!("missing '{}' in motif", close)),
+        }
+    }
+}
+
+fn parse_number(chars: &mut Peekable<Chars<'_>>) -> Result<usize, String> {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(|ch| ch.is_ascii_digit()) {
+        digits.push(digit);
+    }
+    digits
+        .parse()
+        .map_err(|_| "invalid repeat count in motif".to_string())
+}
+
+fn parse_element<C: CharT>(chars: &mut Peekable<Chars<'_>>) -> Result<Element, String> {
+    let class = match chars.next() {
+        Some('x') => vec![true; C::AlphabetSize::USIZE],
+        Some('[') => parse_class::<C>(chars, ']')?,
+        Some('{') => parse_class::<C>(chars, '}')?
+            .into_iter()
+            .map(|excluded| !excluded)
+            .collect(),
+        Some(ch) => {
+            let mut class = vec![false; C::AlphabetSize::USIZE];
+            class[parse_char::<C>(ch)?] = true;
+            class
+        }
+        None => return Err("missing element in motif".to_string()),
+    };
+
+    let (min, max) = if chars.next_if_eq(&'(').is_some() {
+        let min = parse_number(chars)?;
+        let max = if chars.next_if_eq(&',').is_some() {
+            parse_number(chars)?
+        } else {
+            min
+        };
+        if chars.next() != Some(')') {
+            return Err("missing ')' in motif".to_string());
+        }
+        if min > max {
+            return Err(format!("invalid repeat ({},{}) in motif", min, max));
+        }
+        (min, max)
+    } else {
+        (1, 1)
+    };
+
+    Ok(Element { class, min, max })
+}
+
+/// Keeps the shortest of two match ends
+fn min_end(end: Option<usize>, other: Option<usize>) -> Option<usize> {
+    match (end, other) {
+        (Some(end), Some(other)) => Some(end.min(other)),
+        (end, other) => end.or(other),
+    }
+}
+
+impl<C: CharT> Motif<C> {
+    /// Occurrences of the motif in `text` by increasing start position. For each start
+    /// position, only the shortest occurrence is given
+    pub fn find_iter(&self, text: &AStr<C>) -> impl Iterator<Item = MotifMatch> {
+        let state_count = self.states.len();
+        // active[p] is the shortest end of the text matching the states from p onwards,
+        // starting at the current position
+        let mut active: Vec<Option<usize>> = vec![None; state_count + 1];
+        let mut next_active = active.clone();
+        let mut matches = Vec::new();
+        for pos in (0..=text.len()).rev() {
+            if !self.anchored_end || pos == text.len() {
+                active[state_count] = min_end(active[state_count], Some(pos));
+            }
+            for p in (1..=state_count).rev() {
+                if self.states[p - 1].optional {
+                    active[p - 1] = min_end(active[p - 1], active[p]);
+                }
+            }
+            if let Some(end) = active[0]
+                && (!self.anchored_start || pos == 0)
+            {
+                matches.push(MotifMatch { start: pos, end });
+            }
+
+            if pos > 0 {
+                let ch = text[pos - 1].index();
+                next_active.fill(None);
+                for p in 1..=state_count {
+                    if self.elements[self.states[p - 1].element].class[ch] {
+                        next_active[p - 1] = active[p];
+                    }
+                }
+                (active, next_active) = (next_active, active);
+            }
+        }
+        matches.into_iter().rev()
+    }
+
+    /// Start positions of the occurrences of the motif in `text`
+    pub fn indexes(&self, text: &AStr<C>) -> Vec<usize> {
+        self.find_iter(text).map(|mtch| mtch.start).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::polymers::{DnaNt, ProteinAa};
+    use crate::string_model::test_util::Char;
+    use crate::string_model::{AString, arb_astring};
+    use itertools::Itertools;
+    use proptest::collection::vec;
+    use proptest::prelude::{ProptestConfig, Strategy, any};
+    use proptest::{prop_assert_eq, proptest};
+    use regex::Regex;
+
+    fn parse_motif<C: CharT>(s: &str) -> Motif<C> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_n_glycosylation() {
+        let aas: AString<ProteinAa> = "MKNNSTSNGTKNQTFP".parse().unwrap();
+        let motif = parse_motif::<ProteinAa>("N-{P}-[ST]-{P}");
+        assert_eq!(motif.indexes(&aas), vec![2, 3, 7, 11]);
+        assert_eq!(
+            motif.find_iter(&aas).next(),
+            Some(MotifMatch { start: 2, end: 6 })
+        );
+        assert_eq!(
+            parse_motif::<ProteinAa>("N{P}[ST]{P}.").indexes(&aas),
+            vec![2, 3, 7, 11]
+        );
+    }
+
+    #[test]
+    fn test_repeats_and_anchors() {
+        let nts: AString<DnaNt> = "TATAAAGCGTATA".parse().unwrap();
+        assert_eq!(
+            parse_motif::<DnaNt>("T-A-x(1,2)-A")
+                .find_iter(&nts)
+                .collect_vec(),
+            vec![
+                MotifMatch { start: 0, end: 4 },
+                MotifMatch { start: 2, end: 6 },
+                MotifMatch { start: 9, end: 13 },
+            ]
+        );
+        assert_eq!(parse_motif::<DnaNt>("<T-A").indexes(&nts), vec![0]);
+        assert_eq!(parse_motif::<DnaNt>("T-A>").indexes(&nts), vec![11]);
+        assert_eq!(parse_motif::<DnaNt>("[GC](2)").indexes(&nts), vec![6, 7]);
+        assert_eq!(parse_motif::<DnaNt>("<x(0,20)>").indexes(&nts), vec![0]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("N-{P".parse::<Motif<ProteinAa>>().is_err());
+        assert!("N-B".parse::<Motif<ProteinAa>>().is_err());
+        assert!("N-x(3,2)".parse::<Motif<ProteinAa>>().is_err());
+        assert!("N-".parse::<Motif<ProteinAa>>().is_err());
+        assert!("N>-A".parse::<Motif<ProteinAa>>().is_err());
+        assert!("".parse::<Motif<DnaNt>>().is_err());
+    }
+
+    /// Motif over `Char` in PROSITE and in regex syntax
+    fn arb_motif() -> impl Strategy<Value = (String, String)> {
+        let element =
+            (0..4usize, any::<bool>(), 0..3usize, 0..3usize).prop_map(|(kind, a, min, extra)| {
+                let ch = if a { "A" } else { "B" };
+                let (prosite, regex) = match kind {
+                    0 => ("x".to_string(), "[AB]".to_string()),
+                    1 => (ch.to_string(), ch.to_string()),
+                    2 => ("[AB]".to_string(), "[AB]".to_string()),
+                    _ => (format!("{{{}}}", ch), format!("[^{}]", ch)),
+                };
+                let (min, max) = (min, min + extra);
+                (
+                    format!("{}({},{})", prosite, min, max),
+                    format!("{}{{{},{}}}", regex, min, max),
+                )
+            });
+        (any::<bool>(), vec(element, 1..4), any::<bool>()).prop_map(
+            |(anchored_start, elements, anchored_end)| {
+                let (prosite, regex): (Vec<_>, Vec<_>) = elements.into_iter().unzip();
+                (
+                    format!(
+                        "{}{}{}",
+                        if anchored_start { "<" } else { "" },
+                        prosite.join("-"),
+                        if anchored_end { ">" } else { "" }
+                    ),
+                    format!(
+                        "^(?:{}){}",
+                        regex.join(""),
+                        if anchored_end { "$" } else { "" }
+                    ),
+                )
+            },
+        )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn prop_test_motif(text in arb_astring::<Char>(0..30), (prosite, regex) in arb_motif()) {
+            let motif = parse_motif::<Char>(&prosite);
+            let regex = Regex::new(&regex).unwrap();
+            let text_str = text.to_string();
+            let anchored_start = prosite.starts_with('<');
+            let expected = (0..=text.len())
+                .filter(|&start| !anchored_start || start == 0)
+                .filter_map(|start| {
+                    regex
+                        .shortest_match(&text_str[start..])
+                        .map(|len| MotifMatch { start, end: start + len })
+                })
+                .collect_vec();
+            prop_assert_eq!(motif.find_iter(&text).collect_vec(), expected);
+        }
+    }
+}
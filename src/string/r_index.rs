@@ -0,0 +1,328 @@
+//! Run-length compressed BWT with locate (r-index, Gagie, Navarro and Prezza). The BWT of a
+//! collection of similar strings has few runs of equal chars, and the index stores a constant
+//! number of values per run instead of per char
+//!
+//! Backward search keeps the suffix array value of the last row of the range (the toehold).
+//! The rows with the same char in `l` as the row before map to adjacent rows under LF, so the
+//! suffix array value of the previous row of any row follows from samples at run starts (phi)
+
+use crate::alphabet_model::{CharT, CharT2};
+use crate::string::bwt::{WithTerminal, build_l_and_suffix_array, f_char_indexes};
+use crate::string_model::AStr;
+use generic_array::GenericArray;
+use generic_array::typenum::Unsigned;
+use hashbrown::HashSet;
+use std::ops::Range;
+
+#[derive(Debug)]
+pub struct RIndex<C: CharT2> {
+    /// Length of `l`, the indexed string with terminal
+    len: usize,
+    /// Char of each run in `l`
+    run_chars: Vec<usize>,
+    /// Start index in `l` of each run, and the length of `l` at the end
+    run_starts: Vec<usize>,
+    /// For each char, the runs of the char
+    char_runs: Vec<Vec<usize>>,
+    /// For each char, the count of the char in `l` before each run of the char, and the total
+    /// count at the end
+    char_run_ranks: Vec<Vec<usize>>,
+    /// Index of first occurrence of char in f
+    f_char_indexes: GenericArray<usize, C::AlphabetSizeP2>,
+    /// Suffix array value of the last row of each run
+    run_end_samples: Vec<usize>,
+    /// Suffix array values of the first row of each run except the first run, with the suffix
+    /// array value of the row before, sorted
+    phi_samples: Vec<(usize, usize)>,
+}
+
+/// Size of a run-length compressed BWT
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RunLengthStats {
+    /// Length of the BWT, including the terminal
+    pub len: usize,
+    /// Number of runs of equal chars in the BWT
+    pub runs: usize,
+}
+
+impl RunLengthStats {
+    pub fn avg_run_len(&self) -> f64 {
+        self.len as f64 / self.runs as f64
+    }
+}
+
+/// Builds the r-index from the BWT of `s` terminated by `$`, as in `bwt::build_bwt_with`
+pub fn build_r_index<C: CharT2>(s: &AStr<C>) -> RIndex<C>
+where
+    WithTerminal<C>: CharT,
+{
+    let (l, suffix_array) = build_l_and_suffix_array(s);
+    let alphabet_size = <WithTerminal<C> as CharT>::AlphabetSize::USIZE;
+
+    let mut run_chars = Vec::new();
+    let mut run_starts = Vec::new();
+    let mut char_runs = vec![Vec::new(); alphabet_size];
+    let mut char_run_ranks = vec![Vec::new(); alphabet_size];
+    let mut char_counts = vec![0; alphabet_size];
+    let mut run_end_samples = Vec::new();
+    let mut phi_samples = Vec::new();
+    for (idx, ch) in l.iter().enumerate() {
+        let ch = ch.index();
+        if run_chars.last() != Some(&ch) {
+            if idx > 0 {
+                run_end_samples.push(suffix_array[idx - 1]);
+                phi_samples.push((suffix_array[idx], suffix_array[idx - 1]));
+            }
+            char_runs[ch].push(run_chars.len());
+            char_run_ranks[ch].push(char_counts[ch]);
+            run_chars.push(ch);
+            run_starts.push(idx);
+        }
+        char_counts[ch] += 1;
+    }
+    run_end_samples.push(suffix_array[l.len() - 1]);
+    run_starts.push(l.len());
+    for (ranks, count) in char_run_ranks.iter_mut().zip(char_counts) {
+        ranks.push(count);
+    }
+    phi_samples.sort_unstable();
+
+    RIndex {
+        len: l.len(),
+        run_chars,
+        run_starts,
+        char_runs,
+        char_run_ranks,
+        f_char_indexes: f_char_indexes::<C>(&l),
+        run_end_samples,
+        phi_samples,
+    }
+}
+
+impl<C: CharT2> RIndex<C>
+where
+    WithTerminal<C>: CharT,
+{
+    /// Run containing index `idx` of `l`
+    fn run(&self, idx: usize) -> usize {
+        self.run_starts.partition_point(|&start| start <= idx) - 1
+    }
+
+    /// Number of occurrences of char with index `ch` in `l[..idx]`
+    fn rank_index(&self, idx: usize, ch: usize) -> usize {
+        if idx == 0 {
+            return 0;
+        }
+        let run = self.run(idx - 1);
+        // runs of the char before the run
+        let char_run = self.char_runs[ch].partition_point(|&char_run| char_run < run);
+        let rank = self.char_run_ranks[ch][char_run];
+        if self.run_chars[run] == ch {
+            rank + idx - self.run_starts[run]
+        } else {
+            rank
+        }
+    }
+
+    /// Index in `l` of the occurrence of char with index `ch` with rank `rank` (from 0)
+    fn select_index(&self, ch: usize, rank: usize) -> Option<usize> {
+        let ranks = &self.char_run_ranks[ch];
+        if rank >= *ranks.last().unwrap() {
+            return None;
+        }
+        let char_run = ranks.partition_point(|&run_rank| run_rank <= rank) - 1;
+        Some(self.run_starts[self.char_runs[ch][char_run]] + rank - ranks[char_run])
+    }
+
+    /// Number of occurrences of `ch` in `l[..idx]`
+    pub fn rank(&self, idx: usize, ch: C) -> usize {
+        self.rank_index(idx, WithTerminal::Char(ch).index())
+    }
+
+    /// Index in `l` of the occurrence of `ch` with rank `rank` (from 0)
+    pub fn select(&self, ch: C, rank: usize) -> Option<usize> {
+        self.select_index(WithTerminal::Char(ch).index(), rank)
+    }
+
+    /// Range of all rows in the sorted suffixes (matches the empty string)
+    pub fn full_range(&self) -> Range<usize> {
+        0..self.len
+    }
+
+    /// Narrows range of rows matching `t` to the rows matching `ch` followed by `t`
+    pub fn extend_backward(&self, range: Range<usize>, ch: C) -> Range<usize> {
+        let ch = WithTerminal::Char(ch).index();
+        let low = self.f_char_indexes[ch] + self.rank_index(range.start, ch);
+        let high = self.f_char_indexes[ch] + self.rank_index(range.end, ch);
+        low..high
+    }
+
+    /// Range of rows in the sorted suffixes that have `t` as prefix, with the index in s of
+    /// the suffix in the last row of the range if not empty
+    fn suffix_range_with_toehold(&self, t: &AStr<C>) -> (Range<usize>, Option<usize>) {
+        let mut range = self.full_range();
+        let mut toehold = *self.run_end_samples.last().unwrap();
+
+        for ch in t.iter().copied().rev() {
+            let ch_index = WithTerminal::Char(ch).index();
+            let last = range.end - 1;
+            let next_range = self.extend_backward(range, ch);
+            if next_range.is_empty() {
+                return (next_range, None);
+            }
+            // the last row of the new range comes from the last occurrence of the char in the
+            // range, which ends a run unless it is the last row
+            let last_ch = if self.run_chars[self.run(last)] == ch_index {
+                toehold
+            } else {
+                let last_ch_idx = self
+                    .select_index(ch_index, self.rank_index(last + 1, ch_index) - 1)
+                    .unwrap();
+                self.run_end_samples[self.run(last_ch_idx)]
+            };
+            toehold = (last_ch + self.len - 1) % self.len;
+            range = next_range;
+        }
+
+        (range, Some(toehold))
+    }
+
+    /// Range of rows in the sorted suffixes that have `t` as prefix
+    pub fn suffix_range(&self, t: &AStr<C>) -> Range<usize> {
+        self.suffix_range_with_toehold(t).0
+    }
+
+    /// Number of occurrences of `t`
+    pub fn count(&self, t: &AStr<C>) -> usize {
+        self.suffix_range(t).len()
+    }
+
+    /// Index in s of the suffix in the row before the row of the suffix at `suffix`
+    fn phi(&self, suffix: usize) -> usize {
+        let sample = self
+            .phi_samples
+            .partition_point(|&(sampled, _)| sampled <= suffix)
+            - 1;
+        let (sampled, previous) = self.phi_samples[sample];
+        previous + suffix - sampled
+    }
+
+    pub fn indexes_substr(&self, t: &AStr<C>) -> HashSet<usize> {
+        let (range, toehold) = self.suffix_range_with_toehold(t);
+        let Some(mut suffix) = toehold else {
+            return HashSet::new();
+        };
+        let mut indexes = HashSet::with_capacity(range.len());
+        indexes.insert(suffix);
+        for _ in 1..range.len() {
+            suffix = self.phi(suffix);
+            indexes.insert(suffix);
+        }
+        indexes
+    }
+
+    /// Length of the indexed string (without terminal)
+    pub fn len(&self) -> usize {
+        self.len - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of runs of equal chars in the BWT
+    pub fn run_count(&self) -> usize {
+        self.run_chars.len()
+    }
+
+    pub fn stats(&self) -> RunLengthStats {
+        RunLengthStats {
+            len: self.len,
+            runs: self.run_count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::string;
+    use crate::string::bwt::build_bwt;
+    use crate::string_model::test_util::Char;
+    use crate::string_model::{AString, arb_astring};
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+
+    #[test]
+    fn test_runs() {
+        use crate::string_model::test_util::Char::*;
+
+        // l is "abba$aa"
+        let s: &AStr<Char> = AStr::from_slice(&[A, B, A, A, B, A]);
+        let index = build_r_index(s);
+        assert_eq!(index.stats(), RunLengthStats { len: 7, runs: 5 });
+        assert_eq!(index.rank(4, A), 2);
+        assert_eq!(index.rank(7, A), 4);
+        assert_eq!(index.rank(3, B), 2);
+        assert_eq!(index.select(A, 2), Some(5));
+        assert_eq!(index.select(B, 1), Some(2));
+        assert_eq!(index.select(B, 2), None);
+    }
+
+    #[test]
+    fn test_repetitive() {
+        use crate::string_model::test_util::Char::*;
+
+        let unit = [A, B, B, A, B, A, A, B, A, B, B, B];
+        let s = AString::from(unit.repeat(50));
+        let index = build_r_index(&s);
+        assert!(index.run_count() < 30);
+        assert_eq!(index.count(AStr::from_slice(&unit)), 50);
+        assert_eq!(
+            index.indexes_substr(AStr::from_slice(&unit)),
+            (0..50).map(|copy| copy * unit.len()).collect()
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let s: &AStr<Char> = AStr::from_slice(&[]);
+        let index = build_r_index(s);
+        assert!(index.is_empty());
+        assert_eq!(
+            index.indexes_substr(AStr::from_slice(&[])),
+            HashSet::from([0])
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn prop_test_indexes_substr(s in arb_astring::<Char>(0..30), t in arb_astring::<Char>(0..4)) {
+            let index = build_r_index(&s);
+            let expected = string::indexes(&s, &t);
+            prop_assert_eq!(index.count(&t), expected.len());
+            prop_assert_eq!(index.indexes_substr(&t), HashSet::from_iter(expected));
+            prop_assert_eq!(index.suffix_range(&t), build_bwt(&s).suffix_range(&t));
+        }
+
+        #[test]
+        fn prop_test_rank_select(s in arb_astring::<Char>(0..30)) {
+            let index = build_r_index(&s);
+            let (l, _) = build_l_and_suffix_array(&s);
+            for ch in [Char::A, Char::B] {
+                let positions = (0..l.len())
+                    .filter(|&idx| l[idx] == WithTerminal::Char(ch))
+                    .collect::<Vec<_>>();
+                for idx in 0..=l.len() {
+                    prop_assert_eq!(index.rank(idx, ch), positions.iter().filter(|&&pos| pos < idx).count());
+                }
+                for (rank, &pos) in positions.iter().enumerate() {
+                    prop_assert_eq!(index.select(ch, rank), Some(pos));
+                }
+                prop_assert_eq!(index.select(ch, positions.len()), None);
+            }
+        }
+    }
+}
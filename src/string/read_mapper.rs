@@ -0,0 +1,419 @@
+//! Seed-and-extend mapping of reads to a reference indexed by a BWT. Exact seeds are found
+//! with backward search, colinear seeds are chained and the best chains are extended with
+//! banded alignment
+
+pub use crate::polymers::Strand;
+use crate::polymers::{DnaNt, reverse_complement};
+use crate::string::alignment::local_alignment_banded::local_alignment_banded;
+use crate::string::alignment::{AlignmentProperties, Edit, cigar, edit_distance};
+use crate::string::bwt::{BWT, build_bwt};
+use crate::string_model::{AStr, AString};
+use crate::util::sam::{FLAG_REVERSE, SamRecord, SamTag};
+use itertools::Itertools;
+use std::cmp::Reverse;
+
+const MAX_MAPQ: u8 = 60;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SeedMode {
+    /// Seeds are the k-mers of the read starting every `step` positions
+    Kmer { k: usize, step: usize },
+    /// Seeds are the maximal exact matches of the read of length at least `min_len`
+    Mem { min_len: usize },
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MapperProperties {
+    pub seed: SeedMode,
+    /// Number of diagonals on each side of the chain searched when extending
+    pub band: usize,
+    /// Seeds occurring more often in the reference are ignored
+    pub max_seed_occurrences: usize,
+    /// Number of chains extended per read
+    pub max_candidates: usize,
+    pub alignment: AlignmentProperties,
+}
+
+impl MapperProperties {
+    pub fn seed(mut self, seed: SeedMode) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn band(mut self, band: usize) -> Self {
+        self.band = band;
+        self
+    }
+
+    pub fn max_seed_occurrences(mut self, max_seed_occurrences: usize) -> Self {
+        self.max_seed_occurrences = max_seed_occurrences;
+        self
+    }
+
+    pub fn max_candidates(mut self, max_candidates: usize) -> Self {
+        self.max_candidates = max_candidates;
+        self
+    }
+
+    pub fn alignment(mut self, alignment: AlignmentProperties) -> Self {
+        self.alignment = alignment;
+        self
+    }
+}
+
+impl Default for MapperProperties {
+    fn default() -> Self {
+        Self {
+            seed: SeedMode::Kmer { k: 16, step: 8 },
+            band: 10,
+            max_seed_occurrences: 20,
+            max_candidates: 5,
+            alignment: AlignmentProperties::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReadMapping {
+    /// 0-based position in the reference of the first aligned base
+    pub position: usize,
+    /// Strand of the reference the read maps to. Edits are relative to the reverse
+    /// complement of the read for [`Strand::Reverse`]
+    pub strand: Strand,
+    pub edits: AString<Edit>,
+    pub penalty: isize,
+    /// Phred scaled probability that the position is wrong
+    pub mapq: u8,
+}
+
+/// Exact match of `len` chars between read and reference
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Seed {
+    read_pos: usize,
+    ref_pos: usize,
+    len: usize,
+}
+
+impl Seed {
+    fn diagonal(&self) -> isize {
+        self.ref_pos as isize - self.read_pos as isize
+    }
+}
+
+/// Colinear seeds on one strand
+#[derive(Debug, Clone)]
+struct Chain {
+    strand: Strand,
+    score: usize,
+    min_diagonal: isize,
+    max_diagonal: isize,
+}
+
+pub struct Mapper {
+    reference: AString<DnaNt>,
+    bwt: BWT<DnaNt>,
+    props: MapperProperties,
+}
+
+impl Mapper {
+    pub fn new(reference: AString<DnaNt>, props: MapperProperties) -> Self {
+        let bwt = build_bwt(&reference);
+        Self {
+            reference,
+            bwt,
+            props,
+        }
+    }
+
+    pub fn reference(&self) -> &AStr<DnaNt> {
+        &self.reference
+    }
+
+    /// Best mapping of the read on either strand, or `None` if no seeds were found
+    pub fn map(&self, read: &AStr<DnaNt>) -> Option<ReadMapping> {
+        let read_rc = reverse_complement(read);
+
+        let mut chains = self.chains(read, Strand::Forward);
+        chains.extend(self.chains(&read_rc, Strand::Reverse));
+        chains.sort_by_key(|chain| Reverse(chain.score));
+
+        let mut mappings = chains
+            .iter()
+            .take(self.props.max_candidates)
+            .filter_map(|chain| {
+                let read = match chain.strand {
+                    Strand::Forward => read,
+                    Strand::Reverse => &read_rc,
+                };
+                self.extend(read, chain)
+            })
+            .sorted_by_key(|mapping| mapping.penalty)
+            .dedup_by(|mapping1, mapping2| {
+                mapping1.position == mapping2.position && mapping1.strand == mapping2.strand
+            });
+
+        let mut best = mappings.next()?;
+        let best_score = -best.penalty;
+        best.mapq = match mappings.next() {
+            _ if best_score <= 0 => 0,
+            None => MAX_MAPQ,
+            Some(second) => {
+                let second_score = (-second.penalty).max(0);
+                ((best_score - second_score) as f64 / best_score as f64 * MAX_MAPQ as f64).round()
+                    as u8
+            }
+        };
+
+        Some(best)
+    }
+
+    fn seeds(&self, read: &AStr<DnaNt>) -> Vec<Seed> {
+        let mut seeds = Vec::new();
+        let mut add_seeds = |read_pos: usize, len: usize| {
+            let range = self.bwt.suffix_range(&read[read_pos..read_pos + len]);
+            if range.len() <= self.props.max_seed_occurrences {
+                seeds.extend(range.map(|idx| Seed {
+                    read_pos,
+                    ref_pos: self.bwt.locate(idx),
+                    len,
+                }));
+            }
+        };
+
+        match self.props.seed {
+            SeedMode::Kmer { k, step } => {
+                if read.len() >= k {
+                    let last = read.len() - k;
+                    for read_pos in (0..=last)
+                        .step_by(step)
+                        .chain((!last.is_multiple_of(step)).then_some(last))
+                    {
+                        add_seeds(read_pos, k);
+                    }
+                }
+            }
+            SeedMode::Mem { min_len } => {
+                // The longest match ending at `end` starts at or before the longest
+                // match ending at `end + 1`. It is maximal if it starts strictly before
+                let mut next_start = usize::MAX;
+                for end in (1..=read.len()).rev() {
+                    let mut range = self.bwt.full_range();
+                    let mut start = end;
+                    while start > 0 {
+                        let extended = self.bwt.extend_backward(range.clone(), read[start - 1]);
+                        if extended.is_empty() {
+                            break;
+                        }
+                        range = extended;
+                        start -= 1;
+                    }
+                    if start < next_start && end - start >= min_len {
+                        add_seeds(start, end - start);
+                    }
+                    next_start = start;
+                }
+            }
+        }
+
+        seeds
+    }
+
+    /// Best chain ending in each seed, keeping only the best chain for each first seed
+    fn chains(&self, read: &AStr<DnaNt>, strand: Strand) -> Vec<Chain> {
+        let seeds = self
+            .seeds(read)
+            .into_iter()
+            .sorted_by_key(|seed| (seed.ref_pos, seed.read_pos))
+            .collect_vec();
+        let max_diagonal_gap = self.props.band as isize;
+
+        // Score and first seed of best chain ending in each seed
+        let mut best: Vec<(usize, usize)> = Vec::with_capacity(seeds.len());
+        let mut diagonals: Vec<(isize, isize)> = Vec::with_capacity(seeds.len());
+        for (i, seed) in seeds.iter().enumerate() {
+            let mut entry = (seed.len, i);
+            let mut diagonal_range = (seed.diagonal(), seed.diagonal());
+            for (j, prev) in seeds[..i].iter().enumerate() {
+                if prev.read_pos < seed.read_pos
+                    && prev.ref_pos < seed.ref_pos
+                    && (seed.diagonal() - prev.diagonal()).abs() <= max_diagonal_gap
+                {
+                    let added = (seed.read_pos + seed.len)
+                        .saturating_sub(prev.read_pos + prev.len)
+                        .min(seed.len);
+                    if best[j].0 + added > entry.0 {
+                        entry = (best[j].0 + added, best[j].1);
+                        diagonal_range = (
+                            diagonals[j].0.min(seed.diagonal()),
+                            diagonals[j].1.max(seed.diagonal()),
+                        );
+                    }
+                }
+            }
+            best.push(entry);
+            diagonals.push(diagonal_range);
+        }
+
+        best.iter()
+            .zip(diagonals)
+            .enumerate()
+            .sorted_by_key(|(_, ((score, first), _))| (*first, usize::MAX - score))
+            .dedup_by(|(_, ((_, first1), _)), (_, ((_, first2), _))| first1 == first2)
+            .map(|(_, (&(score, _), (min_diagonal, max_diagonal)))| Chain {
+                strand,
+                score,
+                min_diagonal,
+                max_diagonal,
+            })
+            .collect()
+    }
+
+    fn extend(&self, read: &AStr<DnaNt>, chain: &Chain) -> Option<ReadMapping> {
+        let band = self.props.band as isize;
+        let window_start = (chain.min_diagonal - band).max(0) as usize;
+        let window_end = ((chain.max_diagonal + read.len() as isize + band).max(0) as usize)
+            .min(self.reference.len());
+        if window_start >= window_end {
+            return None;
+        }
+        let offset = window_start as isize;
+
+        let align = local_alignment_banded(
+            &self.reference[window_start..window_end],
+            read,
+            self.props.alignment,
+            chain.min_diagonal - offset - band..=chain.max_diagonal - offset + band,
+        )?;
+
+        Some(ReadMapping {
+            position: window_start + align.range.start,
+            strand: chain.strand,
+            edits: align.edits,
+            penalty: align.penalty,
+            mapq: 0,
+        })
+    }
+}
+
+/// SAM record for read with the given mapping. Quality is in phred scores
+pub fn sam_record(
+    reference_name: &str,
+    read_name: &str,
+    read: &AStr<DnaNt>,
+    quality: Option<&[u8]>,
+    mapping: Option<&ReadMapping>,
+) -> SamRecord {
+    let Some(mapping) = mapping else {
+        return SamRecord {
+            qual: quality.map(|quality| quality.to_vec()),
+            ..SamRecord::unmapped(read_name, read)
+        };
+    };
+
+    let (flag, seq, qual) = match mapping.strand {
+        Strand::Forward => (0, read.to_owned(), quality.map(|quality| quality.to_vec())),
+        Strand::Reverse => (
+            FLAG_REVERSE,
+            reverse_complement(read),
+            quality.map(|quality| quality.iter().rev().copied().collect()),
+        ),
+    };
+
+    SamRecord {
+        qname: read_name.to_string(),
+        flag,
+        rname: Some(reference_name.to_string()),
+        pos: mapping.position + 1,
+        mapq: mapping.mapq,
+        cigar: Some(cigar(&mapping.edits)),
+        seq: Some(seq.to_string()),
+        qual,
+        tags: vec![
+            SamTag::nm(edit_distance(&mapping.edits)),
+            SamTag::alignment_score(-mapping.penalty),
+        ],
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::alphabet_model::CharT;
+    use crate::string_model::test_util::random_dna;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn test_map_reads(props: MapperProperties) {
+        let mut rng = StdRng::seed_from_u64(7);
+        let reference = random_dna(&mut rng, 3000);
+        let mapper = Mapper::new(reference.clone(), props);
+
+        for _ in 0..30 {
+            let start = rng.random_range(0..reference.len() - 100);
+            let mut read = reference[start..start + 100].to_vec();
+            let mismatch = rng.random_range(0..read.len());
+            read[mismatch] = DnaNt::from_index((read[mismatch].index() + 1) % 4);
+            let mut read = AString::from_iter(read);
+            let strand = if rng.random_bool(0.5) {
+                read = reverse_complement(&read);
+                Strand::Reverse
+            } else {
+                Strand::Forward
+            };
+
+            let mapping = mapper.map(&read).unwrap();
+            assert_eq!(mapping.position, start);
+            assert_eq!(mapping.strand, strand);
+            assert!(mapping.penalty <= -98);
+            assert_eq!(mapping.mapq, MAX_MAPQ);
+        }
+
+        assert_eq!(mapper.map(&random_dna(&mut rng, 100)), None);
+    }
+
+    #[test]
+    fn test_map_reads_kmer_seeds() {
+        test_map_reads(MapperProperties::default());
+    }
+
+    #[test]
+    fn test_map_reads_mem_seeds() {
+        test_map_reads(MapperProperties::default().seed(SeedMode::Mem { min_len: 16 }));
+    }
+
+    #[test]
+    fn test_map_read_with_indel() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let reference = random_dna(&mut rng, 1000);
+        let mapper = Mapper::new(reference.clone(), MapperProperties::default());
+
+        let mut read = reference[200..300].to_vec();
+        read.remove(50);
+        let read = AString::from_iter(read);
+        let mapping = mapper.map(&read).unwrap();
+        assert_eq!(mapping.position, 200);
+        assert_eq!(mapping.strand, Strand::Forward);
+        assert_eq!(edit_distance(&mapping.edits), 1);
+    }
+
+    #[test]
+    fn test_sam_record() {
+        let read = AStr::from_slice(&[DnaNt::A, DnaNt::C, DnaNt::G]);
+        let mapping = ReadMapping {
+            position: 4,
+            strand: Strand::Reverse,
+            edits: AString::from_iter([Edit::Match, Edit::Mismatch, Edit::Match]),
+            penalty: -1,
+            mapq: 60,
+        };
+        assert_eq!(
+            sam_record("ref", "r1", read, Some(&[10, 20, 30]), Some(&mapping)).to_string(),
+            "r1\t16\tref\t5\t60\t3M\t*\t0\t0\tCGT\t?5+\tNM:i:1\tAS:i:1"
+        );
+        assert_eq!(
+            sam_record("ref", "r2", read, None, None).to_string(),
+            "r2\t4\t*\t0\t0\t*\t*\t0\t0\tACG\t*"
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! Repetitive structure of strings: maximal palindromes (Manacher), reverse complement
+//! palindromes of DNA, and runs, i.e. maximal periodic substrings, from which tandem repeats
+//! and squares are read off
+
+use crate::alphabet_model::CharT;
+use crate::polymers::DnaNt;
+use crate::string::suffix_array::build_array;
+use crate::string_model::{AStr, AString};
+use alloc::borrow::Cow;
+use hashbrown::HashMap;
+use std::ops::RangeInclusive;
+
+/// Palindrome at `s[start..end]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Palindrome {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Palindrome {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Radii of the maximal even palindromes: `radii[i]` is the largest `r` such that
+/// `s[i - r..i + r]` is a palindrome, where chars mirror each other if `mirrors` holds.
+/// `mirrors` must be symmetric
+fn even_radii<C: CharT>(s: &AStr<C>, mirrors: impl Fn(C, C) -> bool) -> Vec<usize> {
+    let n = s.len();
+    let mut radii = vec![0; n + 1];
+    // s[left..right] is the palindrome with the rightmost end found so far
+    let (mut left, mut right) = (0, 0);
+    for i in 0..=n {
+        let mut radius = if i < right {
+            radii[left + right - i].min(right - i)
+        } else {
+            0
+        };
+        while i + radius < n && i > radius && mirrors(s[i - radius - 1], s[i + radius]) {
+            radius += 1;
+        }
+        radii[i] = radius;
+        if i + radius > right {
+            (left, right) = (i - radius, i + radius);
+        }
+    }
+    radii
+}
+
+/// Radii of the maximal odd palindromes: `radii[i]` is the largest `r` such that
+/// `s[i - r..=i + r]` is a palindrome
+fn odd_radii<C: CharT>(s: &AStr<C>) -> Vec<usize> {
+    let n = s.len();
+    let mut radii = vec![0; n];
+    // s[left..right] is the palindrome with the rightmost end found so far
+    let (mut left, mut right) = (0, 0);
+    for i in 0..n {
+        let mut radius = if i + 1 < right {
+            radii[left + right - 1 - i].min(right - 1 - i)
+        } else {
+            0
+        };
+        while i + radius + 1 < n && i > radius && s[i - radius - 1] == s[i + radius + 1] {
+            radius += 1;
+        }
+        radii[i] = radius;
+        if i + radius + 1 > right {
+            (left, right) = (i - radius, i + radius + 1);
+        }
+    }
+    radii
+}
+
+/// The longest palindrome around each center of `s`, of length at least `min_len`, ordered by
+/// center
+pub fn maximal_palindromes<C: CharT>(s: &AStr<C>, min_len: usize) -> Vec<Palindrome> {
+    let even = even_radii(s, |ch1, ch2| ch1 == ch2);
+    let odd = odd_radii(s);
+    let mut palindromes = Vec::new();
+    for i in 0..s.len() {
+        palindromes.push(Palindrome {
+            start: i - even[i],
+            end: i + even[i],
+        });
+        palindromes.push(Palindrome {
+            start: i - odd[i],
+            end: i + odd[i] + 1,
+        });
+    }
+    palindromes.retain(|palindrome| palindrome.len() >= min_len.max(1));
+    palindromes
+}
+
+/// The longest reverse complement palindrome around each center of `dna`, of length at
+/// least `min_len`, ordered by center. Reverse complement palindromes, like restriction
+/// sites, are equal to their reverse complement and hence have even length
+pub fn reverse_complement_palindromes(dna: &AStr<DnaNt>, min_len: usize) -> Vec<Palindrome> {
+    even_radii(dna, |nt1, nt2| nt1 == nt2.bonding_complement())
+        .into_iter()
+        .enumerate()
+        .map(|(i, radius)| Palindrome {
+            start: i - radius,
+            end: i + radius,
+        })
+        .filter(|palindrome| palindrome.len() >= min_len.max(1))
+        .collect()
+}
+
+/// Run at `s[start..end]`: a maximal substring with smallest period `period` that is at least
+/// two periods long
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Run {
+    pub start: usize,
+    pub end: usize,
+    pub period: usize,
+}
+
+impl Run {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Number of copies of the repeat unit, including a partial last copy
+    pub fn copies(&self) -> f64 {
+        self.len() as f64 / self.period as f64
+    }
+
+    /// Repeat unit, the first period of the run
+    pub fn unit<'s, C: CharT>(&self, s: &'s AStr<C>) -> &'s AStr<C> {
+        &s[self.start..self.start + self.period]
+    }
+
+    /// Start positions of the squares `uu` with `|u|` the period of the run
+    pub fn squares(&self) -> RangeInclusive<usize> {
+        self.start..=self.end - 2 * self.period
+    }
+}
+
+/// Longest common extension queries from a suffix array with LCP array and a sparse table
+/// for range minimum queries
+struct Lce {
+    len: usize,
+    rank: Vec<usize>,
+    /// `sparse[j][i]` is the minimum of the LCP array over `i..i + 2^j`
+    sparse: Vec<Vec<usize>>,
+}
+
+impl Lce {
+    fn new<C: CharT>(s: &AStr<C>) -> Self {
+        let array = build_array(Cow::Borrowed(s));
+        let mut rank = vec![0; s.len()];
+        for (i, &suffix) in array.sorted_suffixes().iter().enumerate() {
+            rank[suffix] = i;
+        }
+        let mut sparse = vec![array.lcp_array()];
+        let mut width = 1;
+        while 2 * width <= s.len() {
+            let prev = sparse.last().unwrap();
+            let level = (0..=s.len() - 2 * width)
+                .map(|i| prev[i].min(prev[i + width]))
+                .collect();
+            sparse.push(level);
+            width *= 2;
+        }
+        Self {
+            len: s.len(),
+            rank,
+            sparse,
+        }
+    }
+
+    /// Length of the longest common prefix of the suffixes at `i` and `j`
+    fn lce(&self, i: usize, j: usize) -> usize {
+        if i >= self.len || j >= self.len {
+            return 0;
+        }
+        if i == j {
+            return self.len - i;
+        }
+        let (low, high) = if self.rank[i] < self.rank[j] {
+            (self.rank[i] + 1, self.rank[j])
+        } else {
+            (self.rank[j] + 1, self.rank[i])
+        };
+        let level = (high - low + 1).ilog2() as usize;
+        self.sparse[level][low].min(self.sparse[level][high + 1 - (1 << level)])
+    }
+}
+
+/// All runs of `s` ordered by start and end. For each period `p`, a run of period `p`
+/// contains two positions `i` and `i + p` with `i` a multiple of `p`, and the run is found by
+/// extending the common prefix and suffix at `i` and `i + p`. With constant time extensions
+/// on the suffix arrays of `s` and its reverse, this is `O(n log n)`
+pub fn runs<C: CharT>(s: &AStr<C>) -> Vec<Run> {
+    let n = s.len();
+    if n < 2 {
+        return Vec::new();
+    }
+    let forward = Lce::new(s);
+    let reversed: AString<C> = s.iter().rev().copied().collect();
+    let backward = Lce::new(&reversed);
+
+    // smallest period of each periodic substring, the same substring is found for each
+    // multiple of its smallest period
+    let mut periods: HashMap<(usize, usize), usize> = HashMap::new();
+    for period in 1..=n / 2 {
+        for i in (0..=n - period).step_by(period) {
+            // common suffix of s[..i] and s[..i + period]
+            let left = if i == 0 {
+                0
+            } else {
+                backward.lce(n - i, n - i - period)
+            };
+            // the run is found from the first multiple of the period inside it
+            if left >= period {
+                continue;
+            }
+            let right = forward.lce(i, i + period);
+            if left + right >= period {
+                let key = (i - left, i + period + right);
+                let smallest = periods.entry(key).or_insert(period);
+                *smallest = period.min(*smallest);
+            }
+        }
+    }
+
+    let mut runs = periods
+        .into_iter()
+        .map(|((start, end), period)| Run { start, end, period })
+        .collect::<Vec<_>>();
+    runs.sort_by_key(|run| (run.start, run.end));
+    runs
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TandemRepeatProperties {
+    pub min_period: usize,
+    pub max_period: usize,
+    pub min_copies: f64,
+    pub min_len: usize,
+}
+
+impl TandemRepeatProperties {
+    pub fn min_period(mut self, min_period: usize) -> Self {
+        self.min_period = min_period;
+        self
+    }
+
+    pub fn max_period(mut self, max_period: usize) -> Self {
+        self.max_period = max_period;
+        self
+    }
+
+    pub fn min_copies(mut self, min_copies: f64) -> Self {
+        self.min_copies = min_copies;
+        self
+    }
+
+    pub fn min_len(mut self, min_len: usize) -> Self {
+        self.min_len = min_len;
+        self
+    }
+}
+
+impl Default for TandemRepeatProperties {
+    /// Microsatellites
+    fn default() -> Self {
+        Self {
+            min_period: 1,
+            max_period: 6,
+            min_copies: 3.0,
+            min_len: 12,
+        }
+    }
+}
+
+/// Exact tandem repeats of `s`: the runs with period, copies and length within the limits
+pub fn tandem_repeats<C: CharT>(s: &AStr<C>, props: TandemRepeatProperties) -> Vec<Run> {
+    runs(s)
+        .into_iter()
+        .filter(|run| {
+            (props.min_period..=props.max_period).contains(&run.period)
+                && run.copies() >= props.min_copies
+                && run.len() >= props.min_len
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use itertools::Itertools;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+    use std::str::FromStr;
+
+    fn dna(s: &str) -> AString<DnaNt> {
+        s.parse().unwrap()
+    }
+
+    fn is_palindrome<C: CharT>(s: &AStr<C>) -> bool {
+        s.iter().eq(s.iter().rev())
+    }
+
+    fn smallest_period<C: CharT>(s: &AStr<C>) -> usize {
+        (1..=s.len())
+            .find(|&period| (period..s.len()).all(|i| s[i] == s[i - period]))
+            .unwrap()
+    }
+
+    fn naive_runs<C: CharT>(s: &AStr<C>) -> Vec<Run> {
+        let n = s.len();
+        let mut runs = Vec::new();
+        for start in 0..n {
+            for end in start + 1..=n {
+                let period = smallest_period(&s[start..end]);
+                let extends_left = start > 0 && s[start - 1] == s[start - 1 + period];
+                let extends_right = end < n && s[end] == s[end - period];
+                if end - start >= 2 * period && !extends_left && !extends_right {
+                    runs.push(Run { start, end, period });
+                }
+            }
+        }
+        runs
+    }
+
+    #[test]
+    fn test_maximal_palindromes() {
+        let s = AString::<Char>::from_str("ABAABBA").unwrap();
+        let palindromes = maximal_palindromes(&s, 3)
+            .into_iter()
+            .map(|palindrome| &s[palindrome.start..palindrome.end])
+            .collect_vec();
+        assert_eq!(
+            palindromes,
+            vec![
+                AStr::from_slice(&[Char::A, Char::B, Char::A]),
+                AStr::from_slice(&[Char::B, Char::A, Char::A, Char::B]),
+                AStr::from_slice(&[Char::A, Char::B, Char::B, Char::A]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reverse_complement_palindromes() {
+        // EcoRI site GAATTC
+        let s = dna("TCGAATTCAA");
+        let palindromes = reverse_complement_palindromes(&s, 4);
+        assert_eq!(
+            palindromes,
+            vec![
+                Palindrome { start: 0, end: 4 },
+                Palindrome { start: 2, end: 8 }
+            ]
+        );
+        assert_eq!(&s[2..8], dna("GAATTC").as_str());
+    }
+
+    #[test]
+    fn test_runs() {
+        let s = dna("GCACACACAGTTTG");
+        assert_eq!(
+            runs(&s),
+            vec![
+                Run {
+                    start: 1,
+                    end: 9,
+                    period: 2
+                },
+                Run {
+                    start: 10,
+                    end: 13,
+                    period: 1
+                },
+            ]
+        );
+        let run = runs(&s)[0];
+        assert_eq!(run.copies(), 4.0);
+        assert_eq!(run.unit(&s), dna("CA").as_str());
+        assert_eq!(run.squares(), 1..=5);
+    }
+
+    #[test]
+    fn test_tandem_repeats() {
+        let s = dna("GGCTATCTATCTATCTACGGTTT");
+        let repeats = tandem_repeats(&s, TandemRepeatProperties::default().min_len(8));
+        assert_eq!(
+            repeats,
+            vec![Run {
+                start: 2,
+                end: 17,
+                period: 4
+            }]
+        );
+        assert_eq!(repeats[0].copies(), 3.75);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn prop_test_maximal_palindromes(s in arb_astring::<Char>(0..30)) {
+            let expected = (0..2 * s.len())
+                .filter_map(|center| {
+                    // center between chars for even values, on a char for odd values
+                    let (mut start, mut end) = (center / 2, center.div_ceil(2));
+                    while start > 0 && end < s.len() && s[start - 1] == s[end] {
+                        start -= 1;
+                        end += 1;
+                    }
+                    (end > start).then_some(Palindrome { start, end })
+                })
+                .collect_vec();
+            let palindromes = maximal_palindromes(&s, 1);
+            for palindrome in &palindromes {
+                prop_assert_eq!(is_palindrome(&s[palindrome.start..palindrome.end]), true);
+            }
+            prop_assert_eq!(palindromes, expected);
+        }
+
+        #[test]
+        fn prop_test_runs(s in arb_astring::<Char>(0..25)) {
+            prop_assert_eq!(runs(&s), naive_runs(&s));
+        }
+    }
+}
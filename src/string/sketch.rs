@@ -0,0 +1,508 @@
+//! Sequence sketches from hashed k-mers: (w,k)-minimizers, open and closed syncmers, and bottom-k
+//! MinHash sketches with Jaccard and Mash distance estimates. K-mers are ordered by an
+//! invertible mix of their packed encoding, so distinct k-mers have distinct hashes
+
+use crate::alphabet_model::CharT;
+use crate::polymers::DnaNt;
+use crate::string::kmer::{dna_complement, packed_kmers};
+use crate::string_model::AStr;
+use itertools::Itertools;
+use std::collections::{BTreeSet, VecDeque};
+
+/// Bijective mix of the packed k-mer (the splitmix64 finalizer)
+fn hash64(code: u64) -> u64 {
+    let mut hash = code;
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+fn kmer_hashes<C: CharT>(
+    seq: &AStr<C>,
+    k: usize,
+    complement: Option<fn(usize) -> usize>,
+) -> Vec<u64> {
+    packed_kmers(seq, k, complement).map(hash64).collect()
+}
+
+/// Position of the leftmost smallest hash in each window of `w` consecutive hashes
+fn window_minimums(hashes: &[u64], w: usize) -> impl Iterator<Item = usize> + '_ {
+    assert!(w >= 1, "window must be at least 1");
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    hashes.iter().enumerate().filter_map(move |(pos, &hash)| {
+        while queue.back().is_some_and(|&back| hashes[back] > hash) {
+            queue.pop_back();
+        }
+        queue.push_back(pos);
+        let window_start = (pos + 1).checked_sub(w)?;
+        while queue[0] < window_start {
+            queue.pop_front();
+        }
+        Some(queue[0])
+    })
+}
+
+/// K-mer selected by a sketch
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KmerHash {
+    /// Start of the k-mer in the sequence
+    pub pos: usize,
+    pub hash: u64,
+}
+
+fn minimizers_impl<C: CharT>(
+    seq: &AStr<C>,
+    k: usize,
+    w: usize,
+    complement: Option<fn(usize) -> usize>,
+) -> Vec<KmerHash> {
+    let hashes = kmer_hashes(seq, k, complement);
+    window_minimums(&hashes, w)
+        .dedup()
+        .map(|pos| KmerHash {
+            pos,
+            hash: hashes[pos],
+        })
+        .collect()
+}
+
+/// (w,k)-minimizers: the k-mer with the smallest hash in each window of `w` consecutive
+/// k-mers, the leftmost one if several. Windows with the same minimizer give a single entry.
+/// Sequences with fewer than `w` k-mers have no minimizers
+pub fn minimizers<C: CharT>(seq: &AStr<C>, k: usize, w: usize) -> Vec<KmerHash> {
+    minimizers_impl(seq, k, w, None)
+}
+
+/// (w,k)-minimizers of canonical k-mers, selecting the same k-mers from both strands
+pub fn canonical_minimizers(seq: &AStr<DnaNt>, k: usize, w: usize) -> Vec<KmerHash> {
+    minimizers_impl(seq, k, w, Some(dna_complement))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SyncmerKind {
+    /// The smallest s-mer of the k-mer is at the given offset
+    Open { offset: usize },
+    /// The smallest s-mer of the k-mer is first or last
+    Closed,
+}
+
+fn syncmers_impl<C: CharT>(
+    seq: &AStr<C>,
+    k: usize,
+    s: usize,
+    kind: SyncmerKind,
+    complement: Option<fn(usize) -> usize>,
+) -> Vec<KmerHash> {
+    assert!(s <= k, "s-mers must be at most k long");
+    let last_offset = k - s;
+    if let SyncmerKind::Open { offset } = kind {
+        assert!(offset <= last_offset, "offset must be at most k - s");
+    }
+    let smer_hashes = kmer_hashes(seq, s, complement);
+    let kmer_hashes = kmer_hashes(seq, k, complement);
+    window_minimums(&smer_hashes, last_offset + 1)
+        .enumerate()
+        .filter(|&(pos, min_pos)| match kind {
+            SyncmerKind::Open { offset } => min_pos - pos == offset,
+            SyncmerKind::Closed => min_pos - pos == 0 || min_pos - pos == last_offset,
+        })
+        .map(|(pos, _)| KmerHash {
+            pos,
+            hash: kmer_hashes[pos],
+        })
+        .collect()
+}
+
+/// Syncmers: k-mers selected by the position of their smallest s-mer, the leftmost one if
+/// several. Unlike minimizers, whether a k-mer is selected doesn't depend on its neighbours
+pub fn syncmers<C: CharT>(seq: &AStr<C>, k: usize, s: usize, kind: SyncmerKind) -> Vec<KmerHash> {
+    syncmers_impl(seq, k, s, kind, None)
+}
+
+/// Syncmers selected by canonical s-mers
+pub fn canonical_syncmers(
+    seq: &AStr<DnaNt>,
+    k: usize,
+    s: usize,
+    kind: SyncmerKind,
+) -> Vec<KmerHash> {
+    syncmers_impl(seq, k, s, kind, Some(dna_complement))
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MinHashProperties {
+    pub k: usize,
+    /// Number of smallest hashes kept
+    pub size: usize,
+}
+
+impl MinHashProperties {
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl Default for MinHashProperties {
+    fn default() -> Self {
+        Self { k: 21, size: 1000 }
+    }
+}
+
+/// Bottom-k MinHash sketch: the smallest hashes of the distinct k-mers of a set of sequences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinHashSketch {
+    k: usize,
+    size: usize,
+    /// Distinct hashes in increasing order
+    hashes: Vec<u64>,
+}
+
+impl MinHashSketch {
+    fn new<C: CharT, S: AsRef<AStr<C>>>(
+        seqs: &[S],
+        props: MinHashProperties,
+        complement: Option<fn(usize) -> usize>,
+    ) -> Self {
+        assert!(props.size >= 1, "sketch size must be at least 1");
+        let mut hashes = BTreeSet::new();
+        for seq in seqs {
+            for code in packed_kmers(seq.as_ref(), props.k, complement) {
+                let hash = hash64(code);
+                if hashes.len() < props.size || hash < *hashes.last().unwrap() {
+                    hashes.insert(hash);
+                    if hashes.len() > props.size {
+                        hashes.pop_last();
+                    }
+                }
+            }
+        }
+        Self {
+            k: props.k,
+            size: props.size,
+            hashes: hashes.into_iter().collect(),
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Whether the sequences have fewer distinct k-mers than the sketch size, so that the
+    /// sketch holds all of them
+    pub fn is_complete(&self) -> bool {
+        self.hashes.len() < self.size
+    }
+
+    /// Estimate of the Jaccard index of the k-mer sets: the fraction of the smallest hashes of
+    /// the union that are in both sketches. Exact if both sketches are complete. 0 if both are
+    /// empty
+    pub fn jaccard(&self, other: &MinHashSketch) -> f64 {
+        assert_eq!(self.k, other.k, "sketches must have the same k");
+        let union_size = if self.is_complete() && other.is_complete() {
+            usize::MAX
+        } else {
+            self.size.min(other.size)
+        };
+
+        let mut union = 0;
+        let mut shared = 0;
+        let (mut idx1, mut idx2) = (0, 0);
+        while union < union_size && (idx1 < self.hashes.len() || idx2 < other.hashes.len()) {
+            let hash1 = self.hashes.get(idx1).copied().unwrap_or(u64::MAX);
+            let hash2 = other.hashes.get(idx2).copied().unwrap_or(u64::MAX);
+            if idx1 < self.hashes.len() && idx2 < other.hashes.len() && hash1 == hash2 {
+                shared += 1;
+                idx1 += 1;
+                idx2 += 1;
+            } else if idx2 == other.hashes.len() || (idx1 < self.hashes.len() && hash1 < hash2) {
+                idx1 += 1;
+            } else {
+                idx2 += 1;
+            }
+            union += 1;
+        }
+
+        if union == 0 {
+            0.0
+        } else {
+            shared as f64 / union as f64
+        }
+    }
+
+    /// Mash distance, estimating the per base mutation rate between the sequences from the
+    /// Jaccard index. 1 if no k-mers are shared
+    pub fn mash_distance(&self, other: &MinHashSketch) -> f64 {
+        let jaccard = self.jaccard(other);
+        if jaccard == 0.0 {
+            return 1.0;
+        }
+        (-(2.0 * jaccard / (1.0 + jaccard)).ln() / self.k as f64).min(1.0)
+    }
+}
+
+/// MinHash sketch of the k-mers of the sequences
+pub fn min_hash_sketch<C: CharT, S: AsRef<AStr<C>>>(
+    seqs: &[S],
+    props: MinHashProperties,
+) -> MinHashSketch {
+    MinHashSketch::new(seqs, props, None)
+}
+
+/// MinHash sketch of the canonical k-mers of the sequences, for comparing sequences regardless
+/// of strand
+pub fn canonical_min_hash_sketch<S: AsRef<AStr<DnaNt>>>(
+    seqs: &[S],
+    props: MinHashProperties,
+) -> MinHashSketch {
+    MinHashSketch::new(seqs, props, Some(dna_complement))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembly::simulation::{ErrorProfile, add_errors};
+    use crate::polymers::reverse_complement;
+    use crate::string_model::test_util::Char;
+    use crate::string_model::test_util::random_dna;
+    use crate::string_model::{AString, arb_astring};
+    use hashbrown::HashSet;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::hash::Hash;
+
+    fn naive_hash<C: CharT>(kmer: &AStr<C>) -> u64 {
+        hash64(packed_kmers(kmer, kmer.len(), None).next().unwrap())
+    }
+
+    fn kmer_set<C: CharT + Hash>(seq: &AStr<C>, k: usize) -> HashSet<AString<C>> {
+        seq.windows(k)
+            .map(|kmer| AStr::from_slice(kmer).to_owned())
+            .collect()
+    }
+
+    fn exact_jaccard<C: CharT + Hash>(seq1: &AStr<C>, seq2: &AStr<C>, k: usize) -> f64 {
+        let set1 = kmer_set(seq1, k);
+        let set2 = kmer_set(seq2, k);
+        let union = set1.union(&set2).count();
+        if union == 0 {
+            0.0
+        } else {
+            set1.intersection(&set2).count() as f64 / union as f64
+        }
+    }
+
+    #[test]
+    fn test_minimizers() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let seq = random_dna(&mut rng, 10000);
+        let (k, w) = (15, 10);
+        let mins = minimizers(&seq, k, w);
+
+        // every window has a minimizer, and density is about 2 / (w + 1)
+        assert!(
+            mins.iter()
+                .tuple_windows()
+                .all(|(min1, min2)| min1.pos < min2.pos && min2.pos - min1.pos <= w)
+        );
+        assert!(mins[0].pos < w);
+        assert!(mins.last().unwrap().pos >= seq.len() - k + 1 - w);
+        let density = mins.len() as f64 / (seq.len() - k + 1) as f64;
+        assert!((density - 2.0 / (w + 1) as f64).abs() < 0.02, "{}", density);
+
+        // canonical minimizers are the same on both strands
+        let rc = reverse_complement(&seq);
+        let mins = canonical_minimizers(&seq, k, w);
+        let mut rc_mins = canonical_minimizers(&rc, k, w)
+            .into_iter()
+            .map(|min| KmerHash {
+                pos: seq.len() - k - min.pos,
+                hash: min.hash,
+            })
+            .collect_vec();
+        rc_mins.reverse();
+        let common = mins.iter().filter(|min| rc_mins.contains(min)).count();
+        assert!(
+            common as f64 > 0.95 * mins.len() as f64,
+            "{} {}",
+            common,
+            mins.len()
+        );
+
+        assert!(minimizers(&seq[..k + w - 2], k, w).is_empty());
+        assert_eq!(minimizers(&seq[..k + w - 1], k, w).len(), 1);
+    }
+
+    #[test]
+    fn test_syncmers() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let seq = random_dna(&mut rng, 20000);
+        let (k, s) = (15, 5);
+        let kmers = (seq.len() - k + 1) as f64;
+
+        // density of closed syncmers is about 2 / (k - s + 1), of open syncmers 1 / (k - s + 1)
+        let closed = syncmers(&seq, k, s, SyncmerKind::Closed);
+        let density = closed.len() as f64 / kmers;
+        assert!((density - 2.0 / 11.0).abs() < 0.02, "{}", density);
+        let open = syncmers(&seq, k, s, SyncmerKind::Open { offset: 2 });
+        let density = open.len() as f64 / kmers;
+        assert!((density - 1.0 / 11.0).abs() < 0.02, "{}", density);
+
+        // selection is context free, so a k-mer is selected in any sequence
+        for syncmer in closed.iter().take(20) {
+            let kmer = &seq[syncmer.pos..syncmer.pos + k];
+            assert_eq!(
+                syncmers(kmer, k, s, SyncmerKind::Closed),
+                vec![KmerHash {
+                    pos: 0,
+                    hash: syncmer.hash
+                }]
+            );
+        }
+
+        // closed canonical syncmers are the same on both strands, except when the smallest
+        // s-mer occurs more than once in the k-mer
+        let rc = reverse_complement(&seq);
+        let positions = canonical_syncmers(&seq, k, s, SyncmerKind::Closed)
+            .into_iter()
+            .map(|syncmer| syncmer.pos)
+            .collect_vec();
+        let rc_positions = canonical_syncmers(&rc, k, s, SyncmerKind::Closed)
+            .into_iter()
+            .map(|syncmer| seq.len() - k - syncmer.pos)
+            .collect::<HashSet<_>>();
+        let common = positions
+            .iter()
+            .filter(|&pos| rc_positions.contains(pos))
+            .count();
+        assert!(
+            common as f64 > 0.95 * positions.len() as f64,
+            "{} {}",
+            common,
+            positions.len()
+        );
+    }
+
+    #[test]
+    fn test_min_hash() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let genome1 = random_dna(&mut rng, 20000);
+        let genome2 = add_errors(
+            &genome1,
+            ErrorProfile::default().substitution_rate(0.01),
+            &mut rng,
+        );
+        let props = MinHashProperties::default();
+        let sketch1 = min_hash_sketch(&[&genome1], props);
+        let sketch2 = min_hash_sketch(&[&genome2], props);
+        assert_eq!(sketch1.hashes().len(), 1000);
+        assert!(!sketch1.is_complete());
+
+        let exact = exact_jaccard(&genome1, &genome2, 21);
+        let estimate = sketch1.jaccard(&sketch2);
+        assert!((estimate - exact).abs() < 0.05, "{} {}", estimate, exact);
+        let distance = sketch1.mash_distance(&sketch2);
+        assert!((distance - 0.01).abs() < 0.003, "{}", distance);
+        assert_eq!(sketch1.jaccard(&sketch1), 1.0);
+        assert_eq!(sketch1.mash_distance(&sketch1), 0.0);
+
+        // canonical sketches don't depend on strand
+        let sketch1 = canonical_min_hash_sketch(&[&genome1], props);
+        let sketch_rc = canonical_min_hash_sketch(&[reverse_complement(&genome1)], props);
+        assert_eq!(sketch1, sketch_rc);
+
+        let unrelated = min_hash_sketch(&[random_dna(&mut rng, 20000)], props);
+        assert_eq!(sketch2.jaccard(&unrelated), 0.0);
+        assert_eq!(sketch2.mash_distance(&unrelated), 1.0);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn prop_test_minimizers(seq in arb_astring::<Char>(0..60), k in 1..6usize, w in 1..6usize) {
+            let expected = seq
+                .windows(k)
+                .map(|kmer| naive_hash(AStr::from_slice(kmer)))
+                .collect_vec()
+                .windows(w)
+                .enumerate()
+                .map(|(start, window)| start + window.iter().position_min().unwrap())
+                .dedup()
+                .collect_vec();
+            let mins = minimizers(&seq, k, w);
+            prop_assert_eq!(mins.iter().map(|min| min.pos).collect_vec(), expected);
+            for min in mins {
+                prop_assert_eq!(min.hash, naive_hash(&seq[min.pos..min.pos + k]));
+            }
+        }
+
+        #[test]
+        fn prop_test_syncmers(seq in arb_astring::<Char>(0..60), k in 1..8usize, s in 1..8usize, offset in 0..8usize) {
+            let s = s.min(k);
+            let offset = offset.min(k - s);
+            let smallest_smer = |kmer: &[Char]| {
+                kmer.windows(s)
+                    .map(|smer| naive_hash(AStr::from_slice(smer)))
+                    .position_min()
+                    .unwrap()
+            };
+            let closed = seq
+                .windows(k)
+                .positions(|kmer| [0, k - s].contains(&smallest_smer(kmer)))
+                .collect_vec();
+            let open = seq
+                .windows(k)
+                .positions(|kmer| smallest_smer(kmer) == offset)
+                .collect_vec();
+            prop_assert_eq!(
+                syncmers(&seq, k, s, SyncmerKind::Closed).iter().map(|syncmer| syncmer.pos).collect_vec(),
+                closed
+            );
+            prop_assert_eq!(
+                syncmers(&seq, k, s, SyncmerKind::Open { offset }).iter().map(|syncmer| syncmer.pos).collect_vec(),
+                open
+            );
+        }
+
+        #[test]
+        fn prop_test_min_hash_jaccard(seq1 in arb_astring::<Char>(0..40), seq2 in arb_astring::<Char>(0..40), k in 1..6usize) {
+            // sketches large enough to hold all k-mers give the exact Jaccard index
+            let props = MinHashProperties::default().k(k).size(64);
+            let sketch1 = min_hash_sketch(&[&seq1], props);
+            let sketch2 = min_hash_sketch(&[&seq2], props);
+            let exact = exact_jaccard(&seq1, &seq2, k);
+            prop_assert_eq!(sketch1.jaccard(&sketch2), exact);
+            prop_assert_eq!(sketch2.jaccard(&sketch1), exact);
+
+            // small sketches estimate from the smallest hashes of the union
+            let props = props.size(4);
+            let sketch1 = min_hash_sketch(&[&seq1], props);
+            let sketch2 = min_hash_sketch(&[&seq2], props);
+            let union: HashSet<u64> = sketch1.hashes().iter().chain(sketch2.hashes()).copied().collect();
+            let bottom = union.into_iter().sorted().take(4).collect_vec();
+            let shared = bottom
+                .iter()
+                .filter(|hash| sketch1.hashes().contains(hash) && sketch2.hashes().contains(hash))
+                .count();
+            let estimate = sketch1.jaccard(&sketch2);
+            if sketch1.is_complete() && sketch2.is_complete() {
+                prop_assert_eq!(estimate, exact);
+            } else {
+                prop_assert_eq!(estimate, shared as f64 / bottom.len() as f64);
+            }
+            prop_assert!((0.0..=1.0).contains(&estimate));
+        }
+    }
+}
@@ -44,6 +44,40 @@ pub fn build_array<'s, C: CharT>(s: Cow<'s, AStr<C>>) -> SuffixArray<'s, C> {
     SuffixArray { sorted_suffixes, s }
 }
 
+impl<'s, C: CharT> SuffixArray<'s, C> {
+    /// Start positions of the suffixes in lexicographic order
+    pub fn sorted_suffixes(&self) -> &[usize] {
+        &self.sorted_suffixes
+    }
+
+    /// Length of the longest common prefix of each suffix and the previous suffix in
+    /// lexicographic order, 0 for the first suffix (Kasai et al.)
+    pub fn lcp_array(&self) -> Vec<usize> {
+        let n = self.sorted_suffixes.len();
+        let mut rank = vec![0; n];
+        for (i, &suffix) in self.sorted_suffixes.iter().enumerate() {
+            rank[suffix] = i;
+        }
+        let mut lcp = vec![0; n];
+        // the common prefix with the previous suffix shrinks by at most one from a suffix
+        // to the next shorter suffix
+        let mut len: usize = 0;
+        for suffix in 0..n {
+            if rank[suffix] == 0 {
+                len = 0;
+                continue;
+            }
+            let prev = self.sorted_suffixes[rank[suffix] - 1];
+            while suffix + len < n && prev + len < n && self.s[suffix + len] == self.s[prev + len] {
+                len += 1;
+            }
+            lcp[rank[suffix]] = len;
+            len = len.saturating_sub(1);
+        }
+        lcp
+    }
+}
+
 impl<'s, C: CharT + Ord> SuffixArray<'s, C> {
     fn ord_suffix(&self, i: usize) -> &AStr<C> {
         &self.s[self.sorted_suffixes[i]..]
@@ -168,9 +202,32 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_lcp_array() {
+        use crate::string_model::test_util::Char::*;
+
+        let s = AStr::from_slice(&[A, B, A, A, B, A]);
+        let array = build_array(Cow::Borrowed(s));
+
+        assert_eq!(array.sorted_suffixes(), &[5, 2, 3, 0, 4, 1]);
+        assert_eq!(array.lcp_array(), vec![0, 1, 1, 3, 0, 2]);
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(2000))]
 
+        #[test]
+        fn prop_test_lcp_array(s in arb_astring::<Char>(1..30)) {
+            let array = build_array(Cow::Borrowed(&s));
+            let lcp = array.lcp_array();
+            for i in 1..s.len() {
+                let prev = &s[array.sorted_suffixes()[i - 1]..];
+                let suffix = &s[array.sorted_suffixes()[i]..];
+                prop_assert!(prev < suffix);
+                prop_assert_eq!(lcp[i], prev.lcp(suffix).len());
+            }
+        }
+
         #[test]
         fn prop_test_trie_naive(s in arb_astring::<Char>(0..20), t in arb_astring::<Char>(3)) {
             let array = build_array(Cow::Borrowed(&s));
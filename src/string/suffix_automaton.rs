@@ -0,0 +1,325 @@
+//! Suffix automaton (DAWG), the smallest automaton accepting the suffixes of a string, built
+//! online in linear time (Blumer et al.). Each state is an equivalence class of substrings
+//! with the same end positions in the string
+
+use crate::alphabet_model::CharT;
+use crate::string_model::{AStr, AString};
+use generic_array::{ArrayLength, GenericArray};
+
+const ROOT: usize = 0;
+
+#[derive(Debug, Clone)]
+struct State<N: ArrayLength> {
+    /// Length of the longest substring of the class
+    len: usize,
+    /// State of the longest suffix in another class
+    link: Option<usize>,
+    /// End position of the first occurrence of the substrings of the class
+    first_end: usize,
+    /// Number of occurrences of the substrings of the class
+    count: usize,
+    /// Number of distinct non-empty strings spelled by paths from the state
+    paths: usize,
+    next: GenericArray<Option<usize>, N>,
+}
+
+impl<N: ArrayLength> State<N> {
+    fn new(len: usize, first_end: usize) -> Self {
+        Self {
+            len,
+            link: None,
+            first_end,
+            count: 0,
+            paths: 0,
+            next: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SuffixAutomaton<C: CharT> {
+    states: Vec<State<C::AlphabetSize>>,
+    last: usize,
+}
+
+/// Builds the suffix automaton of `s`
+pub fn build_suffix_automaton<C: CharT>(s: &AStr<C>) -> SuffixAutomaton<C> {
+    let mut automaton = SuffixAutomaton {
+        states: vec![State::new(0, 0)],
+        last: ROOT,
+    };
+    for (pos, &ch) in s.iter().enumerate() {
+        automaton.extend(ch, pos);
+    }
+    automaton.count_occurrences_and_paths();
+    automaton
+}
+
+impl<C: CharT> SuffixAutomaton<C> {
+    fn extend(&mut self, ch: C, pos: usize) {
+        let ch = ch.index();
+        let cur = self.states.len();
+        let mut cur_state = State::new(self.states[self.last].len + 1, pos);
+        cur_state.count = 1;
+        self.states.push(cur_state);
+
+        let mut p = Some(self.last);
+        while let Some(state) = p
+            && self.states[state].next[ch].is_none()
+        {
+            self.states[state].next[ch] = Some(cur);
+            p = self.states[state].link;
+        }
+
+        self.states[cur].link = Some(match p {
+            None => ROOT,
+            Some(state) => {
+                let q = self.states[state].next[ch].unwrap();
+                if self.states[state].len + 1 == self.states[q].len {
+                    q
+                } else {
+                    // split the class of q, the clone takes the substrings up to the new length
+                    let clone = self.states.len();
+                    let mut clone_state = self.states[q].clone();
+                    clone_state.len = self.states[state].len + 1;
+                    clone_state.count = 0;
+                    self.states.push(clone_state);
+                    while let Some(state) = p
+                        && self.states[state].next[ch] == Some(q)
+                    {
+                        self.states[state].next[ch] = Some(clone);
+                        p = self.states[state].link;
+                    }
+                    self.states[q].link = Some(clone);
+                    clone
+                }
+            }
+        });
+        self.last = cur;
+    }
+
+    /// Accumulates occurrence counts along suffix links and path counts along transitions,
+    /// both in order of decreasing length
+    fn count_occurrences_and_paths(&mut self) {
+        let mut by_len = vec![0; self.states[self.last].len + 2];
+        for state in &self.states {
+            by_len[state.len + 1] += 1;
+        }
+        for len in 1..by_len.len() {
+            by_len[len] += by_len[len - 1];
+        }
+        let mut order = vec![0; self.states.len()];
+        for (index, state) in self.states.iter().enumerate() {
+            order[by_len[state.len]] = index;
+            by_len[state.len] += 1;
+        }
+
+        for &index in order.iter().rev() {
+            if let Some(link) = self.states[index].link {
+                self.states[link].count += self.states[index].count;
+            }
+            self.states[index].paths = self.states[index]
+                .next
+                .iter()
+                .flatten()
+                .map(|&next| 1 + self.states[next].paths)
+                .sum();
+        }
+    }
+
+    /// State reached by reading `t` from the root
+    fn walk(&self, t: &AStr<C>) -> Option<usize> {
+        t.iter()
+            .try_fold(ROOT, |state, ch| self.states[state].next[ch.index()])
+    }
+
+    /// Whether `t` is a substring
+    pub fn contains(&self, t: &AStr<C>) -> bool {
+        self.walk(t).is_some()
+    }
+
+    /// Number of occurrences of `t`, including overlapping ones
+    pub fn count(&self, t: &AStr<C>) -> usize {
+        match self.walk(t) {
+            Some(ROOT) => self.states[self.last].len + 1,
+            Some(state) => self.states[state].count,
+            None => 0,
+        }
+    }
+
+    /// Start position of the first occurrence of `t`
+    pub fn first_index(&self, t: &AStr<C>) -> Option<usize> {
+        match self.walk(t)? {
+            ROOT => Some(0),
+            state => Some(self.states[state].first_end + 1 - t.len()),
+        }
+    }
+
+    /// Number of distinct non-empty substrings
+    pub fn distinct_substrings(&self) -> usize {
+        self.states[ROOT].paths
+    }
+
+    /// The `k`-th (from 0) distinct non-empty substring in lexicographic order of char
+    /// indexes
+    pub fn kth_substring(&self, mut k: usize) -> Option<AString<C>> {
+        if k >= self.distinct_substrings() {
+            return None;
+        }
+        let mut substring = Vec::new();
+        let mut state = ROOT;
+        loop {
+            for (ch_index, &next) in self.states[state].next.iter().enumerate() {
+                let Some(next) = next else {
+                    continue;
+                };
+                let paths = 1 + self.states[next].paths;
+                if k < paths {
+                    substring.push(C::from_index(ch_index));
+                    if k == 0 {
+                        return Some(AString::from(substring));
+                    }
+                    k -= 1;
+                    state = next;
+                    break;
+                }
+                k -= paths;
+            }
+        }
+    }
+
+    /// For each end position `j` in `t`, the length of the longest substring ending at `j`
+    /// (inclusive) that is a substring of the indexed string
+    pub fn longest_suffix_matches(&self, t: &AStr<C>) -> Vec<usize> {
+        let mut state = ROOT;
+        let mut len = 0;
+        t.iter()
+            .map(|ch| {
+                let ch = ch.index();
+                while state != ROOT && self.states[state].next[ch].is_none() {
+                    state = self.states[state].link.unwrap();
+                    len = self.states[state].len;
+                }
+                match self.states[state].next[ch] {
+                    Some(next) => {
+                        state = next;
+                        len += 1;
+                    }
+                    None => len = 0,
+                }
+                len
+            })
+            .collect()
+    }
+
+    /// Matching statistics of `t`: for each start position `i` in `t`, the length of the
+    /// longest prefix of `t[i..]` that is a substring of the indexed string
+    pub fn matching_statistics(&self, t: &AStr<C>) -> Vec<usize> {
+        let suffix_matches = self.longest_suffix_matches(t);
+        // the start of the longest match ending at j, j + 1 - suffix_matches[j], does not
+        // decrease with j, so the match from i is the one with the largest end starting at
+        // or before i
+        let mut end = 0;
+        (0..t.len())
+            .map(|i| {
+                while end < t.len() && end + 1 - suffix_matches[end] <= i {
+                    end += 1;
+                }
+                end.saturating_sub(i)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use hashbrown::HashSet;
+    use itertools::Itertools;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+
+    #[test]
+    fn test_queries() {
+        let automaton = build_suffix_automaton(ascii("abcbcab"));
+        assert!(automaton.contains(ascii("bcbc")));
+        assert!(!automaton.contains(ascii("cc")));
+        assert_eq!(automaton.count(ascii("bc")), 2);
+        assert_eq!(automaton.count(ascii("ab")), 2);
+        assert_eq!(automaton.count(ascii("")), 8);
+        assert_eq!(automaton.first_index(ascii("cab")), Some(4));
+        assert_eq!(automaton.first_index(ascii("b")), Some(1));
+        assert_eq!(automaton.first_index(ascii("ba")), None);
+    }
+
+    #[test]
+    fn test_distinct_substrings() {
+        let automaton = build_suffix_automaton(ascii("aaa"));
+        assert_eq!(automaton.distinct_substrings(), 3);
+        assert_eq!(automaton.kth_substring(2), Some(ascii("aaa").to_owned()));
+        assert_eq!(automaton.kth_substring(3), None);
+
+        let automaton = build_suffix_automaton(ascii("abab"));
+        let substrings = (0..automaton.distinct_substrings())
+            .map(|k| automaton.kth_substring(k).unwrap().to_string())
+            .collect_vec();
+        assert_eq!(substrings, vec!["a", "ab", "aba", "abab", "b", "ba", "bab"]);
+    }
+
+    #[test]
+    fn test_matching_statistics() {
+        let automaton = build_suffix_automaton(ascii("abcab"));
+        assert_eq!(
+            automaton.matching_statistics(ascii("cabxbca")),
+            vec![3, 2, 1, 0, 3, 2, 1]
+        );
+        assert_eq!(
+            automaton.longest_suffix_matches(ascii("cabxbca")),
+            vec![1, 2, 3, 0, 1, 2, 3]
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn prop_test_queries(s in arb_astring::<Char>(0..30), t in arb_astring::<Char>(0..5)) {
+            let automaton = build_suffix_automaton(&s);
+            let indexes = string::indexes(&s, &t);
+            prop_assert_eq!(automaton.contains(&t), !indexes.is_empty());
+            prop_assert_eq!(automaton.count(&t), indexes.len());
+            prop_assert_eq!(automaton.first_index(&t), indexes.first().copied());
+        }
+
+        #[test]
+        fn prop_test_substrings(s in arb_astring::<Char>(0..20)) {
+            let automaton = build_suffix_automaton(&s);
+            let substrings = (0..s.len())
+                .flat_map(|start| (start + 1..=s.len()).map(move |end| (start, end)))
+                .map(|(start, end)| s[start..end].to_vec())
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .sorted_by_key(|substring| substring.iter().map(|ch| ch.index()).collect_vec())
+                .map(AString::from)
+                .collect_vec();
+            prop_assert_eq!(automaton.distinct_substrings(), substrings.len());
+            let kth = (0..substrings.len())
+                .map(|k| automaton.kth_substring(k).unwrap())
+                .collect_vec();
+            prop_assert_eq!(kth, substrings);
+        }
+
+        #[test]
+        fn prop_test_matching_statistics(s in arb_astring::<Char>(0..20), t in arb_astring::<Char>(0..20)) {
+            let automaton = build_suffix_automaton(&s);
+            let expected = (0..t.len())
+                .map(|i| (0..=t.len() - i).rev().find(|&len| s.contains(&t[i..i + len])).unwrap())
+                .collect_vec();
+            prop_assert_eq!(automaton.matching_statistics(&t), expected);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! All-pairs suffix-prefix overlaps. Prefixes of length `min_overlap` are indexed by a rolling
+//! hash, and for each suffix of each string the strings with a matching prefix hash are
+//! verified as candidates
+
+use crate::alphabet_model::CharT;
+use crate::string_model::AStr;
+use hashbrown::HashMap;
+
+const HASH_BASE: u64 = 0x100000001b3;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Overlap {
+    /// Index of the string whose prefix overlaps the suffix of the source string
+    pub target: usize,
+    pub len: usize,
+}
+
+/// Rolling hash over windows of fixed length
+struct RollingHash {
+    window_len: usize,
+    /// `HASH_BASE` to the power `window_len`
+    base_pow: u64,
+}
+
+impl RollingHash {
+    fn new(window_len: usize) -> Self {
+        Self {
+            window_len,
+            base_pow: HASH_BASE.wrapping_pow(window_len as u32),
+        }
+    }
+
+    fn char_value<C: CharT>(ch: C) -> u64 {
+        ch.index() as u64 + 1
+    }
+
+    fn hash<C: CharT>(&self, s: &AStr<C>) -> u64 {
+        s[..self.window_len].iter().fold(0, |hash, &ch| {
+            hash.wrapping_mul(HASH_BASE)
+                .wrapping_add(Self::char_value(ch))
+        })
+    }
+
+    /// Hashes of all windows in `s`, first window first
+    fn window_hashes<'a, C: CharT>(&'a self, s: &'a AStr<C>) -> impl Iterator<Item = u64> + 'a {
+        let first = (s.len() >= self.window_len).then(|| self.hash(s));
+        first.into_iter().chain((self.window_len..s.len()).scan(
+            first.unwrap_or_default(),
+            move |hash, i| {
+                *hash = hash
+                    .wrapping_mul(HASH_BASE)
+                    .wrapping_add(Self::char_value(s[i]))
+                    .wrapping_sub(
+                        Self::char_value(s[i - self.window_len]).wrapping_mul(self.base_pow),
+                    );
+                Some(*hash)
+            },
+        ))
+    }
+}
+
+/// For each string, all overlaps of length at least `min_overlap` where a suffix of the string
+/// equals a prefix of another string. Overlaps of a string with itself are not included.
+/// Overlaps for each string are ordered by target and length
+pub fn all_overlaps<C: CharT, S: AsRef<AStr<C>>>(
+    strs: &[S],
+    min_overlap: usize,
+) -> Vec<Vec<Overlap>> {
+    let rolling_hash = RollingHash::new(min_overlap);
+
+    let mut prefix_index: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (idx, s) in strs.iter().enumerate() {
+        let s = s.as_ref();
+        if s.len() >= min_overlap {
+            prefix_index
+                .entry(rolling_hash.hash(s))
+                .or_default()
+                .push(idx);
+        }
+    }
+
+    strs.iter()
+        .enumerate()
+        .map(|(idx, s)| {
+            let s = s.as_ref();
+            let mut overlaps = Vec::new();
+            for (start, hash) in rolling_hash.window_hashes(s).enumerate() {
+                let suffix = &s[start..];
+                let Some(candidates) = prefix_index.get(&hash) else {
+                    continue;
+                };
+                for &target in candidates {
+                    let target_str = strs[target].as_ref();
+                    if target != idx
+                        && suffix.len() <= target_str.len()
+                        && *suffix == target_str[..suffix.len()]
+                    {
+                        overlaps.push(Overlap {
+                            target,
+                            len: suffix.len(),
+                        });
+                    }
+                }
+            }
+            overlaps.sort();
+            overlaps
+        })
+        .collect()
+}
+
+/// For each string, the longest overlap of length at least `min_overlap` with each other
+/// string, see [`all_overlaps`]
+pub fn longest_overlaps<C: CharT, S: AsRef<AStr<C>>>(
+    strs: &[S],
+    min_overlap: usize,
+) -> Vec<Vec<Overlap>> {
+    all_overlaps(strs, min_overlap)
+        .into_iter()
+        .map(|mut overlaps| {
+            overlaps.reverse();
+            overlaps.dedup_by_key(|overlap| overlap.target);
+            overlaps.reverse();
+            overlaps
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string;
+    use crate::string_model::test_util::Char;
+    use crate::string_model::{AString, arb_astring};
+    use proptest::collection::vec;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+
+    #[test]
+    fn test_all_overlaps() {
+        let strs = [ascii("abcab"), ascii("abcd"), ascii("cabx"), ascii("ab")];
+        assert_eq!(
+            all_overlaps(&strs, 2),
+            vec![
+                vec![
+                    Overlap { target: 1, len: 2 },
+                    Overlap { target: 2, len: 3 },
+                    Overlap { target: 3, len: 2 },
+                ],
+                vec![],
+                vec![],
+                vec![Overlap { target: 0, len: 2 }, Overlap { target: 1, len: 2 }],
+            ]
+        );
+        assert_eq!(
+            longest_overlaps(&[ascii("aaaa"), ascii("aaa")], 1),
+            vec![
+                vec![Overlap { target: 1, len: 3 }],
+                vec![Overlap { target: 0, len: 3 }]
+            ]
+        );
+        assert_eq!(
+            all_overlaps(&[ascii("abc"), ascii("abc")], 0)[0],
+            vec![Overlap { target: 1, len: 0 }, Overlap { target: 1, len: 3 }]
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn prop_test_longest_overlaps(
+            strs in vec(arb_astring::<Char>(0..8), 0..6),
+            min_overlap in 0..4usize)
+        {
+            let expected: Vec<Vec<Overlap>> = strs
+                .iter()
+                .enumerate()
+                .map(|(idx, s)| {
+                    strs.iter()
+                        .enumerate()
+                        .filter(|&(target, _)| target != idx)
+                        .filter_map(|(target, t)| {
+                            (min_overlap..=s.len().min(t.len()))
+                                .rev()
+                                .find(|&len| s[s.len() - len..] == t[..len])
+                                .map(|len| Overlap { target, len })
+                        })
+                        .collect()
+                })
+                .collect();
+            let strs: Vec<&AStr<Char>> = strs.iter().map(AString::as_str).collect();
+            let overlaps = longest_overlaps(&strs, min_overlap);
+            prop_assert_eq!(&overlaps, &expected);
+            for (idx, overlaps) in overlaps.iter().enumerate() {
+                for overlap in overlaps.iter().filter(|overlap| overlap.len > 0) {
+                    prop_assert_eq!(string::overlap(strs[idx], strs[overlap.target]), overlap.len);
+                }
+            }
+        }
+    }
+}
@@ -19,7 +19,7 @@ use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::ops::{Deref, DerefMut};
 use std::path::Path;
-use std::{mem, ptr};
+use std::{iter, mem, ptr};
 
 const GRAPH_DEBUG: bool = false;
 
@@ -33,6 +33,11 @@ impl<'arena, 's, C: CharT> SuffixTrie<'arena, 's, C> {
     pub fn root(&self) -> NodeReference<'arena, 's, C> {
         NodeReference(self.root)
     }
+
+    /// The string represented in the trie
+    pub fn string(&self) -> &'s AStr<C> {
+        self.s
+    }
 }
 
 #[derive(Debug)]
@@ -198,6 +203,11 @@ impl<'arena, 's, C: CharT + Copy> SuffixTrie<'arena, 's, C> {
         result
     }
 
+    /// Whether given string is a substring of the string represented in the trie
+    pub fn contains_substr(&self, t: &AStr<C>) -> bool {
+        matches!(scan_rec(self.root, t).matched, ScanMatch::FullMatch)
+    }
+
     /// Finds index of maximal prefixes of given string
     pub fn index_substr_maximal(&self, t: &AStr<C>) -> MaximalSubstrMatch {
         match scan_rec(self.root, t) {
@@ -218,6 +228,121 @@ impl<'arena, 's, C: CharT + Copy> SuffixTrie<'arena, 's, C> {
     }
 }
 
+impl<'arena, 's, C: CharT + Copy> SuffixTrie<'arena, 's, C> {
+    /// Matching statistics of each suffix `t[i..]` of `t` in order. The match for `t[i + 1..]`
+    /// is found from the match for `t[i..]` by following the suffix link of the node above its
+    /// end, so `t` is scanned once (Chang and Lawler)
+    pub fn matching_statistics<'t>(
+        &self,
+        t: &'t AStr<C>,
+    ) -> impl Iterator<Item = MatchingStatistic<'arena, 's, C>> + 't
+    where
+        'arena: 't,
+        's: 't,
+    {
+        let root = self.root;
+        // the match is the path to upper followed by the first edge_matched chars of edge
+        let mut upper = root;
+        let mut upper_depth = 0;
+        let mut edge: Option<&'arena RefCell<Edge<'arena, 's, C, C::AlphabetSize>>> = None;
+        let mut edge_matched = 0;
+        let mut len = 0;
+        let mut i = 0;
+        iter::from_fn(move || {
+            if i >= t.len() {
+                return None;
+            }
+
+            while i + len < t.len() {
+                let ch = t[i + len];
+                if let Some(current_edge) = edge {
+                    let edge_ref = current_edge.borrow();
+                    if edge_ref.chars[edge_matched] != ch {
+                        break;
+                    }
+                    edge_matched += 1;
+                    len += 1;
+                    if edge_matched == edge_ref.chars.len() {
+                        upper = edge_ref.target;
+                        upper_depth += edge_matched;
+                        edge = None;
+                        edge_matched = 0;
+                    }
+                } else {
+                    match upper.borrow().children[ch.index()] {
+                        Some(child_edge) => edge = Some(child_edge),
+                        None => break,
+                    }
+                }
+            }
+
+            let statistic = match edge {
+                Some(edge) => MatchingStatistic {
+                    length: len,
+                    locus: edge.borrow().target,
+                    locus_depth: upper_depth + edge.borrow().chars.len(),
+                },
+                None => MatchingStatistic {
+                    length: len,
+                    locus: upper,
+                    locus_depth: upper_depth,
+                },
+            };
+
+            if len > 0 {
+                // leaves, and the last head inserted, have no suffix link. The chars of the
+                // edges above them are rescanned from the nearest ancestor with a link
+                while upper.borrow().suffix.is_none() && !ptr::eq(upper, root) {
+                    let parent_edge = upper.borrow().parent.expect("non-root has parent");
+                    upper_depth -= parent_edge.borrow().chars.len();
+                    upper = parent_edge.borrow().source;
+                }
+                let suffix = upper.borrow().suffix;
+                let mut rescan = match suffix {
+                    Some(suffix) => {
+                        upper = suffix;
+                        upper_depth -= 1;
+                        &t[i + 1 + upper_depth..i + len]
+                    }
+                    // the root
+                    None => &t[i + 1..i + len],
+                };
+                edge = None;
+                edge_matched = 0;
+                while let Some(ch) = rescan.first() {
+                    let child_edge = upper.borrow().children[ch.index()].expect("must be in trie");
+                    let child_edge_len = child_edge.borrow().chars.len();
+                    if child_edge_len <= rescan.len() {
+                        upper = child_edge.borrow().target;
+                        upper_depth += child_edge_len;
+                        rescan = &rescan[child_edge_len..];
+                    } else {
+                        edge = Some(child_edge);
+                        edge_matched = rescan.len();
+                        rescan = AStr::empty();
+                    }
+                }
+                len -= 1;
+            }
+            i += 1;
+
+            Some(statistic)
+        })
+    }
+}
+
+/// Matching statistic of a suffix `t[i..]` of a string `t`
+#[derive(Debug)]
+pub struct MatchingStatistic<'arena, 's, C: CharT> {
+    /// Length of the longest prefix of `t[i..]` that is a substring of the string in the trie
+    pub length: usize,
+    /// The highest node with the longest prefix as a prefix of its path. The terminals below
+    /// it are the occurrences of the longest prefix
+    pub locus: &'arena RefCell<Node<'arena, 's, C, C::AlphabetSize>>,
+    /// Length of the path to `locus`
+    pub locus_depth: usize,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct MaximalSubstrMatch {
     pub index: usize,
@@ -739,8 +864,8 @@ mod test {
     use super::*;
 
     use crate::string;
-    use crate::string_model::arb_astring;
     use crate::string_model::test_util::Char;
+    use crate::string_model::{AString, arb_astring};
 
     use proptest::prelude::ProptestConfig;
     use proptest::{prop_assert_eq, proptest};
@@ -808,6 +933,21 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_contains_substr() {
+        use crate::string_model::test_util::Char::*;
+
+        let s = AStr::from_slice(&[A, B, A, A, B]);
+
+        let bump = Bump::new();
+        let trie = build_trie_with_allocator(s, &bump);
+
+        assert!(trie.contains_substr(AStr::from_slice(&[])));
+        assert!(trie.contains_substr(AStr::from_slice(&[B, A, A])));
+        assert!(!trie.contains_substr(AStr::from_slice(&[B, B])));
+        assert!(!trie.contains_substr(AStr::from_slice(&[A, A, B, A])));
+    }
+
     #[test]
     fn test_find_maximal_substr() {
         use crate::string_model::test_util::Char::*;
@@ -831,9 +971,55 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_matching_statistics() {
+        use crate::string_model::test_util::Char::*;
+
+        let s = AStr::from_slice(&[A, B, A, A, B, B]);
+        let t = AStr::from_slice(&[B, A, A, B, A, B, B, B]);
+        let bump = Bump::new();
+        let trie = build_trie_with_allocator(s, &bump);
+
+        let lengths = trie
+            .matching_statistics(t)
+            .map(|statistic| statistic.length)
+            .collect::<Vec<_>>();
+        assert_eq!(lengths, vec![4, 3, 3, 2, 3, 2, 2, 1]);
+    }
+
+    #[test]
+    fn test_matching_statistics_periodic() {
+        use crate::string_model::test_util::Char::*;
+
+        // the matches end at leaves, with as many edges above them as chars / 2
+        let s = AString::from([A, B].repeat(25_000));
+        let t = AString::from([A, B].repeat(50_000));
+        let bump = Bump::new();
+        let trie = build_trie_with_allocator(&s, &bump);
+
+        for (i, statistic) in trie.matching_statistics(&t).enumerate() {
+            assert_eq!(statistic.length, (s.len() - i % 2).min(t.len() - i));
+        }
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(2000))]
 
+        #[test]
+        fn prop_test_matching_statistics(s in arb_astring::<Char>(0..20), t in arb_astring::<Char>(0..20)) {
+            let bump = Bump::new();
+            let trie = build_trie_with_allocator(&s, &bump);
+            for (i, statistic) in trie.matching_statistics(&t).enumerate() {
+                let expected = trie.index_substr_maximal(&t[i..]).length;
+                prop_assert_eq!(statistic.length, expected);
+                let mut occurrences = HashSet::new();
+                terminals(&statistic.locus.borrow(), |suffix| {
+                    occurrences.insert(suffix);
+                });
+                prop_assert_eq!(occurrences, trie.indexes_substr(&t[i..i + expected]));
+            }
+        }
+
         #[test]
         fn prop_test_trie(s in arb_astring::<Char>(0..20), t in arb_astring::<Char>(3)) {
             let bump = Bump::new();
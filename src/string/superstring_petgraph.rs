@@ -1,5 +1,5 @@
 use crate::alphabet_model::CharT;
-use crate::string::overlap;
+use crate::string::suffix_prefix_overlap::longest_overlaps;
 use crate::string_model::AString;
 use itertools::Itertools;
 use petgraph::Direction;
@@ -21,25 +21,26 @@ pub fn scs<C: CharT + Debug>(
     strs: impl IntoIterator<Item = AString<C>> + Clone,
     min_overlap: usize,
 ) -> Vec<AString<C>> {
-    let strs = strs.into_iter();
-    let mut graph: DiMatrix<Node<C>, Edge> = MatrixGraph::with_capacity(strs.size_hint().0);
+    let strs = strs.into_iter().collect_vec();
+    let overlaps = longest_overlaps(&strs, min_overlap);
+    let mut graph: DiMatrix<Node<C>, Edge> = MatrixGraph::with_capacity(strs.len());
 
     let node_idxs = strs
         .into_iter()
         .map(|str| graph.add_node(Node::new(str)))
         .collect_vec();
 
-    let edges = node_idxs.into_iter().permutations(2).map(|edge_nodes| {
-        let [node1_idx, node2_idx] = edge_nodes.into_iter().collect_array().expect("length 2");
-
-        let node1 = graph.node_weight(node1_idx);
-        let node2 = graph.node_weight(node2_idx);
-
-        let overlap = overlap(&node1.str, &node2.str);
-        graph.add_edge(node1_idx, node2_idx, Edge::new(overlap));
-
-        EdgeHeapEntry::new(node1_idx, node2_idx, overlap)
-    });
+    let edges = overlaps
+        .into_iter()
+        .enumerate()
+        .flat_map(|(source, overlaps)| overlaps.into_iter().map(move |overlap| (source, overlap)))
+        .map(|(source, overlap)| {
+            let node1_idx = node_idxs[source];
+            let node2_idx = node_idxs[overlap.target];
+            graph.add_edge(node1_idx, node2_idx, Edge::new(overlap.len));
+
+            EdgeHeapEntry::new(node1_idx, node2_idx, overlap.len)
+        });
 
     let mut edge_heap: BinaryHeap<_> = edges.collect();
 
@@ -1,5 +1,5 @@
 use crate::alphabet_model::CharT;
-use crate::string::overlap;
+use crate::string::suffix_prefix_overlap::longest_overlaps;
 use crate::string_model::AString;
 use itertools::Itertools;
 use std::cell::RefCell;
@@ -17,29 +17,35 @@ pub fn scs<C: CharT + Debug>(
     strs: impl IntoIterator<Item = AString<C>> + Clone,
     min_overlap: usize,
 ) -> Vec<AString<C>> {
+    let strs = strs.into_iter().collect_vec();
+    let overlaps = longest_overlaps(&strs, min_overlap);
     let mut nodes = strs
         .into_iter()
         .map(|str| Rc::new(RefCell::new(Node::new(str))))
         .collect_vec();
 
-    let edges = nodes.iter().permutations(2).map(|edge_nodes| {
-        let [node1, node2] = edge_nodes.into_iter().collect_array().expect("length 2");
-
-        let mut node1_mut = node1.borrow_mut();
-        let mut node2_mut = node2.borrow_mut();
-
-        let overlap = overlap(&node1_mut.str, &node2_mut.str);
-        let edge = Rc::new(RefCell::new(Edge::new(
-            node1.clone(),
-            node2.clone(),
-            overlap,
-        )));
-
-        node1_mut.outgoing.push(edge.clone());
-        node2_mut.incoming.push(edge.clone());
-
-        edge
-    });
+    let edges = overlaps
+        .into_iter()
+        .enumerate()
+        .flat_map(|(source, overlaps)| overlaps.into_iter().map(move |overlap| (source, overlap)))
+        .map(|(source, overlap)| {
+            let node1 = &nodes[source];
+            let node2 = &nodes[overlap.target];
+
+            let mut node1_mut = node1.borrow_mut();
+            let mut node2_mut = node2.borrow_mut();
+
+            let edge = Rc::new(RefCell::new(Edge::new(
+                node1.clone(),
+                node2.clone(),
+                overlap.len,
+            )));
+
+            node1_mut.outgoing.push(edge.clone());
+            node2_mut.incoming.push(edge.clone());
+
+            edge
+        });
 
     let mut edge_heap: BinaryHeap<_> = edges.map(HeapEntry::new).collect();
 
@@ -0,0 +1,358 @@
+//! Substrings occurring once in a string and strings absent from it, found in the suffix
+//! tree, e.g. for designing probes that bind only at one position and for finding signatures
+//! of a genome among others
+
+use crate::alphabet_model::CharT;
+use crate::string::suffix_trie_mcc_arena::{Node, SuffixTrie, build_trie_with_allocator};
+use crate::string_model::{AStr, AString};
+use bumpalo::Bump;
+use generic_array::typenum::Unsigned;
+use hashbrown::HashSet;
+use itertools::Itertools;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::Range;
+
+/// Suffix trie node with the string of the path to it
+type NodeWithPath<'arena, 's, C> = (
+    &'arena RefCell<Node<'arena, 's, C, <C as CharT>::AlphabetSize>>,
+    Vec<C>,
+);
+
+/// For each start position, the length of the shortest substring starting there that occurs
+/// once in the string, if any. A suffix has unique prefixes only if it ends in a leaf, and the
+/// shortest one is one char longer than the string of the parent node
+fn minimal_unique_lengths<C: CharT>(
+    trie: &SuffixTrie<'_, '_, C>,
+    len: usize,
+) -> Vec<Option<usize>> {
+    let mut lengths = vec![None; len];
+    let mut to_visit = vec![(trie.root, 0)];
+    while let Some((node, depth)) = to_visit.pop() {
+        let node_ref = node.borrow();
+        for edge in node_ref.children.iter().flatten() {
+            let edge_ref = edge.borrow();
+            let target_ref = edge_ref.target.borrow();
+            if let Some(terminal) = &target_ref.terminal
+                && target_ref.children.iter().all(|child| child.is_none())
+            {
+                lengths[terminal.suffix_index] = Some(depth + 1);
+            }
+            to_visit.push((edge_ref.target, depth + edge_ref.chars.len()));
+        }
+    }
+    lengths
+}
+
+/// For each position of `s`, the leftmost of the shortest substrings covering the position
+/// that occur once in `s`
+pub fn shortest_unique_substrings<C: CharT>(s: &AStr<C>) -> Vec<Range<usize>> {
+    let bump = Bump::new();
+    let trie = build_trie_with_allocator(s, &bump);
+    let lengths = minimal_unique_lengths(&trie, s.len());
+
+    // starts of the minimal unique substrings by end position
+    let mut ending = vec![Vec::new(); s.len() + 1];
+    for (start, len) in lengths.iter().enumerate() {
+        if let Some(len) = len {
+            ending[start + len].push(start);
+        }
+    }
+
+    // smallest end of the unique substrings starting after each position
+    let mut next_end = vec![None; s.len() + 1];
+    for start in (0..s.len()).rev() {
+        next_end[start] = next_end[start + 1];
+        if let Some(len) = lengths[start] {
+            next_end[start] =
+                Some(next_end[start].map_or(start + len, |end: usize| end.min(start + len)));
+        }
+    }
+
+    let mut shortest = Vec::with_capacity(s.len());
+    // minimal unique substrings covering the position, shortest and then leftmost on top
+    let mut covering = BinaryHeap::new();
+    // start of the last minimal unique substring ending before or at the position
+    let mut last_ended = None;
+    for pos in 0..s.len() {
+        if let Some(len) = lengths[pos] {
+            covering.push(Reverse((len, pos)));
+        }
+        for &start in &ending[pos] {
+            last_ended = last_ended.max(Some(start));
+        }
+        while let Some(&Reverse((len, start))) = covering.peek()
+            && start + len <= pos
+        {
+            covering.pop();
+        }
+
+        // a minimal unique substring before or after the position is extended to cover it
+        let extended_right = last_ended.map(|start| start..pos + 1);
+        let extended_left = next_end[pos + 1].map(|end| pos..end);
+        let covered = covering
+            .peek()
+            .map(|&Reverse((len, start))| start..start + len);
+        shortest.push(
+            extended_right
+                .into_iter()
+                .chain(extended_left)
+                .chain(covered)
+                .min_by_key(|range| (range.len(), range.start))
+                .expect("whole string is unique"),
+        );
+    }
+    shortest
+}
+
+/// Explicit nodes with string of length at most `max_depth`, with their string
+fn collect_nodes<'arena, 's, C: CharT>(
+    node: &'arena RefCell<Node<'arena, 's, C, C::AlphabetSize>>,
+    path: &mut Vec<C>,
+    max_depth: usize,
+    nodes: &mut Vec<NodeWithPath<'arena, 's, C>>,
+) {
+    nodes.push((node, path.clone()));
+    for edge in node.borrow().children.iter().flatten() {
+        let edge_ref = edge.borrow();
+        if path.len() + edge_ref.chars.len() <= max_depth {
+            path.extend(edge_ref.chars.iter().copied());
+            collect_nodes(edge_ref.target, path, max_depth, nodes);
+            path.truncate(path.len() - edge_ref.chars.len());
+        }
+    }
+}
+
+/// Minimal absent words of `s` of length at most `max_len`, strings not in `s` with all
+/// proper substrings in `s`, ordered by length and then by char indexes. Each is `a u b` with
+/// chars `a` and `b` and `u` the string of an explicit node of the suffix tree, since for
+/// other `u` every occurrence is followed by the same char
+pub fn minimal_absent_words<C: CharT>(s: &AStr<C>, max_len: usize) -> Vec<AString<C>> {
+    let bump = Bump::new();
+    let trie = build_trie_with_allocator(s, &bump);
+    let alphabet = (0..C::AlphabetSize::USIZE).map(C::from_index).collect_vec();
+
+    let mut words = Vec::new();
+    if max_len >= 1 {
+        words.extend(
+            alphabet
+                .iter()
+                .filter(|&&ch| !trie.contains_substr(AStr::from_slice(&[ch])))
+                .map(|&ch| vec![ch]),
+        );
+    }
+    if max_len >= 2 {
+        let mut nodes = Vec::new();
+        collect_nodes(trie.root, &mut Vec::new(), max_len - 2, &mut nodes);
+        for (node, u) in nodes {
+            let right = node
+                .borrow()
+                .children
+                .iter()
+                .flatten()
+                .map(|edge| edge.borrow().chars[0])
+                .collect_vec();
+            for &a in &alphabet {
+                let mut word = Vec::with_capacity(u.len() + 2);
+                word.push(a);
+                word.extend_from_slice(&u);
+                if !trie.contains_substr(AStr::from_slice(&word)) {
+                    continue;
+                }
+                for &b in &right {
+                    word.push(b);
+                    if !trie.contains_substr(AStr::from_slice(&word)) {
+                        words.push(word.clone());
+                    }
+                    word.pop();
+                }
+            }
+        }
+    }
+
+    words
+        .into_iter()
+        .sorted_by_key(|word| (word.len(), word.iter().map(|ch| ch.index()).collect_vec()))
+        .map(AString::from)
+        .collect()
+}
+
+/// For each genome, the shortest substrings absent from all the other genomes, as the range
+/// of their first occurrence. The shortest substring starting at a position and absent from
+/// another genome is one char longer than the matching statistic there
+pub fn shortest_absent_from_others<C: CharT>(genomes: &[&AStr<C>]) -> Vec<Vec<Range<usize>>> {
+    let bump = Bump::new();
+    let tries = genomes
+        .iter()
+        .map(|genome| build_trie_with_allocator(genome, &bump))
+        .collect_vec();
+
+    genomes
+        .iter()
+        .enumerate()
+        .map(|(index, genome)| {
+            let mut lengths = vec![1; genome.len()];
+            for (other_index, trie) in tries.iter().enumerate() {
+                if other_index == index {
+                    continue;
+                }
+                for (start, statistic) in trie.matching_statistics(genome).enumerate() {
+                    lengths[start] = lengths[start].max(statistic.length + 1);
+                }
+            }
+
+            let Some(shortest) = lengths
+                .iter()
+                .enumerate()
+                .filter(|&(start, &len)| start + len <= genome.len())
+                .map(|(_, &len)| len)
+                .min()
+            else {
+                return Vec::new();
+            };
+            let mut seen = HashSet::new();
+            (0..genome.len())
+                .filter(|&start| lengths[start] == shortest && start + shortest <= genome.len())
+                .map(|start| start..start + shortest)
+                .filter(|range| {
+                    seen.insert(
+                        genome[range.clone()]
+                            .iter()
+                            .map(|ch| ch.index())
+                            .collect_vec(),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string;
+    use crate::string_model::arb_astring;
+    use crate::string_model::test_util::Char;
+    use proptest::collection::vec;
+    use proptest::prelude::ProptestConfig;
+    use proptest::{prop_assert_eq, proptest};
+
+    fn all_strings<C: CharT>(max_len: usize) -> Vec<Vec<C>> {
+        let mut strings = vec![Vec::new()];
+        let mut current = vec![Vec::new()];
+        for _ in 0..max_len {
+            current = current
+                .iter()
+                .flat_map(|s: &Vec<C>| {
+                    (0..C::AlphabetSize::USIZE).map(move |index| {
+                        let mut s = s.clone();
+                        s.push(C::from_index(index));
+                        s
+                    })
+                })
+                .collect();
+            strings.extend(current.iter().cloned());
+        }
+        strings
+    }
+
+    fn count<C: CharT>(s: &AStr<C>, t: &[C]) -> usize {
+        string::indexes(s, AStr::from_slice(t)).len()
+    }
+
+    #[test]
+    fn test_shortest_unique_substrings() {
+        let s = ascii("abcabcab");
+        let shortest = shortest_unique_substrings(s);
+        // "cabc" is the shortest unique substring, and the only one of length 4
+        assert_eq!(shortest[0], 0..6);
+        assert_eq!(shortest[3], 2..6);
+        assert_eq!(shortest[7], 2..8);
+        assert_eq!(
+            shortest_unique_substrings(ascii("aab")),
+            vec![0..2, 0..2, 2..3]
+        );
+    }
+
+    #[test]
+    fn test_minimal_absent_words() {
+        let words = minimal_absent_words(ascii("abaab"), 4)
+            .into_iter()
+            .map(|word| word.to_string())
+            .filter(|word| word.chars().all(|ch| ch == 'a' || ch == 'b'))
+            .collect_vec();
+        assert_eq!(words, vec!["bb", "aaa", "bab", "aaba"]);
+    }
+
+    #[test]
+    fn test_shortest_absent_from_others() {
+        let genomes = [ascii("aacgt"), ascii("acgtt"), ascii("acgt")];
+        let shortest = shortest_absent_from_others(&genomes);
+        assert_eq!(shortest[0], vec![0..2]);
+        assert_eq!(shortest[1], vec![3..5]);
+        assert_eq!(shortest[2], Vec::<Range<usize>>::new());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn prop_test_shortest_unique_substrings(s in arb_astring::<Char>(1..20)) {
+            let expected = (0..s.len())
+                .map(|pos| {
+                    (0..=pos)
+                        .flat_map(|start| (pos + 1..=s.len()).map(move |end| start..end))
+                        .filter(|range| count(&s, &s[range.clone()]) == 1)
+                        .min_by_key(|range| (range.len(), range.start))
+                        .unwrap()
+                })
+                .collect_vec();
+            prop_assert_eq!(shortest_unique_substrings(&s), expected);
+        }
+
+        #[test]
+        fn prop_test_minimal_absent_words(s in arb_astring::<Char>(0..20), max_len in 0..6usize) {
+            let expected = all_strings::<Char>(max_len)
+                .into_iter()
+                .filter(|word| !word.is_empty())
+                .filter(|word| {
+                    count(&s, word) == 0
+                        && count(&s, &word[1..]) > 0
+                        && count(&s, &word[..word.len() - 1]) > 0
+                })
+                .map(AString::from)
+                .collect_vec();
+            prop_assert_eq!(minimal_absent_words(&s, max_len), expected);
+        }
+
+        #[test]
+        fn prop_test_shortest_absent_from_others(genomes in vec(arb_astring::<Char>(0..12), 1..4)) {
+            let genomes = genomes.iter().map(|genome| genome.as_ref()).collect_vec();
+            let shortest = shortest_absent_from_others(&genomes);
+            for (index, genome) in genomes.iter().enumerate() {
+                let absent = |t: &[Char]| {
+                    genomes
+                        .iter()
+                        .enumerate()
+                        .all(|(other, other_genome)| other == index || count(other_genome, t) == 0)
+                };
+                let expected_len = (1..=genome.len()).find(|&len| {
+                    genome.windows(len).any(absent)
+                });
+                match expected_len {
+                    None => prop_assert_eq!(&shortest[index], &Vec::<Range<usize>>::new()),
+                    Some(len) => {
+                        let expected = (0..=genome.len() - len)
+                            .map(|start| start..start + len)
+                            .filter(|range| absent(&genome[range.clone()]))
+                            .unique_by(|range| genome[range.clone()].to_vec())
+                            .collect_vec();
+                        prop_assert_eq!(&shortest[index], &expected);
+                    }
+                }
+            }
+        }
+    }
+}
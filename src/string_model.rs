@@ -71,6 +71,12 @@ impl<C> DerefMut for AStr<C> {
     }
 }
 
+impl<C> AsRef<AStr<C>> for AStr<C> {
+    fn as_ref(&self) -> &AStr<C> {
+        self
+    }
+}
+
 impl<C> AsRef<[C]> for AStr<C> {
     fn as_ref(&self) -> &[C] {
         &self.0
@@ -1,4 +1,24 @@
+use crate::alphabet_model::CharT;
 use crate::enum_char;
+use crate::polymers::DnaNt;
+use crate::string_model::{AStr, AString};
+use rand::Rng;
 use std::fmt::{Debug, Formatter};
 
 enum_char!(Char; A, B);
+
+/// Random DNA of length `len`
+pub fn random_dna(rng: &mut impl Rng, len: usize) -> AString<DnaNt> {
+    (0..len)
+        .map(|_| DnaNt::from_index(rng.random_range(0..DnaNt::all().len())))
+        .collect()
+}
+
+/// Reads of length `read_len` starting every `step` chars and covering `genome`
+pub fn tiled_reads(genome: &AStr<DnaNt>, read_len: usize, step: usize) -> Vec<AString<DnaNt>> {
+    (0..genome.len() - read_len)
+        .step_by(step)
+        .chain([genome.len() - read_len])
+        .map(|start| genome[start..start + read_len].to_owned())
+        .collect()
+}
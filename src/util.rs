@@ -1,11 +1,14 @@
 pub mod alloc;
+pub mod sam;
 
 use crate::alphabet_model::CharT;
 use crate::string_model::AString;
 use hdrhistogram::{Counter, Histogram};
+use itertools::Itertools;
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
+use std::iter;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -96,6 +99,139 @@ fn fasta_polymers_lines<C: CharT, S: AsRef<str>>(
     res.into_iter()
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct FastqEntry<C: CharT> {
+    pub description: String,
+    pub polymer: AString<C>,
+    /// Phred quality scores
+    pub quality: Vec<u8>,
+}
+
+const PHRED_OFFSET: u8 = 33;
+
+/// Highest Phred quality score with a printable char (`~`)
+const MAX_PHRED: u8 = 93;
+
+/// Char for Phred quality score, with higher scores written as `MAX_PHRED`
+fn phred_char(q: u8) -> char {
+    (q.min(MAX_PHRED) + PHRED_OFFSET) as char
+}
+
+impl<C: CharT> FastqEntry<C> {
+    pub fn quality_str(&self) -> String {
+        self.quality.iter().map(|&q| phred_char(q)).collect()
+    }
+
+    pub fn write(&self, write: &mut impl Write) {
+        writeln!(write, "@{}", self.description).unwrap();
+        writeln!(write, "{}", self.polymer).unwrap();
+        writeln!(write, "+").unwrap();
+        writeln!(write, "{}", self.quality_str()).unwrap();
+    }
+}
+
+impl<C: CharT> From<FastqEntry<C>> for FastaEntry<C> {
+    fn from(value: FastqEntry<C>) -> Self {
+        Self {
+            description: value.description,
+            polymer: value.polymer,
+        }
+    }
+}
+
+/// Sequence with description, and possibly base qualities, read from FASTA or FASTQ
+pub trait SequenceEntry<C: CharT> {
+    fn description(&self) -> &str;
+
+    fn polymer(&self) -> &AString<C>;
+
+    fn polymer_mut(&mut self) -> &mut AString<C>;
+
+    /// Phred quality scores, if known
+    fn quality(&self) -> Option<&[u8]>;
+}
+
+impl<C: CharT> SequenceEntry<C> for FastaEntry<C> {
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn polymer(&self) -> &AString<C> {
+        &self.polymer
+    }
+
+    fn polymer_mut(&mut self) -> &mut AString<C> {
+        &mut self.polymer
+    }
+
+    fn quality(&self) -> Option<&[u8]> {
+        None
+    }
+}
+
+impl<C: CharT> SequenceEntry<C> for FastqEntry<C> {
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn polymer(&self) -> &AString<C> {
+        &self.polymer
+    }
+
+    fn polymer_mut(&mut self) -> &mut AString<C> {
+        &mut self.polymer
+    }
+
+    fn quality(&self) -> Option<&[u8]> {
+        Some(&self.quality)
+    }
+}
+
+pub fn fastq_entries_file<C: CharT>(path: impl AsRef<Path>) -> impl Iterator<Item = FastqEntry<C>> {
+    fastq_entries_lines(lines_file(path))
+}
+
+pub fn fastq_entries<C: CharT>(data: &str) -> impl Iterator<Item = FastqEntry<C>> {
+    fastq_entries_lines(data.lines())
+}
+
+/// Parses records of four lines. Blank lines are skipped between records only, since the
+/// sequence and quality lines of an empty read are blank
+fn fastq_entries_lines<C: CharT, S: AsRef<str>>(
+    mut lines: impl Iterator<Item = S>,
+) -> impl Iterator<Item = FastqEntry<C>> {
+    iter::from_fn(move || {
+        let descr = lines.find(|line| !line.as_ref().trim().is_empty())?;
+        let mut next_line = || lines.next().expect("invalid format: truncated record");
+        let polymer = next_line();
+        let separator = next_line();
+        let quality = next_line();
+
+        let description = descr
+            .as_ref()
+            .strip_prefix("@")
+            .expect("invalid format")
+            .to_string();
+        assert!(separator.as_ref().starts_with('+'), "invalid format");
+        let polymer = AString::from_str(polymer.as_ref().trim()).unwrap();
+        let quality = quality
+            .as_ref()
+            .trim()
+            .bytes()
+            .map(|q| {
+                q.checked_sub(PHRED_OFFSET)
+                    .expect("invalid format: quality char below '!'")
+            })
+            .collect_vec();
+        assert_eq!(polymer.len(), quality.len(), "invalid format");
+        Some(FastqEntry {
+            description,
+            polymer,
+            quality,
+        })
+    })
+}
+
 pub fn print_histogram<T: Counter>(label: &str, hist: &Histogram<T>) {
     println!(
         "{}: mean={}, max= {}, q0.05={}, q0.25={},  q0.50={} q0.75={} q0.95={}",
@@ -109,3 +245,46 @@ pub fn print_histogram<T: Counter>(label: &str, hist: &Histogram<T>) {
         hist.value_at_quantile(0.95)
     );
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::polymers::DnaNt;
+
+    #[test]
+    fn test_fastq_entries() {
+        let data = "@read1 x\nACGT\n+\nII#I\n@read2\nTT\n+read2\n!!\n";
+        let entries = fastq_entries::<DnaNt>(data).collect_vec();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].description, "read1 x");
+        assert_eq!(entries[0].polymer.to_string(), "ACGT");
+        assert_eq!(entries[0].quality, vec![40, 40, 2, 40]);
+        assert_eq!(entries[0].quality_str(), "II#I");
+        assert_eq!(entries[1].quality, vec![0, 0]);
+
+        let mut written = Vec::new();
+        for entry in &entries {
+            entry.write(&mut written);
+        }
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "@read1 x\nACGT\n+\nII#I\n@read2\nTT\n+\n!!\n"
+        );
+    }
+    #[test]
+    fn test_fastq_entries_empty_read() {
+        let data = "@read1\n\n+\n\n\n@read2\nAC\n+\nII\n";
+        let entries = fastq_entries::<DnaNt>(data).collect_vec();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].polymer.is_empty());
+        assert!(entries[0].quality.is_empty());
+        assert_eq!(entries[1].description, "read2");
+        assert_eq!(entries[1].polymer.to_string(), "AC");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid format")]
+    fn test_fastq_entries_invalid_quality() {
+        fastq_entries::<DnaNt>("@read1\nAC\n+\n\x1fI\n").for_each(drop);
+    }
+}
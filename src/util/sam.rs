@@ -0,0 +1,498 @@
+//! SAM text format for aligned and mapped sequences
+
+use crate::alphabet_model::CharT;
+use crate::string::alignment::{GlobalAlignment, LocalAlignment, cigar, edit_distance};
+use crate::string_model::AStr;
+use crate::util::{PHRED_OFFSET, lines_file, phred_char};
+use itertools::Itertools;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const FLAG_UNMAPPED: u16 = 0x4;
+pub const FLAG_REVERSE: u16 = 0x10;
+pub const FLAG_SECONDARY: u16 = 0x100;
+
+const SAM_VERSION: &str = "1.6";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sam {
+    pub header: SamHeader,
+    pub records: Vec<SamRecord>,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SamHeader {
+    /// `@SQ` lines
+    pub references: Vec<SamReference>,
+    /// `@PG` lines
+    pub programs: Vec<SamProgram>,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SamReference {
+    pub name: String,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SamProgram {
+    pub id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub command_line: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SamRecord {
+    pub qname: String,
+    pub flag: u16,
+    /// Reference name, `None` is written as `*`
+    pub rname: Option<String>,
+    /// 1-based position of first aligned base, 0 if unavailable
+    pub pos: usize,
+    pub mapq: u8,
+    /// `None` is written as `*`
+    pub cigar: Option<String>,
+    pub rnext: Option<String>,
+    pub pnext: usize,
+    pub tlen: isize,
+    /// `None` is written as `*`
+    pub seq: Option<String>,
+    /// Phred quality scores, `None` is written as `*`
+    pub qual: Option<Vec<u8>>,
+    pub tags: Vec<SamTag>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamTag {
+    pub tag: String,
+    pub value: SamTagValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SamTagValue {
+    Char(char),
+    Int(i64),
+    Float(f32),
+    String(String),
+}
+
+impl SamTag {
+    pub fn new(tag: &str, value: SamTagValue) -> Self {
+        Self {
+            tag: tag.to_string(),
+            value,
+        }
+    }
+
+    /// Edit distance to the reference
+    pub fn nm(edit_distance: usize) -> Self {
+        Self::new("NM", SamTagValue::Int(edit_distance as i64))
+    }
+
+    /// Alignment score
+    pub fn alignment_score(score: isize) -> Self {
+        Self::new("AS", SamTagValue::Int(score as i64))
+    }
+}
+
+impl SamRecord {
+    pub fn is_unmapped(&self) -> bool {
+        self.flag & FLAG_UNMAPPED != 0
+    }
+
+    pub fn is_reverse(&self) -> bool {
+        self.flag & FLAG_REVERSE != 0
+    }
+
+    pub fn tag(&self, tag: &str) -> Option<&SamTagValue> {
+        self.tags
+            .iter()
+            .find(|sam_tag| sam_tag.tag == tag)
+            .map(|sam_tag| &sam_tag.value)
+    }
+
+    /// Unmapped record for sequence
+    pub fn unmapped<C: CharT>(qname: &str, seq: &AStr<C>) -> Self {
+        Self {
+            qname: qname.to_string(),
+            flag: FLAG_UNMAPPED,
+            seq: Some(seq.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Record for local alignment of `y` inside reference `rname`
+    pub fn from_local_alignment<C: CharT>(
+        qname: &str,
+        rname: &str,
+        y: &AStr<C>,
+        align: &LocalAlignment,
+    ) -> Self {
+        Self {
+            qname: qname.to_string(),
+            rname: Some(rname.to_string()),
+            pos: align.range.start + 1,
+            mapq: 255,
+            cigar: Some(cigar(&align.edits)),
+            seq: Some(y.to_string()),
+            tags: vec![
+                SamTag::nm(edit_distance(&align.edits)),
+                SamTag::alignment_score(-align.penalty),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// Record for global alignment of `y` to reference `rname`
+    pub fn from_global_alignment<C: CharT>(
+        qname: &str,
+        rname: &str,
+        y: &AStr<C>,
+        align: &GlobalAlignment,
+    ) -> Self {
+        Self {
+            qname: qname.to_string(),
+            rname: Some(rname.to_string()),
+            pos: 1,
+            mapq: 255,
+            cigar: Some(cigar(&align.edits)),
+            seq: Some(y.to_string()),
+            tags: vec![
+                SamTag::nm(edit_distance(&align.edits)),
+                SamTag::alignment_score(-(align.penalty as isize)),
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+impl Sam {
+    pub fn write(&self, write: &mut impl Write) {
+        self.header.write(write);
+        for record in &self.records {
+            record.write(write);
+        }
+    }
+}
+
+impl SamHeader {
+    pub fn write(&self, write: &mut impl Write) {
+        writeln!(write, "@HD\tVN:{}\tSO:unsorted", SAM_VERSION).unwrap();
+        for reference in &self.references {
+            writeln!(write, "@SQ\tSN:{}\tLN:{}", reference.name, reference.len).unwrap();
+        }
+        for program in &self.programs {
+            write!(write, "@PG\tID:{}", program.id).unwrap();
+            let fields = [
+                ("PN", &program.name),
+                ("VN", &program.version),
+                ("CL", &program.command_line),
+            ];
+            for (tag, value) in fields {
+                if let Some(value) = value {
+                    write!(write, "\t{}:{}", tag, value).unwrap();
+                }
+            }
+            writeln!(write).unwrap();
+        }
+    }
+}
+
+impl SamRecord {
+    pub fn write(&self, write: &mut impl Write) {
+        writeln!(write, "{}", self).unwrap();
+    }
+}
+
+fn or_star(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("*")
+}
+
+impl Display for SamRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let qual = self.qual.as_ref().map_or("*".to_string(), |qual| {
+            qual.iter().map(|&q| phred_char(q)).collect()
+        });
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.qname,
+            self.flag,
+            or_star(&self.rname),
+            self.pos,
+            self.mapq,
+            or_star(&self.cigar),
+            or_star(&self.rnext),
+            self.pnext,
+            self.tlen,
+            or_star(&self.seq),
+            qual
+        )?;
+        for tag in &self.tags {
+            write!(f, "\t{}", tag)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SamTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            SamTagValue::Char(value) => write!(f, "{}:A:{}", self.tag, value),
+            SamTagValue::Int(value) => write!(f, "{}:i:{}", self.tag, value),
+            SamTagValue::Float(value) => write!(f, "{}:f:{}", self.tag, value),
+            SamTagValue::String(value) => write!(f, "{}:Z:{}", self.tag, value),
+        }
+    }
+}
+
+fn parse_field<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {}: {}", name, value))
+}
+
+fn star_or(value: &str) -> Option<String> {
+    (value != "*").then(|| value.to_string())
+}
+
+impl FromStr for SamTag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tag, tag_type, value) = s
+            .splitn(3, ':')
+            .collect_tuple()
+            .ok_or_else(|| format!("invalid tag: {}", s))?;
+        let value = match tag_type {
+            "A" => SamTagValue::Char(parse_field("char tag", value)?),
+            "i" => SamTagValue::Int(parse_field("int tag", value)?),
+            "f" => SamTagValue::Float(parse_field("float tag", value)?),
+            "Z" => SamTagValue::String(value.to_string()),
+            _ => return Err(format!("unsupported tag type: {}", s)),
+        };
+        Ok(Self::new(tag, value))
+    }
+}
+
+impl FromStr for SamRecord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split('\t').collect_vec();
+        if fields.len() < 11 {
+            return Err(format!("expected 11 mandatory fields: {}", s));
+        }
+
+        Ok(Self {
+            qname: fields[0].to_string(),
+            flag: parse_field("flag", fields[1])?,
+            rname: star_or(fields[2]),
+            pos: parse_field("pos", fields[3])?,
+            mapq: parse_field("mapq", fields[4])?,
+            cigar: star_or(fields[5]),
+            rnext: star_or(fields[6]),
+            pnext: parse_field("pnext", fields[7])?,
+            tlen: parse_field("tlen", fields[8])?,
+            seq: star_or(fields[9]),
+            qual: star_or(fields[10])
+                .map(|qual| {
+                    qual.bytes()
+                        .map(|q| {
+                            q.checked_sub(PHRED_OFFSET)
+                                .ok_or_else(|| format!("invalid quality char: {}", q as char))
+                        })
+                        .collect::<Result<_, _>>()
+                })
+                .transpose()?,
+            tags: fields[11..]
+                .iter()
+                .map(|tag| tag.parse())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl SamHeader {
+    /// Parses header line into header. Line types other than `@SQ` and `@PG` are ignored
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let mut fields = line.split('\t');
+        let line_type = fields.next().unwrap_or_default();
+        let fields: Vec<(&str, &str)> = fields
+            .map(|field| {
+                field
+                    .split_once(':')
+                    .ok_or_else(|| format!("invalid header field: {}", field))
+            })
+            .collect::<Result<_, _>>()?;
+        let field = |tag: &str| {
+            fields
+                .iter()
+                .find(|(field_tag, _)| *field_tag == tag)
+                .map(|(_, value)| value.to_string())
+        };
+
+        match line_type {
+            "@SQ" => self.references.push(SamReference {
+                name: field("SN").ok_or_else(|| format!("missing SN: {}", line))?,
+                len: parse_field(
+                    "LN",
+                    &field("LN").ok_or_else(|| format!("missing LN: {}", line))?,
+                )?,
+            }),
+            "@PG" => self.programs.push(SamProgram {
+                id: field("ID").ok_or_else(|| format!("missing ID: {}", line))?,
+                name: field("PN"),
+                version: field("VN"),
+                command_line: field("CL"),
+            }),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Sam {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_sam_lines(s.lines())
+    }
+}
+
+pub fn sam_file(path: impl AsRef<Path>) -> Sam {
+    parse_sam_lines(lines_file(path)).unwrap()
+}
+
+fn parse_sam_lines<S: AsRef<str>>(lines: impl Iterator<Item = S>) -> Result<Sam, String> {
+    let mut sam = Sam::default();
+    for line in lines {
+        let line = line.as_ref().trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('@') {
+            sam.header.parse_line(line)?;
+        } else {
+            sam.records.push(line.parse()?);
+        }
+    }
+    Ok(sam)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ascii::ascii;
+    use crate::string::alignment::{AlignmentProperties, local_alignment_wagner_fischer};
+
+    fn sam() -> Sam {
+        Sam {
+            header: SamHeader {
+                references: vec![SamReference {
+                    name: "ref".to_string(),
+                    len: 8,
+                }],
+                programs: vec![SamProgram {
+                    id: "bioinformatics".to_string(),
+                    name: Some("bioinformatics".to_string()),
+                    version: None,
+                    command_line: Some("map reads.fa".to_string()),
+                }],
+            },
+            records: vec![
+                SamRecord {
+                    qname: "r1".to_string(),
+                    flag: FLAG_REVERSE,
+                    rname: Some("ref".to_string()),
+                    pos: 3,
+                    mapq: 60,
+                    cigar: Some("2M1I1M".to_string()),
+                    seq: Some("cdxa".to_string()),
+                    qual: Some(vec![10, 20, 30, 40]),
+                    tags: vec![
+                        SamTag::nm(1),
+                        SamTag::alignment_score(2),
+                        SamTag::new("XC", SamTagValue::Char('c')),
+                        SamTag::new("XF", SamTagValue::Float(0.5)),
+                        SamTag::new("XS", SamTagValue::String("a b".to_string())),
+                    ],
+                    ..Default::default()
+                },
+                SamRecord::unmapped("r2", ascii("abc")),
+            ],
+        }
+    }
+
+    const SAM_TEXT: &str = "@HD\tVN:1.6\tSO:unsorted\n\
+        @SQ\tSN:ref\tLN:8\n\
+        @PG\tID:bioinformatics\tPN:bioinformatics\tCL:map reads.fa\n\
+        r1\t16\tref\t3\t60\t2M1I1M\t*\t0\t0\tcdxa\t+5?I\tNM:i:1\tAS:i:2\tXC:A:c\tXF:f:0.5\tXS:Z:a b\n\
+        r2\t4\t*\t0\t0\t*\t*\t0\t0\tabc\t*\n";
+
+    #[test]
+    fn test_write_sam() {
+        let mut out = Vec::new();
+        sam().write(&mut out);
+        assert_eq!(String::from_utf8(out).unwrap(), SAM_TEXT);
+    }
+
+    #[test]
+    fn test_parse_sam() {
+        let parsed: Sam = SAM_TEXT.parse().unwrap();
+        assert_eq!(parsed, sam());
+        assert!(parsed.records[0].is_reverse());
+        assert!(!parsed.records[0].is_unmapped());
+        assert!(parsed.records[1].is_unmapped());
+        assert_eq!(parsed.records[0].tag("NM"), Some(&SamTagValue::Int(1)));
+        assert_eq!(parsed.records[0].tag("MD"), None);
+    }
+
+    #[test]
+    fn test_parse_sam_invalid() {
+        assert!("r1\t0\tref".parse::<Sam>().is_err());
+        assert!("r1\tx\t*\t0\t0\t*\t*\t0\t0\t*\t*".parse::<Sam>().is_err());
+        assert!(
+            "r1\t0\t*\t0\t0\t*\t*\t0\t0\t*\t*\tNM:B:1"
+                .parse::<Sam>()
+                .is_err()
+        );
+        assert!(
+            "r1\t0\t*\t0\t0\t*\t*\t0\t0\tAC\t\x1fI"
+                .parse::<Sam>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_local_alignment() {
+        let x = ascii("abcdabcd");
+        let y = ascii("cdcbc");
+        let align =
+            local_alignment_wagner_fischer::local_alignment(x, y, AlignmentProperties::default());
+        let record = SamRecord::from_local_alignment("y", "x", y, &align);
+        assert_eq!(
+            record.to_string(),
+            "y\t0\tx\t3\t255\t5M\t*\t0\t0\tcdcbc\t*\tNM:i:1\tAS:i:3"
+        );
+
+        let empty = ascii("");
+        let align = local_alignment_wagner_fischer::local_alignment(
+            x,
+            empty,
+            AlignmentProperties::default(),
+        );
+        let record = SamRecord::from_local_alignment("empty", "x", empty, &align);
+        assert_eq!(record.cigar.as_deref(), Some("*"));
+        assert_eq!(record.to_string().split('\t').nth(5), Some("*"));
+
+        let record = SamRecord {
+            qual: Some(vec![0, 40, 93, 94, 255]),
+            ..record
+        };
+        assert_eq!(record.to_string().split('\t').nth(10), Some("!I~~~"));
+    }
+}